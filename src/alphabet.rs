@@ -0,0 +1,56 @@
+// Wordoku: puzzles written with arbitrary symbols, such as the
+// letters of a word, instead of digits.
+
+use super::{error, num_to_char};
+
+/// The symbols that stand for the digits 1, 2, 3, ... in order.
+pub struct Alphabet {
+    symbols: Vec<char>,
+}
+
+impl Alphabet {
+    /// Create an alphabet of `side` distinct symbols; the dot
+    /// is reserved for blank cells.
+    pub fn new(symbols: &str, side: usize) -> Self {
+        let symbols: Vec<char> = symbols.chars().collect();
+        if symbols.len() != side {
+            error(format!("invalid alphabet length; expected {}, got {}", side, symbols.len()));
+        }
+        for (i, &symbol) in symbols.iter().enumerate() {
+            if symbol == '.' || symbols[.. i].contains(&symbol) {
+                error(format!("invalid alphabet symbol {:?}", symbol));
+            }
+        }
+        return Alphabet { symbols };
+    }
+
+    /// Convert a puzzle written with the symbols to digits, or return
+    /// why it cannot be.
+    pub fn to_digits(&self, text: &str) -> Result<String, String> {
+        text.chars()
+            .map(|c| match self.symbols.iter().position(|&s| s == c) {
+                Some(i) => Ok(num_to_char(i as u32 + 1)),
+                None if c == '.' => Ok(c),
+                None => Err(format!("invalid symbol ({:?}) in string", c)),
+            })
+            .collect()
+    }
+
+    /// Convert a board written with digits to the symbols.
+    pub fn to_symbols(&self, digits: &str) -> String {
+        digits.chars()
+            .map(|c| match c.to_digit(36) {
+                Some(n) if n >= 1 => self.symbols[n as usize - 1],
+                _ => c,
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let alphabet = Alphabet::new("WORDPLAYS", 9);
+    assert_eq!(alphabet.to_digits("W.S.A"), Ok("1.9.7".to_string()));
+    assert!(alphabet.to_digits("W.X").is_err());
+    assert_eq!(alphabet.to_symbols("1.9.7"), "W.S.A");
+}
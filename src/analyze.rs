@@ -0,0 +1,304 @@
+// Analyses of puzzles for setters and for research into puzzles:
+// the givens a puzzle can do without, and the minimal puzzles left
+// by taking some out.  A given needed by a puzzle is needed by every
+// puzzle with fewer givens, which bounds the givens worth trying.
+// The backdoors of a puzzle, the fewest digits of its solution that
+// let singles solve it, measure how hard it is.  The unavoidable sets
+// of a solution grid, sets of cells whose digits can be swapped
+// around into another grid, each need a given of every puzzle with
+// that solution.  Blanking the cells of a few digits and solving
+// again turns up the small ones.
+
+use super::{num_to_char, set_to_num, SudokuBoard};
+use logic::{self, Technique};
+
+/// Largest backdoor looked for.
+const MAX_BACKDOOR: usize = 2;
+
+/// Largest unavoidable set reported.
+const MAX_UNAVOIDABLE: usize = 12;
+
+/// Most grids looked at for each set of digits blanked.
+const MAX_GRIDS: usize = 1000;
+
+/// An analysis of the `analyze` command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Analysis {
+    /// The givens without which the solution stays unique.
+    Redundant,
+    /// The minimal puzzles whose givens are among those of the
+    /// puzzle, at most `limit` of them.
+    Minimal { limit: usize },
+    /// The smallest sets of digits of the solution that let singles
+    /// solve the puzzle, of at most `MAX_BACKDOOR` digits.
+    Backdoors,
+    /// The small minimal unavoidable sets of a solution grid, of at
+    /// most `MAX_UNAVOIDABLE` cells.
+    UnavoidableSets,
+}
+
+/// Return the line of the `analysis` of `board`, a puzzle.
+pub fn run(analysis: Analysis, board: &SudokuBoard) -> String {
+    if board.count_solutions(2) != 1 {
+        return "No unique solution".to_string();
+    }
+    let shape = board.rules.shape;
+    let solution = board.solve().unwrap();
+    let name = |cell: usize| {
+        format!("r{}c{}={}", shape.row(cell) + 1, shape.col(cell) + 1, num_to_char(set_to_num(solution.cells[cell])))
+    };
+    match analysis {
+        Analysis::Redundant => {
+            let givens = (0 .. board.cells.len()).filter(|&c| board.cell_solved(c)).count();
+            let cells: Vec<String> = redundant(board).into_iter().map(name).collect();
+            let mut line = format!("{} of {} givens redundant", cells.len(), givens);
+            if !cells.is_empty() {
+                line += &format!(": {}", cells.join(" "));
+            }
+            return line;
+        }
+        Analysis::Minimal { limit } => {
+            let mut found = Vec::new();
+            minimal(&mut board.clone(), &redundant(board), None, limit, &mut found);
+            return found.join("\n");
+        }
+        Analysis::Backdoors => {
+            return match backdoors(board, &solution) {
+                Some(found) => {
+                    let size = found.first().map_or(0, Vec::len);
+                    let sets: Vec<String> = found.iter()
+                        .map(|cells| cells.iter().map(|&cell| name(cell)).collect::<Vec<_>>().join(" "))
+                        .collect();
+                    let mut line = format!("backdoor size {}", size);
+                    if size > 0 {
+                        line += &format!(": {}", sets.join(", "));
+                    }
+                    line
+                }
+                None => format!("backdoor size >{}", MAX_BACKDOOR),
+            };
+        }
+        Analysis::UnavoidableSets => {
+            if !board.solved() {
+                return "Not a solution grid".to_string();
+            }
+            let cell_name = |cell: usize| format!("r{}c{}", shape.row(cell) + 1, shape.col(cell) + 1);
+            let sets: Vec<String> = unavoidable_sets(board).iter()
+                .map(|cells| cells.iter().map(|&cell| cell_name(cell)).collect::<Vec<_>>().join(" "))
+                .collect();
+            return format!("{} unavoidable sets: {}", sets.len(), sets.join(", "));
+        }
+    }
+}
+
+/// Return the givens of `board`, a puzzle with a unique solution,
+/// each of which can be taken out with the solution staying unique.
+/// The puzzle is minimal if there are none.
+pub fn redundant(board: &SudokuBoard) -> Vec<usize> {
+    let full = board.rules.shape.full_set();
+    return (0 .. board.cells.len()).filter(|&cell| {
+        if !board.cell_solved(cell) {
+            return false;
+        }
+        let mut fewer = board.clone();
+        fewer.cells[cell] = full;
+        return fewer.count_solutions(2) == 1;
+    }).collect();
+}
+
+/// Add to `found` the digits of the minimal puzzles left by taking
+/// out of `board`, a puzzle with a unique solution, some of its
+/// `removable` givens, each of which it can do without, as long as
+/// `found` holds fewer than `limit` puzzles.  The givens are taken
+/// out in order, those after `after` only, so that each set of
+/// givens is visited once.
+fn minimal(board: &mut SudokuBoard, removable: &[usize], after: Option<usize>, limit: usize,
+           found: &mut Vec<String>) {
+    if removable.is_empty() {
+        found.push(board.to_str());
+        return;
+    }
+    let full = board.rules.shape.full_set();
+    for &cell in removable.iter().filter(|&&cell| after.is_none_or(|after| cell > after)) {
+        if found.len() >= limit {
+            return;
+        }
+        let given = board.cells[cell];
+        board.cells[cell] = full;
+        let left: Vec<usize> = removable.iter().cloned().filter(|&other| {
+            let mut fewer = board.clone();
+            fewer.cells[other] = full;
+            return other != cell && fewer.count_solutions(2) == 1;
+        }).collect();
+        minimal(board, &left, Some(cell), limit, found);
+        board.cells[cell] = given;
+    }
+}
+
+/// Return the smallest sets of cells of `board`, a puzzle, whose
+/// digits in `solution` let singles solve it, or None if they hold
+/// more than `MAX_BACKDOOR` cells.  A puzzle singles solve has a
+/// single backdoor, without any cell.
+fn backdoors<'a>(board: &SudokuBoard<'a>, solution: &SudokuBoard) -> Option<Vec<Vec<usize>>> {
+    let singles = |board: &SudokuBoard<'a>| logic::solve(board, &[Technique::Singles]).0;
+    // The sets of cells of a size, and the positions singles reach
+    // from their digits.
+    let mut level = vec![(Vec::new(), singles(board))];
+    for size in 0 ..= MAX_BACKDOOR {
+        let found: Vec<Vec<usize>> = level.iter()
+            .filter(|&(_, position)| position.solved())
+            .map(|(cells, _)| cells.clone())
+            .collect();
+        if !found.is_empty() {
+            return Some(found);
+        }
+        if size == MAX_BACKDOOR {
+            break;
+        }
+        let mut next = Vec::new();
+        for (cells, position) in level.iter() {
+            // Cells are added in order, so that each set comes once.
+            let after = cells.last().map_or(0, |&last| last + 1);
+            for cell in (after .. position.cells.len()).filter(|&c| !position.cell_solved(c)) {
+                let mut assigned = position.clone();
+                assigned.cells[cell] = solution.cells[cell];
+                let mut cells = cells.clone();
+                cells.push(cell);
+                next.push((cells, singles(&assigned)));
+            }
+        }
+        level = next;
+    }
+    return None;
+}
+
+/// Add to `found` the solutions of `board`, as long as it holds
+/// fewer than `limit` of them.
+fn solutions<'a>(board: &SudokuBoard<'a>, limit: usize, found: &mut Vec<SudokuBoard<'a>>) {
+    let mut newboard = board.clone();
+    newboard.propagate();
+    if newboard.solved() {
+        found.push(newboard);
+        return;
+    }
+    if !newboard.solvable() {
+        return;
+    }
+    if let Some(cell) = newboard.most_promising() {
+        let candidates = newboard.cells[cell];
+        for d in (0 .. board.rules.shape.side()).filter(|d| candidates & (1 << d) != 0) {
+            if found.len() >= limit {
+                return;
+            }
+            newboard.cells[cell] = 1 << d;
+            solutions(&newboard, limit, found);
+        }
+    }
+}
+
+/// Return the minimal unavoidable sets of at most `MAX_UNAVOIDABLE`
+/// cells found in `grid`, a solution grid, by blanking the cells of
+/// two or three digits: the cells where another grid differs from it
+/// make an unavoidable set.  The sets are sorted by size.
+fn unavoidable_sets(grid: &SudokuBoard) -> Vec<Vec<usize>> {
+    let side = grid.rules.shape.side();
+    let full = grid.rules.shape.full_set();
+    let mut digit_sets: Vec<Vec<usize>> = Vec::new();
+    for a in 0 .. side {
+        for b in a + 1 .. side {
+            digit_sets.push(vec![a, b]);
+            digit_sets.extend((b + 1 .. side).map(|c| vec![a, b, c]));
+        }
+    }
+    let mut sets: Vec<Vec<usize>> = Vec::new();
+    for digits in digit_sets.iter() {
+        let mut blanked = grid.clone();
+        for set in blanked.cells.iter_mut() {
+            if digits.iter().any(|&d| *set == 1 << d) {
+                *set = full;
+            }
+        }
+        let mut others = Vec::new();
+        solutions(&blanked, MAX_GRIDS, &mut others);
+        for other in others.iter() {
+            let cells: Vec<usize> = (0 .. grid.cells.len()).filter(|&c| other.cells[c] != grid.cells[c]).collect();
+            if !cells.is_empty() && cells.len() <= MAX_UNAVOIDABLE && !sets.contains(&cells) {
+                sets.push(cells);
+            }
+        }
+    }
+    // A set holding another is not minimal.
+    let holds = |big: &Vec<usize>, small: &Vec<usize>| big != small && small.iter().all(|c| big.contains(c));
+    let mut minimal: Vec<Vec<usize>> = sets.iter().filter(|&set| !sets.iter().any(|other| holds(set, other))).cloned().collect();
+    minimal.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    return minimal;
+}
+
+#[test]
+fn test_redundant() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let board = SudokuBoard::from_str(PUZZLE, &rules);
+    assert_eq!(run(Analysis::Redundant, &board), "21 of 32 givens redundant: r1c3=3 r1c5=2 r2c4=3 r2c6=5 r2c9=1 \
+                                                  r3c3=1 r3c6=6 r3c7=4 r4c3=8 r4c4=1 r4c7=9 r5c9=8 r6c6=8 r6c7=2 \
+                                                  r7c3=2 r7c4=6 r7c7=5 r8c4=2 r8c6=3 r9c3=5 r9c7=3");
+    // A puzzle of 17 givens, the fewest a proper puzzle can have.
+    let board = SudokuBoard::from_str(".......1.4.........2...........5.4.7..8...3....1.9....3..4..2...5.1........8.6...", &rules);
+    assert_eq!(run(Analysis::Redundant, &board), "0 of 17 givens redundant");
+    let board = SudokuBoard::from_str(&".".repeat(81), &rules);
+    assert_eq!(run(Analysis::Redundant, &board), "No unique solution");
+}
+
+#[test]
+fn test_minimal() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let puzzle = PUZZLE;
+    let found = run(Analysis::Minimal { limit: 3 }, &SudokuBoard::from_str(puzzle, &rules));
+    assert_eq!(found.lines().count(), 3);
+    for digits in found.lines() {
+        assert!(digits.chars().zip(puzzle.chars()).all(|(d, p)| d == '.' || d == p));
+        assert_eq!(redundant(&SudokuBoard::from_str(digits, &rules)), vec![]);
+    }
+    let minimal = ".......1.4.........2...........5.4.7..8...3....1.9....3..4..2...5.1........8.6...";
+    assert_eq!(run(Analysis::Minimal { limit: 3 }, &SudokuBoard::from_str(minimal, &rules)), minimal);
+}
+
+#[test]
+fn test_backdoors() {
+    use super::{standard_rules, INKALA, PUZZLE};
+
+    let rules = standard_rules();
+    let board = SudokuBoard::from_str(PUZZLE, &rules);
+    assert_eq!(run(Analysis::Backdoors, &board), "backdoor size 0");
+    // Arto Inkala's "world's hardest sudoku".
+    let board = SudokuBoard::from_str(INKALA, &rules);
+    assert_eq!(run(Analysis::Backdoors, &board), "backdoor size 2: r1c3=2 r5c2=6, r1c3=2 r9c3=6, r2c2=4 r6c1=2, \
+                                                 r4c3=4 r5c3=9, r5c2=6 r6c1=2, r5c2=6 r9c4=3, r6c1=2 r9c3=6");
+}
+
+#[test]
+fn test_unavoidable_sets() {
+    use super::{standard_rules, Shape, PUZZLE, SOLUTION};
+
+    let rules = standard_rules();
+    let grid = SudokuBoard::from_str(SOLUTION, &rules);
+    let sets = unavoidable_sets(&grid);
+    assert!(!sets.is_empty() && sets.iter().all(|set| set.len() >= 4 && set.len() <= MAX_UNAVOIDABLE));
+    // Without a given among its cells, a puzzle has other solutions;
+    // with any one of them, the others are no longer unavoidable.
+    for set in sets.iter().take(3) {
+        let mut puzzle = grid.clone();
+        for &cell in set.iter() {
+            puzzle.cells[cell] = Shape::STANDARD.full_set();
+        }
+        assert_eq!(puzzle.count_solutions(2), 2);
+        let mut given = puzzle.clone();
+        given.cells[set[0]] = grid.cells[set[0]];
+        assert_eq!(given.count_solutions(2), 1);
+    }
+    let board = SudokuBoard::from_str(PUZZLE, &rules);
+    assert_eq!(run(Analysis::UnavoidableSets, &board), "Not a solution grid");
+}
@@ -0,0 +1,49 @@
+// Arrow sudoku: the digits along an arrow add up to the digit in
+// its circle.  Digits may repeat along an arrow, unless the rules
+// forbid it otherwise.
+
+use super::CandidateSet;
+use constraint::Constraint;
+use sum::{between, bounds, max_digit, min_digit, prune_terms};
+
+pub struct Arrow {
+    pub circle: usize,
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for Arrow {
+    /// Remove the candidates that fall outside the bounds of the sum:
+    /// the circle lies between the smallest and largest sums of the
+    /// arrow, and every cell of the arrow between what the circle and
+    /// the rest of the arrow leave for it.  Return whether any
+    /// candidate was removed.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        if cells[self.circle] == 0 || self.cells.iter().any(|&c| cells[c] == 0) {
+            return false;
+        }
+        let (lo, hi) = bounds(&self.cells, cells);
+        let circle = between(cells[self.circle], lo, hi);
+        let changed = circle != cells[self.circle];
+        cells[self.circle] = circle;
+        if circle == 0 {
+            return changed;
+        }
+
+        let (circle_lo, circle_hi) = (min_digit(circle) as i64, max_digit(circle) as i64);
+        return prune_terms(&self.cells, cells, circle_lo, circle_hi) || changed;
+    }
+}
+
+#[test]
+fn test_prune() {
+    // A circle fed by two cells holds at least 2...
+    let arrow = Arrow { circle: 0, cells: vec![1, 2] };
+    let mut cells = vec![0b1_1111_1111; 3];
+    assert!(arrow.prune(&mut cells));
+    assert_eq!(cells, vec![0b1_1111_1110, 0b0_1111_1111, 0b0_1111_1111]);
+
+    // ...and a 3 in the circle leaves a 1 and a 2 on the arrow.
+    cells[0] = 0b100;
+    assert!(arrow.prune(&mut cells));
+    assert_eq!(cells, vec![0b100, 0b11, 0b11]);
+}
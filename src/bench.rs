@@ -0,0 +1,55 @@
+// Benchmarking: solve a list of puzzles and report the time taken.
+// The puzzle files of the repository can be built into the program
+// with the `corpora` feature, so that runs on different machines
+// time the same puzzles.
+
+use std::time::{Duration, Instant};
+
+/// The built-in corpora, by name.
+#[cfg(feature = "corpora")]
+const CORPORA: [(&str, &str); 2] = [
+    ("problems", include_str!("../problems.txt")),
+    ("very-hard", include_str!("../very_hard.txt")),
+];
+#[cfg(not(feature = "corpora"))]
+const CORPORA: [(&str, &str); 0] = [];
+
+/// Return the puzzles of the built-in corpus `name`, one per line.
+pub fn corpus(name: &str) -> Option<&'static str> {
+    CORPORA.iter().find(|&&(corpus, _)| corpus == name).map(|&(_, puzzles)| puzzles)
+}
+
+/// Return the names of the built-in corpora.
+pub fn corpus_names() -> Vec<&'static str> {
+    CORPORA.iter().map(|&(name, _)| name).collect()
+}
+
+/// Solve `puzzles` with `solve`, which returns None for a puzzle
+/// without solution.  Return the time taken and the number of
+/// puzzles without solution.
+pub fn run(puzzles: &[String], solve: &dyn Fn(&str) -> Option<String>) -> (Duration, usize) {
+    let start = Instant::now();
+    let unsolved = puzzles.iter().filter(|puzzle| solve(puzzle).is_none()).count();
+    return (start.elapsed(), unsolved);
+}
+
+/// Return the report of a run over `count` puzzles.
+pub fn report(count: usize, elapsed: Duration, unsolved: usize) -> String {
+    let mut report = format!("{} puzzles in {:.3} s", count, elapsed.as_secs_f64());
+    if count > 0 {
+        report += &format!(", {:.1} us per puzzle", 1e6 * elapsed.as_secs_f64() / count as f64);
+    }
+    if unsolved > 0 {
+        report += &format!(", {} without solution", unsolved);
+    }
+    return report;
+}
+
+#[test]
+fn test_run() {
+    let puzzles: Vec<String> = ["1", "2", "3"].iter().map(|p| p.to_string()).collect();
+    let (_, unsolved) = run(&puzzles, &|p: &str| if p == "2" { None } else { Some(p.to_string()) });
+    assert_eq!(unsolved, 1);
+    assert_eq!(report(4, Duration::from_millis(2), 1), "4 puzzles in 0.002 s, 500.0 us per puzzle, 1 without solution");
+    assert_eq!(corpus("none"), None);
+}
@@ -0,0 +1,174 @@
+// The campaign: a sequence of puzzles of increasing difficulty, in
+// tiers from easy to diabolical.  Clearing the puzzles of a tier
+// unlocks the next one, and the puzzles solved, the best time, the
+// points and the mistakes of each tier are kept in a small text file
+// next to the saved game, e.g.:
+//
+//     sudoku-rs campaign
+//     easy 5 142 4630 2
+//     medium 2 305 3100 0
+//     hard 0 - 0 0
+//     ...
+//
+// The puzzles are generated from seeds of their own, so that the n-th
+// puzzle of a tier is always the same.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use logic::Difficulty;
+use play;
+
+/// The first line of the file of a campaign.
+const MAGIC: &str = "sudoku-rs campaign";
+/// The difficulties of the tiers, in the order they are played.
+const TIERS: [Difficulty; 5] = [
+    Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Fiendish, Difficulty::Diabolical,
+];
+/// The puzzles to solve to clear a tier.
+const PUZZLES: usize = 5;
+
+/// What was done in a tier.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tier {
+    /// The puzzles solved.
+    pub solved: usize,
+    /// The fewest seconds taken to solve a puzzle, if any was.
+    pub best: Option<u64>,
+    /// The points scored.
+    pub points: u64,
+    /// The mistakes made.
+    pub mistakes: usize,
+}
+
+/// The progress of a player through the campaign: a tier for each
+/// difficulty, from easy to diabolical.
+#[derive(Debug, PartialEq)]
+pub struct Progress {
+    pub tiers: Vec<Tier>,
+}
+
+impl Progress {
+    /// Start a campaign.
+    pub fn new() -> Self {
+        return Progress { tiers: vec![Tier::default(); TIERS.len()] };
+    }
+
+    /// Return the difficulty of the tier being played: the first tier
+    /// not cleared, or the last one once the campaign is over.
+    pub fn current(&self) -> Difficulty {
+        let tier = self.tiers.iter().position(|tier| tier.solved < PUZZLES).unwrap_or(self.tiers.len() - 1);
+        return TIERS[tier];
+    }
+
+    /// Return the seed of the next puzzle of the tier being played.
+    pub fn seed(&self) -> u64 {
+        let difficulty = self.current();
+        return (difficulty as u64 + 1) << 32 | self.tiers[difficulty as usize].solved as u64;
+    }
+
+    /// Count a puzzle of `difficulty` solved in `elapsed` with
+    /// `mistakes` mistakes, for `score` points.  Return whether it
+    /// cleared its tier.
+    pub fn record(&mut self, difficulty: Difficulty, elapsed: Duration, mistakes: usize, score: u64) -> bool {
+        let tier = &mut self.tiers[difficulty as usize];
+        tier.solved += 1;
+        tier.best = Some(tier.best.map_or(elapsed.as_secs(), |best| best.min(elapsed.as_secs())));
+        tier.points += score;
+        tier.mistakes += mistakes;
+        return tier.solved == PUZZLES;
+    }
+
+    /// Return the table of the tiers, a line each, e.g.,
+    /// "medium      2/5  best 5:05  3100 points  0 mistakes"; the
+    /// tiers not unlocked yet are "locked".
+    pub fn summary(&self) -> String {
+        let current = self.current() as usize;
+        let lines: Vec<String> = self.tiers.iter().enumerate().map(|(level, tier)| {
+            let name = TIERS[level].name();
+            if level > current {
+                return format!("{:<10}  locked", name);
+            }
+            let best = tier.best.map_or("-:--".to_string(), |best| play::clock(Duration::from_secs(best)));
+            return format!("{:<10}  {}/{}  best {}  {} points  {} mistake{}", name, tier.solved.min(PUZZLES), PUZZLES,
+                           best, tier.points, tier.mistakes, if tier.mistakes == 1 { "" } else { "s" });
+        }).collect();
+        return lines.join("\n");
+    }
+}
+
+/// Return the text of `progress`.
+pub fn to_text(progress: &Progress) -> String {
+    let mut text = format!("{}\n", MAGIC);
+    for (level, tier) in progress.tiers.iter().enumerate() {
+        text.push_str(&format!("{} {} {} {} {}\n", TIERS[level].name(), tier.solved,
+                               tier.best.map_or("-".to_string(), |best| best.to_string()), tier.points, tier.mistakes));
+    }
+    return text;
+}
+
+/// Return the progress kept in `text`, or why it cannot be read.
+pub fn from_text(text: &str) -> Result<Progress, String> {
+    let mut lines = text.lines();
+    if lines.next() != Some(MAGIC) {
+        return Err("not a campaign".to_string());
+    }
+    let mut progress = Progress::new();
+    for (level, tier) in progress.tiers.iter_mut().enumerate() {
+        let name = TIERS[level].name();
+        let invalid = || format!("invalid {} tier in campaign", name);
+        let fields: Vec<&str> = lines.next().unwrap_or("").split(' ').collect();
+        if fields.len() != 5 || fields[0] != name {
+            return Err(format!("missing {} tier in campaign", name));
+        }
+        tier.solved = fields[1].parse().map_err(|_| invalid())?;
+        tier.best = match fields[2] {
+            "-" => None,
+            best => Some(best.parse().map_err(|_| invalid())?),
+        };
+        tier.points = fields[3].parse().map_err(|_| invalid())?;
+        tier.mistakes = fields[4].parse().map_err(|_| invalid())?;
+    }
+    return Ok(progress);
+}
+
+/// Return the progress kept in the file at `path`, or a new campaign
+/// if there is none.
+pub fn read(path: &PathBuf) -> Result<Progress, String> {
+    if !path.exists() {
+        return Ok(Progress::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    return from_text(&text);
+}
+
+/// Keep `progress` in the file at `path`, making its directory if
+/// need be.
+pub fn write(path: &PathBuf, progress: &Progress) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot make {}: {}", dir.display(), e))?;
+    }
+    return fs::write(path, to_text(progress)).map_err(|e| format!("cannot write {}: {}", path.display(), e));
+}
+
+#[test]
+fn test_campaign() {
+    let mut progress = Progress::new();
+    assert_eq!(progress.current(), Difficulty::Easy);
+    for i in 0 .. PUZZLES {
+        assert_eq!(progress.seed(), 1 << 32 | i as u64);
+        let cleared = progress.record(Difficulty::Easy, Duration::from_secs(200 - i as u64), 1, 700);
+        assert_eq!(cleared, i + 1 == PUZZLES);
+    }
+    assert_eq!((progress.current(), progress.seed()), (Difficulty::Medium, 2 << 32));
+    let summary = progress.summary();
+    assert!(summary.starts_with("easy        5/5  best 3:16  3500 points  5 mistakes\nmedium      0/5  best -:--"));
+    assert!(summary.ends_with("diabolical  locked"));
+
+    let text = to_text(&progress);
+    assert!(text.contains("\neasy 5 196 3500 5\nmedium 0 - 0 0\n"));
+    assert_eq!(from_text(&text), Ok(progress));
+    assert!(from_text(&text.replace("medium 0", "hard 0")).is_err());
+    assert!(from_text(&text.replace("196", "x")).is_err());
+}
@@ -0,0 +1,278 @@
+// Canonical forms of puzzles: isomorphic puzzles, the same up to a
+// relabeling of the digits and, for plain sudoku, a reordering of
+// the rows and columns that keeps the boxes together, have the same
+// canonical form.  It is the smallest of their digit strings, blank
+// cells coming first and digits numbered in order of appearance.
+// The transformations that lead to the same form also tell how one
+// puzzle turns into the other.  A fingerprint, a 64-bit FNV-1a hash
+// of the form, stands for it where forms are too long to keep.
+
+use std::cmp::Ordering;
+
+use super::{num_to_char, Shape};
+use transform::Transform;
+
+/// Most column orders tried; grids with more fall back to relabeling.
+const MAX_ORDERS: usize = 100_000;
+
+/// The transformations that turn a puzzle into an isomorphic one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Isomorphisms {
+    /// Relabeling the digits, for rules tied to particular cells.
+    Digits,
+    /// Relabeling the digits, reordering the bands and stacks, the
+    /// rows within a band and the columns within a stack, and, for
+    /// square boxes, transposing.
+    Sudoku,
+}
+
+/// A partly built canonical form: the grid and column order it
+/// comes from, the rows taken so far and the labels given so far.
+struct Candidate {
+    grid: usize,
+    cols: usize,
+    rows: Vec<usize>,
+    labels: [u32; RADIX],
+    next: u32,
+}
+
+/// A digit without a label yet.
+const UNLABELED: u32 = u32::MAX;
+
+/// Number of digits written with a single character, 0 to Z.
+const RADIX: usize = 36;
+
+/// Return the canonical form of the puzzle `digits` on a grid of
+/// `shape` under `isomorphisms`.
+pub fn canonical(digits: &str, shape: Shape, isomorphisms: Isomorphisms) -> String {
+    return form(digits, shape, isomorphisms).0;
+}
+
+/// Return the fingerprint of the canonical form `form`, the same on
+/// every machine and in every version.
+pub fn fingerprint(form: &str) -> u64 {
+    return form.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3));
+}
+
+/// Return a transformation turning the puzzle `a` into the puzzle
+/// `b`, both on a single grid of `shape`, under `isomorphisms`, or
+/// None if they are not isomorphic.
+pub fn isomorphism(a: &str, b: &str, shape: Shape, isomorphisms: Isomorphisms) -> Option<Transform> {
+    let (form_a, to_form_a) = form(a, shape, isomorphisms);
+    let (form_b, to_form_b) = form(b, shape, isomorphisms);
+    if form_a != form_b {
+        return None;
+    }
+    return Some(to_form_a.then(&to_form_b.inverse()));
+}
+
+/// Return the canonical form of the puzzle `digits` on a grid of
+/// `shape` under `isomorphisms`, with a transformation turning the
+/// puzzle into it, if it is on a single grid.
+fn form(digits: &str, shape: Shape, isomorphisms: Isomorphisms) -> (String, Transform) {
+    let values: Vec<u32> = digits.chars().map(|c| c.to_digit(RADIX as u32).unwrap_or(0)).collect();
+    let side = shape.side();
+    let col_orders = if isomorphisms == Isomorphisms::Sudoku && values.len() == shape.cells() {
+        orders(side / shape.box_cols, shape.box_cols)
+    } else {
+        None
+    };
+    let col_orders = match col_orders {
+        Some(col_orders) => col_orders,
+        None => {
+            let (mut labels, mut next) = ([UNLABELED; RADIX], 1);
+            let form = values.iter().map(|&value| label(value, &mut labels, &mut next)).collect();
+            return (form, Transform::relabel(shape, relabeling(shape, &labels)));
+        }
+    };
+
+    let mut grids = vec![values.clone()];
+    if shape.box_rows == shape.box_cols {
+        grids.push((0 .. values.len()).map(|i| values[side * (i % side) + i / side]).collect());
+    }
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for grid in 0 .. grids.len() {
+        for cols in 0 .. col_orders.len() {
+            candidates.push(Candidate { grid, cols, rows: Vec::new(), labels: [UNLABELED; RADIX], next: 1 });
+        }
+    }
+
+    // Take the rows one at a time, keeping the candidates whose next
+    // row is the smallest.
+    let mut form = String::with_capacity(values.len());
+    let mut text: Vec<char> = Vec::with_capacity(side);
+    for i in 0 .. side {
+        let mut best: Option<Vec<char>> = None;
+        let mut kept = Vec::new();
+        for candidate in candidates.iter() {
+            let (grid, cols) = (&grids[candidate.grid], &col_orders[candidate.cols]);
+            for row in next_rows(shape, &candidate.rows, i) {
+                let (mut labels, mut next) = (candidate.labels, candidate.next);
+                text.clear();
+                // Give up on the row as soon as it is larger than the best.
+                let mut order = if best.is_some() { Ordering::Equal } else { Ordering::Less };
+                for (j, &c) in cols.iter().enumerate() {
+                    let ch = label(grid[side * row + c], &mut labels, &mut next);
+                    if order == Ordering::Equal {
+                        order = ch.cmp(&best.as_ref().unwrap()[j]);
+                        if order == Ordering::Greater {
+                            break;
+                        }
+                    }
+                    text.push(ch);
+                }
+                match order {
+                    Ordering::Greater => { continue; }
+                    Ordering::Equal => {}
+                    Ordering::Less => {
+                        best = Some(text.clone());
+                        kept.clear();
+                    }
+                }
+                let mut rows = candidate.rows.clone();
+                rows.push(row);
+                kept.push(Candidate { grid: candidate.grid, cols: candidate.cols, rows, labels, next });
+            }
+        }
+        form.extend(best.unwrap());
+        candidates = kept;
+    }
+    // Any candidate left gives the form.
+    let candidate = &candidates[0];
+    return (form, Transform {
+        transpose: candidate.grid == 1,
+        rows: candidate.rows.clone(),
+        cols: col_orders[candidate.cols].clone(),
+        digits: relabeling(shape, &candidate.labels),
+    });
+}
+
+/// Return the labels of the digits of a grid of `shape`, given by
+/// `labels`, as a relabeling: the digits without a label take the
+/// labels left, in order.
+fn relabeling(shape: Shape, labels: &[u32; RADIX]) -> Vec<u32> {
+    let side = shape.side() as u32;
+    let mut unused = (1 ..= side).filter(|label| !labels.contains(label));
+    return (1 ..= side).map(|d| match labels[d as usize] {
+        UNLABELED => unused.next().unwrap(),
+        label => label,
+    }).collect();
+}
+
+/// Return the character of `value` in a canonical form: a dot for a
+/// blank cell, else the label of the digit, the next one if it has
+/// none yet.
+fn label(value: u32, labels: &mut [u32; RADIX], next: &mut u32) -> char {
+    if value == 0 {
+        return '.';
+    }
+    if labels[value as usize] == UNLABELED {
+        labels[value as usize] = *next;
+        *next += 1;
+    }
+    return num_to_char(labels[value as usize]);
+}
+
+/// Return the rows that can come at position `i`, after `rows`: any
+/// row of an unused band at the start of a band, else the other rows
+/// of the band of the previous row.
+fn next_rows(shape: Shape, rows: &[usize], i: usize) -> Vec<usize> {
+    let band = |row: usize| row / shape.box_rows;
+    (0 .. shape.side())
+        .filter(|row| !rows.contains(row))
+        .filter(|&row| if i.is_multiple_of(shape.box_rows) {
+            !rows.iter().any(|&r| band(r) == band(row))
+        } else {
+            band(row) == band(rows[i - 1])
+        })
+        .collect()
+}
+
+/// Return every order of `groups` groups of `size` lines that keeps
+/// the groups together, or None if there are too many.
+fn orders(groups: usize, size: usize) -> Option<Vec<Vec<usize>>> {
+    let count = (1 ..= groups).product::<usize>()
+        .checked_mul((1 ..= size).product::<usize>().checked_pow(groups as u32)?)?;
+    if count > MAX_ORDERS {
+        return None;
+    }
+    let mut all_orders = Vec::with_capacity(count);
+    for group_order in permutations(groups) {
+        let mut partial: Vec<Vec<usize>> = vec![Vec::new()];
+        for &group in group_order.iter() {
+            let within = permutations(size);
+            partial = partial.iter()
+                .flat_map(|order| within.iter().map(move |lines| {
+                    let mut order = order.clone();
+                    order.extend(lines.iter().map(|line| size * group + line));
+                    order
+                }))
+                .collect();
+        }
+        all_orders.extend(partial);
+    }
+    return Some(all_orders);
+}
+
+/// Return the permutations of 0 .. n.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut all = Vec::new();
+    for shorter in permutations(n - 1) {
+        for i in 0 ..= shorter.len() {
+            let mut longer = shorter.clone();
+            longer.insert(i, n - 1);
+            all.push(longer);
+        }
+    }
+    return all;
+}
+
+#[test]
+fn test_canonical() {
+    use super::PUZZLE;
+
+    let puzzle = PUZZLE;
+    let form = canonical(puzzle, Shape::STANDARD, Isomorphisms::Sudoku);
+    // Swap the first two bands, relabel 1 and 2, and transpose.
+    let swapped: String = puzzle[27 .. 54].chars().chain(puzzle[.. 27].chars()).chain(puzzle[54 ..].chars())
+        .map(|c| match c { '1' => '2', '2' => '1', c => c })
+        .collect();
+    let transposed: String = (0 .. 81).map(|i| swapped.as_bytes()[9 * (i % 9) + i / 9] as char).collect();
+    assert_eq!(canonical(&transposed, Shape::STANDARD, Isomorphisms::Sudoku), form);
+    let relabeled: String = puzzle.chars().map(|c| match c { '1' => '2', '2' => '1', c => c }).collect();
+    assert_eq!(canonical(&relabeled, Shape::STANDARD, Isomorphisms::Digits),
+               canonical(puzzle, Shape::STANDARD, Isomorphisms::Digits));
+    assert!(canonical(&transposed, Shape::STANDARD, Isomorphisms::Digits)
+            != canonical(puzzle, Shape::STANDARD, Isomorphisms::Digits));
+}
+
+#[test]
+fn test_fingerprint() {
+    // The published test vectors of FNV-1a.
+    assert_eq!(fingerprint(""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fingerprint("a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fingerprint("foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+fn test_isomorphism() {
+    use super::PUZZLE;
+
+    let puzzle = PUZZLE;
+    let swapped: String = puzzle[27 .. 54].chars().chain(puzzle[.. 27].chars()).chain(puzzle[54 ..].chars())
+        .map(|c| match c { '1' => '2', '2' => '1', c => c })
+        .collect();
+    let transposed: String = (0 .. 81).map(|i| swapped.as_bytes()[9 * (i % 9) + i / 9] as char).collect();
+    for other in [swapped, transposed].iter() {
+        let transform = isomorphism(puzzle, other, Shape::STANDARD, Isomorphisms::Sudoku).unwrap();
+        assert_eq!(&transform.apply(puzzle), other);
+        assert_eq!(&transform.digits[.. 3], &[2, 1, 3]);
+    }
+    let transform = isomorphism(puzzle, puzzle, Shape::STANDARD, Isomorphisms::Digits).unwrap();
+    assert_eq!(transform.text(), "rows 1 2 3 4 5 6 7 8 9, columns 1 2 3 4 5 6 7 8 9, digits 1 2 3 4 5 6 7 8 9");
+    let other = format!("1{}", &puzzle[1 ..]);
+    assert_eq!(isomorphism(puzzle, &other, Shape::STANDARD, Isomorphisms::Sudoku), None);
+}
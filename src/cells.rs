@@ -0,0 +1,80 @@
+// The candidates of the cells of a board.  Boards of up to 81
+// cells, the standard grid and the smaller ones, keep them in a
+// fixed array, so that creating and cloning the boards of a search
+// never allocates; larger boards keep them on the heap.
+
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use super::{CandidateSet, EMPTY_SET, NSQ};
+
+/// The candidate sets of the cells of a board.
+// The array is large on purpose: it is what saves the allocations.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cells {
+    /// The first `len` sets of the array.
+    Inline(usize, [CandidateSet; NSQ]),
+    Heap(Vec<CandidateSet>),
+}
+
+impl Cells {
+    /// Return `len` cells with the candidates `set`.
+    pub fn filled(set: CandidateSet, len: usize) -> Self {
+        if len <= NSQ {
+            let mut array = [EMPTY_SET; NSQ];
+            array[.. len].fill(set);
+            return Cells::Inline(len, array);
+        }
+        return Cells::Heap(vec![set; len]);
+    }
+}
+
+impl Cells {
+    /// Return the bytes the cells take on the heap.
+    pub fn heap_size(&self) -> usize {
+        match *self {
+            Cells::Inline(..) => 0,
+            Cells::Heap(ref sets) => sets.capacity() * mem::size_of::<CandidateSet>(),
+        }
+    }
+}
+
+impl From<&[CandidateSet]> for Cells {
+    fn from(sets: &[CandidateSet]) -> Self {
+        let mut cells = Cells::filled(EMPTY_SET, sets.len());
+        cells.copy_from_slice(sets);
+        return cells;
+    }
+}
+
+impl Deref for Cells {
+    type Target = [CandidateSet];
+
+    fn deref(&self) -> &[CandidateSet] {
+        match *self {
+            Cells::Inline(len, ref array) => &array[.. len],
+            Cells::Heap(ref sets) => sets,
+        }
+    }
+}
+
+impl DerefMut for Cells {
+    fn deref_mut(&mut self) -> &mut [CandidateSet] {
+        match *self {
+            Cells::Inline(len, ref mut array) => &mut array[.. len],
+            Cells::Heap(ref mut sets) => sets,
+        }
+    }
+}
+
+#[test]
+fn test_cells() {
+    let small = Cells::filled(7, 4);
+    assert!(matches!(small, Cells::Inline(4, _)));
+    assert_eq!(&small[..], &[7, 7, 7, 7]);
+    let large = Cells::from(&[1; NSQ + 1][..]);
+    assert!(matches!(large, Cells::Heap(_)));
+    assert_eq!(large.len(), NSQ + 1);
+    assert_eq!((small.heap_size(), large.heap_size()), (0, 4 * (NSQ + 1)));
+}
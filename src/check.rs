@@ -0,0 +1,134 @@
+// Checking the work of a solver: the digits filled in on a puzzle
+// that break its rules, by repeating a digit where the digits must
+// differ, or that disagree with its solution, for a "check my work"
+// button, the pencil marks that rule out the digit of the solution,
+// and whether a solution handed in solves the puzzle.
+
+use super::{Shape, SudokuBoard};
+
+/// A mistake among the digits filled in on a puzzle.  Digits count
+/// from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Conflict {
+    /// `first` and `second` must hold different digits, but both hold
+    /// `digit`.
+    Duplicate { first: usize, second: usize, digit: usize },
+    /// `cell` holds `digit`, which is not its digit in the solution.
+    Wrong { cell: usize, digit: usize },
+    /// The marks of `cell` leave out `digit`, its digit in the
+    /// solution.
+    Excluded { cell: usize, digit: usize },
+}
+
+impl Conflict {
+    /// Describe the conflict, naming the cells of a grid of `shape`
+    /// by their row and column, e.g., "r1c1=3 r1c5=3 repeated".
+    pub fn text(&self, shape: Shape) -> String {
+        let name = |cell: usize| format!("r{}c{}", shape.row(cell) + 1, shape.col(cell) + 1);
+        return match *self {
+            Conflict::Duplicate { first, second, digit } => {
+                format!("{}={} {}={} repeated", name(first), digit, name(second), digit)
+            }
+            Conflict::Wrong { cell, digit } => format!("{}={} wrong", name(cell), digit),
+            Conflict::Excluded { cell, digit } => format!("{}<>{} excluded", name(cell), digit),
+        };
+    }
+}
+
+/// Return the pairs of cells of `board` that must hold different
+/// digits but hold the same one.
+fn duplicates(board: &SudokuBoard) -> Vec<Conflict> {
+    let mut found = Vec::new();
+    for first in (0 .. board.cells.len()).filter(|&c| board.cell_solved(c)) {
+        for &second in board.rules.neighbors[first].iter() {
+            if second > first && board.cells[second] == board.cells[first] {
+                let digit = board.cells[first].trailing_zeros() as usize + 1;
+                found.push(Conflict::Duplicate { first, second, digit });
+            }
+        }
+    }
+    return found;
+}
+
+/// Return the conflicts of `progress`, the digits filled in so far
+/// on `puzzle` under the same rules, givens included, or its pencil
+/// marks, the cells with a single candidate being filled in.  Digits
+/// and marks only disagree with the solution of a puzzle with a
+/// unique solution.
+pub fn conflicts(puzzle: &SudokuBoard, progress: &SudokuBoard) -> Vec<Conflict> {
+    let digit = |cell: usize| progress.cells[cell].trailing_zeros() as usize + 1;
+    let filled: Vec<usize> = (0 .. progress.cells.len()).filter(|&c| progress.cell_solved(c)).collect();
+    let mut found = duplicates(progress);
+    if puzzle.count_solutions(2) == 1 {
+        let solution = puzzle.solve().unwrap();
+        for &cell in filled.iter() {
+            if progress.cells[cell] != solution.cells[cell] {
+                found.push(Conflict::Wrong { cell, digit: digit(cell) });
+            }
+        }
+        for cell in (0 .. progress.cells.len()).filter(|&c| !progress.cell_solved(c)) {
+            if progress.cells[cell] & solution.cells[cell] == 0 {
+                found.push(Conflict::Excluded { cell, digit: solution.cells[cell].trailing_zeros() as usize + 1 });
+            }
+        }
+    }
+    return found;
+}
+
+/// Check that `solution` solves `puzzle`, under the same rules: that
+/// it fills every cell, keeps the givens and satisfies every rule.
+/// Return why it does not otherwise.
+pub fn verify(puzzle: &SudokuBoard, solution: &SudokuBoard) -> Result<(), String> {
+    let shape = puzzle.rules.shape;
+    let name = |cell: usize| format!("r{}c{}", shape.row(cell) + 1, shape.col(cell) + 1);
+    if let Some(cell) = (0 .. solution.cells.len()).find(|&c| !solution.cell_solved(c)) {
+        return Err(format!("{} is empty", name(cell)));
+    }
+    if let Some(cell) = (0 .. puzzle.cells.len()).find(|&c| puzzle.cell_solved(c) && puzzle.cells[c] != solution.cells[c]) {
+        return Err(format!("{} is not the given {}", name(cell), puzzle.cells[cell].trailing_zeros() + 1));
+    }
+    if let Some(conflict) = duplicates(solution).first() {
+        return Err(conflict.text(shape));
+    }
+    let mut cells = solution.cells.clone();
+    if solution.rules.constraints.iter().any(|constraint| constraint.prune(&mut cells)) {
+        return Err("a rule is broken".to_string());
+    }
+    return Ok(());
+}
+
+#[test]
+fn test_conflicts() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let puzzle = SudokuBoard::from_str(PUZZLE, &rules);
+    // Right so far: 4 and 8 in r1c1 and r1c2.
+    let progress = SudokuBoard::from_str("483.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..", &rules);
+    assert!(conflicts(&puzzle, &progress).is_empty());
+    // A 3 in r1c4, where the 3 of r1c3 and r2c4 forbid it.
+    let progress = SudokuBoard::from_str("48332.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..", &rules);
+    let found: Vec<String> = conflicts(&puzzle, &progress).iter().map(|c| c.text(Shape::STANDARD)).collect();
+    assert_eq!(found, vec!["r1c3=3 r1c4=3 repeated", "r1c4=3 r2c4=3 repeated", "r1c4=3 wrong"]);
+    // Marks leaving out the 9 of r1c4 and the 5 of r1c8.
+    let mut marks = SudokuBoard::from_str("4.3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..", &rules);
+    marks.cells[3] = 0b0_0100_0001;
+    marks.cells[7] = 0b0_0000_0101;
+    let found: Vec<String> = conflicts(&puzzle, &marks).iter().map(|c| c.text(Shape::STANDARD)).collect();
+    assert_eq!(found, vec!["r1c4<>9 excluded", "r1c8<>5 excluded"]);
+}
+
+#[test]
+fn test_verify() {
+    use super::{standard_rules, PUZZLE, SOLUTION};
+
+    let rules = standard_rules();
+    let puzzle = SudokuBoard::from_str(PUZZLE, &rules);
+    let solution = SOLUTION;
+    assert_eq!(verify(&puzzle, &SudokuBoard::from_str(solution, &rules)), Ok(()));
+    let check = |digits: String| verify(&puzzle, &SudokuBoard::from_str(&digits, &rules)).unwrap_err();
+    assert_eq!(check(format!(".{}", &solution[1 ..])), "r1c1 is empty");
+    assert_eq!(check(format!("48{}", &solution[1 ..])[.. 81].to_string()), "r1c3 is not the given 3");
+    // r1c1 and r1c2 swapped keep the givens but repeat digits in columns.
+    assert_eq!(check(format!("84{}", &solution[2 ..])), "r1c1=8 r8c1=8 repeated");
+}
@@ -0,0 +1,122 @@
+// Checkpoints of long batch solves: the puzzles of the input done so
+// far, what came of them and the bytes of their lines and rejects,
+// kept in a small JSON file rewritten every so often, e.g.:
+//
+//     {"offset": 120000, "solved": 119950, "unsolvable": 30, "given_up": 12, "rejected": 8,
+//      "output_bytes": 9837860, "rejects_bytes": 1402}
+//
+// A checkpoint is written only once the lines of the puzzles it counts
+// are written out; the output cut back to its bytes, a job resumed
+// from it neither skips a puzzle nor writes one twice.  It is written
+// to a file beside it first, then renamed, so that a crash never
+// leaves half of one.
+
+use std::fs;
+use std::time::Duration;
+
+use json::{self, Json};
+
+/// The time between two checkpoints.
+pub const INTERVAL: Duration = Duration::from_secs(30);
+
+/// What came of a puzzle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Solved,
+    Unsolvable,
+    /// Its search gave up.
+    GivenUp,
+    /// It could not be read.
+    Rejected,
+}
+
+/// The progress of a batch solve.
+#[derive(Debug, Default, PartialEq)]
+pub struct Checkpoint {
+    /// The puzzles of the input done.
+    pub offset: u64,
+    pub solved: u64,
+    pub unsolvable: u64,
+    pub given_up: u64,
+    pub rejected: u64,
+    /// The bytes of the lines written out.
+    pub output_bytes: u64,
+    /// The bytes of the rejects written out.
+    pub rejects_bytes: u64,
+}
+
+impl Checkpoint {
+    /// Count a puzzle done, with its `outcome`.
+    pub fn count(&mut self, outcome: Outcome) {
+        self.offset += 1;
+        match outcome {
+            Outcome::Solved => { self.solved += 1; }
+            Outcome::Unsolvable => { self.unsolvable += 1; }
+            Outcome::GivenUp => { self.given_up += 1; }
+            Outcome::Rejected => { self.rejected += 1; }
+        }
+    }
+}
+
+/// Return the text of `checkpoint`.
+pub fn to_text(checkpoint: &Checkpoint) -> String {
+    return Json::object(vec![
+        ("offset", Json::Number(checkpoint.offset as f64)),
+        ("solved", Json::Number(checkpoint.solved as f64)),
+        ("unsolvable", Json::Number(checkpoint.unsolvable as f64)),
+        ("given_up", Json::Number(checkpoint.given_up as f64)),
+        ("rejected", Json::Number(checkpoint.rejected as f64)),
+        ("output_bytes", Json::Number(checkpoint.output_bytes as f64)),
+        ("rejects_bytes", Json::Number(checkpoint.rejects_bytes as f64)),
+    ]).to_string();
+}
+
+/// Return the checkpoint kept in `text`, or why it cannot be read.
+pub fn from_text(text: &str) -> Result<Checkpoint, String> {
+    let value = json::parse(text.trim())?;
+    let field = |key: &str| -> Result<u64, String> {
+        return value.get(key).and_then(Json::as_u64).ok_or_else(|| format!("missing {:?} in checkpoint", key));
+    };
+    let checkpoint = Checkpoint {
+        offset: field("offset")?,
+        solved: field("solved")?,
+        unsolvable: field("unsolvable")?,
+        given_up: field("given_up")?,
+        rejected: field("rejected")?,
+        output_bytes: field("output_bytes")?,
+        rejects_bytes: field("rejects_bytes")?,
+    };
+    if checkpoint.solved + checkpoint.unsolvable + checkpoint.given_up + checkpoint.rejected != checkpoint.offset {
+        return Err("the outcomes of the checkpoint do not add up to its offset".to_string());
+    }
+    return Ok(checkpoint);
+}
+
+/// Return the checkpoint kept in the file at `path`.
+pub fn read(path: &str) -> Result<Checkpoint, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    return from_text(&text);
+}
+
+/// Keep `checkpoint` in the file at `path`, in place of the one there.
+pub fn write(path: &str, checkpoint: &Checkpoint) -> Result<(), String> {
+    let written = format!("{}.tmp", path);
+    fs::write(&written, format!("{}\n", to_text(checkpoint))).map_err(|e| format!("cannot write {}: {}", written, e))?;
+    return fs::rename(&written, path).map_err(|e| format!("cannot write {}: {}", path, e));
+}
+
+#[test]
+fn test_checkpoint() {
+    let mut checkpoint = Checkpoint::default();
+    for &outcome in [Outcome::Solved, Outcome::Solved, Outcome::Rejected, Outcome::GivenUp].iter() {
+        checkpoint.count(outcome);
+    }
+    checkpoint.output_bytes = 246;
+    let text = to_text(&checkpoint);
+    assert_eq!(text, r#"{"offset": 4, "solved": 2, "unsolvable": 0, "given_up": 1, "rejected": 1, "#.to_string()
+                     + r#""output_bytes": 246, "rejects_bytes": 0}"#);
+    assert_eq!(from_text(&text), Ok(checkpoint));
+    assert!(from_text(&text.replace("\"offset\": 4", "\"offset\": 5")).is_err());
+    assert!(from_text(&text.replace("\"solved\": 2, ", "")).is_err());
+    assert!(from_text("{").is_err());
+}
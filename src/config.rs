@@ -0,0 +1,435 @@
+// The config file of the game: its colors, the lines of its grid and
+// its keys, in `sudoku-rs/config.toml` under the config directory of
+// the user, e.g.:
+//
+//     [colors]
+//     given = "bold blue"
+//     cursor = "on-cyan"
+//
+//     [grid]
+//     lines = "light"
+//
+//     [keys]
+//     up = ["up", "k"]
+//     hint = "?"
+//
+// The file is read as the small subset of TOML it needs: tables of
+// keys, each a string or an array of strings, and comments.  A key
+// bound to an action replaces the keys bound to it by default.
+
+use std::env;
+use std::fs;
+use std::iter::{self, Peekable};
+use std::path::PathBuf;
+use std::str::Chars;
+
+use play::Action;
+use terminal::{self, Key, BOLD, RED, REVERSE, YELLOW};
+
+/// A value of the config file.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Str(String),
+    List(Vec<String>),
+}
+
+/// The settings of the game.
+pub struct Config {
+    pub theme: Theme,
+    pub keys: Keys,
+}
+
+/// How the grid is drawn: the escape sequences of each kind of cell,
+/// and the lines between the boxes.
+pub struct Theme {
+    /// The givens.
+    pub given: String,
+    /// The cells of the mistakes.
+    pub mistake: String,
+    /// The cells of a hint.
+    pub hint: String,
+    /// The selected cell.
+    pub cursor: String,
+    pub lines: Lines,
+}
+
+/// The edges of the boxes, from the top of the grid to its bottom.
+#[derive(Clone, Copy)]
+pub enum Edge {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// The characters of the lines between the boxes.
+pub struct Lines {
+    horizontal: char,
+    pub vertical: char,
+    /// The corners and crossings of each edge, left to right.
+    corners: [[char; 3]; 3],
+}
+
+/// The keys bound to each action.
+pub struct Keys {
+    bindings: Vec<(Key, Action)>,
+    /// The keys of the digits, from 1; the digits and capital letters
+    /// by default.
+    digits: Option<Vec<char>>,
+}
+
+impl Lines {
+    /// Return the lines named `name`: `ascii`, or the box-drawing
+    /// characters of `light`, `heavy` or `double`.
+    pub fn from_name(name: &str) -> Option<Lines> {
+        let (horizontal, vertical, corners) = match name {
+            "ascii" => ('-', '|', [['+'; 3]; 3]),
+            "light" => ('─', '│', [['┌', '┬', '┐'], ['├', '┼', '┤'], ['└', '┴', '┘']]),
+            "heavy" => ('━', '┃', [['┏', '┳', '┓'], ['┣', '╋', '┫'], ['┗', '┻', '┛']]),
+            "double" => ('═', '║', [['╔', '╦', '╗'], ['╠', '╬', '╣'], ['╚', '╩', '╝']]),
+            _ => { return None; }
+        };
+        return Some(Lines { horizontal, vertical, corners });
+    }
+
+    /// Return the line of `edge` across `count` boxes, each `width`
+    /// characters wide.
+    pub fn border(&self, edge: Edge, width: usize, count: usize) -> String {
+        let [left, crossing, right] = self.corners[edge as usize];
+        let segment: String = iter::repeat_n(self.horizontal, width).collect();
+        return format!("{}{}{}", left, vec![segment; count].join(&crossing.to_string()), right);
+    }
+}
+
+impl Keys {
+    /// Return the keys of the game by default.
+    pub fn new() -> Self {
+        let bindings = vec![
+            (Key::Up, Action::Up),
+            (Key::Down, Action::Down),
+            (Key::Left, Action::Left),
+            (Key::Right, Action::Right),
+            (Key::Char('0'), Action::Erase),
+            (Key::Backspace, Action::Erase),
+            (Key::Delete, Action::Erase),
+            (Key::Char('.'), Action::Erase),
+            (Key::Char(' '), Action::Erase),
+            (Key::Char('m'), Action::Marks),
+            (Key::Char('a'), Action::AllMarks),
+            (Key::Char('u'), Action::Undo),
+            (Key::Char('r'), Action::Redo),
+            (Key::Char('h'), Action::Hint),
+            (Key::Char('q'), Action::Quit),
+        ];
+        return Keys { bindings, digits: None };
+    }
+
+    /// Return the action of `key`, if any.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        if let Some(&(_, action)) = self.bindings.iter().find(|binding| binding.0 == key) {
+            return Some(action);
+        }
+        let c = match key {
+            Key::Char(c) => c,
+            _ => { return None; }
+        };
+        return match self.digits {
+            Some(ref digits) => digits.iter().position(|&d| d == c).map(|d| Action::Digit(d + 1)),
+            // The lowercase letters are left to the commands.
+            None if c.is_ascii_lowercase() => None,
+            None => c.to_digit(36).map(|d| Action::Digit(d as usize)),
+        };
+    }
+
+    /// Check that there is a key for every digit of a grid of `side`.
+    pub fn check(&self, side: usize) -> Result<(), String> {
+        match self.digits {
+            Some(ref digits) if digits.len() < side => {
+                Err(format!("the config has {} digit keys; the grid needs {}", digits.len(), side))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return the name of the first key bound to `action`.
+    pub fn first(&self, action: Action) -> String {
+        return self.bindings.iter().find(|binding| binding.1 == action).map_or("none".to_string(), |b| name(b.0));
+    }
+
+    /// Return the keys of the game, to be shown under the grid.
+    pub fn help(&self) -> String {
+        let moves = [Action::Up, Action::Down, Action::Left, Action::Right];
+        let arrows = [Key::Up, Key::Down, Key::Left, Key::Right];
+        let moving = if moves.iter().zip(arrows.iter()).all(|(&action, &key)| self.first(action) == name(key)) {
+            "arrows".to_string()
+        } else {
+            moves.iter().map(|&action| self.first(action)).collect::<Vec<String>>().join("/")
+        };
+        let digits = match self.digits {
+            Some(ref digits) => format!("{}..{}", digits[0], digits[digits.len() - 1]),
+            None => "digits".to_string(),
+        };
+        return format!("{}: move  {}: fill in  {}: erase  {}: marks  {}: all marks  {}: undo  {}: redo  {}: hint  {}: quit",
+                       moving, digits, self.first(Action::Erase), self.first(Action::Marks),
+                       self.first(Action::AllMarks), self.first(Action::Undo), self.first(Action::Redo),
+                       self.first(Action::Hint), self.first(Action::Quit));
+    }
+}
+
+/// Return the name of `key` in the config file.
+fn name(key: Key) -> String {
+    return match key {
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Interrupt => "ctrl-c".to_string(),
+        Key::Click { .. } => "click".to_string(),
+        Key::Char(' ') => "space".to_string(),
+        Key::Char(c) => c.to_string(),
+    };
+}
+
+/// Return the key named `name` in the config file: a printable
+/// character, or `up`, `down`, `left`, `right`, `backspace`, `delete`
+/// or `space`.
+fn key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c)).filter(|_| c.is_ascii_graphic());
+    }
+    return match name {
+        "up" => Some(Key::Up),
+        "down" => Some(Key::Down),
+        "left" => Some(Key::Left),
+        "right" => Some(Key::Right),
+        "backspace" => Some(Key::Backspace),
+        "delete" => Some(Key::Delete),
+        "space" => Some(Key::Char(' ')),
+        _ => None,
+    };
+}
+
+/// The actions bound to keys, by their names in the config file.
+const ACTIONS: [(&str, Action); 11] = [
+    ("up", Action::Up), ("down", Action::Down), ("left", Action::Left), ("right", Action::Right),
+    ("erase", Action::Erase), ("marks", Action::Marks), ("all-marks", Action::AllMarks),
+    ("undo", Action::Undo), ("redo", Action::Redo), ("hint", Action::Hint), ("quit", Action::Quit),
+];
+
+/// Return the action named `name` in the config file.
+fn action(name: &str) -> Option<Action> {
+    return ACTIONS.iter().find(|named| named.0 == name).map(|named| named.1);
+}
+
+/// Return the name of `action` in the config file.
+fn action_name(action: Action) -> &'static str {
+    return ACTIONS.iter().find(|named| named.1 == action).map_or("a digit", |named| named.0);
+}
+
+/// Return the string at the start of `chars`, in double quotes, with
+/// backslash escapes, or in single quotes, without.
+fn string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let quote = match chars.next() {
+        Some(c) if c == '"' || c == '\'' => c,
+        _ => { return Err("expected a string".to_string()); }
+    };
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            None => { return Err("unclosed string".to_string()); }
+            Some(c) if c == quote => { return Ok(text); }
+            Some('\\') if quote == '"' => match chars.next() {
+                Some('n') => { text.push('\n'); }
+                Some('t') => { text.push('\t'); }
+                Some(c) if c == '"' || c == '\\' => { text.push(c); }
+                _ => { return Err("invalid escape in string".to_string()); }
+            },
+            Some(c) => { text.push(c); }
+        }
+    }
+}
+
+/// Return the value at the start of `chars`: a string, or an array of
+/// strings on a line.
+fn value(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    if chars.peek() != Some(&'[') {
+        return string(chars).map(Value::Str);
+    }
+    chars.next();
+    let mut list = Vec::new();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek() == Some(&']') {
+            chars.next();
+            return Ok(Value::List(list));
+        }
+        list.push(string(chars)?);
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        match chars.next() {
+            Some(',') => {}
+            Some(']') => { return Ok(Value::List(list)); }
+            _ => { return Err("expected , or ] in array".to_string()); }
+        }
+    }
+}
+
+/// Return the values of `text`, named after their tables, e.g.,
+/// `keys.up`, in the order they come.  Return why it cannot be read
+/// otherwise.
+fn parse(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut table = String::new();
+    let mut values = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let at = |msg: String| format!("line {}: {}", number + 1, msg);
+        // The rest of a line after a table name or a value.
+        let ending = |rest: &str| rest.trim().is_empty() || rest.trim().starts_with('#');
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('[') {
+            let (name, rest) = rest.split_once(']').ok_or_else(|| at("unclosed table name".to_string()))?;
+            if !ending(rest) {
+                return Err(at("text after the table name".to_string()));
+            }
+            table = name.trim().to_string();
+            continue;
+        }
+        let (name, rest) = line.split_once('=').ok_or_else(|| at("expected key = value".to_string()))?;
+        let mut chars = rest.trim().chars().peekable();
+        let value = value(&mut chars).map_err(at)?;
+        if !ending(&chars.collect::<String>()) {
+            return Err(at("text after the value".to_string()));
+        }
+        let name = if table.is_empty() { name.trim().to_string() } else { format!("{}.{}", table, name.trim()) };
+        values.push((name, value));
+    }
+    return Ok(values);
+}
+
+/// Return the settings of `text`, or why they cannot be used.
+pub fn from_text(text: &str) -> Result<Config, String> {
+    let mut theme = Theme {
+        given: BOLD.to_string(),
+        mistake: RED.to_string(),
+        hint: YELLOW.to_string(),
+        cursor: REVERSE.to_string(),
+        lines: Lines::from_name("ascii").unwrap(),
+    };
+    let mut keys = Keys::new();
+    for (name, value) in parse(text)? {
+        let names = match value {
+            Value::Str(ref text) => vec![text.clone()],
+            Value::List(ref list) => list.clone(),
+        };
+        let text = match value {
+            Value::Str(text) => Some(text),
+            Value::List(_) => None,
+        };
+        let needs_string = || format!("{} needs a string", name);
+        let style = |text: Option<String>| -> Result<String, String> {
+            let text = text.ok_or_else(needs_string)?;
+            return terminal::style(&text).ok_or_else(|| format!("invalid style {:?} of {}", text, name));
+        };
+        match name.as_str() {
+            "colors.given" => { theme.given = style(text)?; }
+            "colors.mistake" => { theme.mistake = style(text)?; }
+            "colors.hint" => { theme.hint = style(text)?; }
+            "colors.cursor" => { theme.cursor = style(text)?; }
+            "grid.lines" => {
+                let text = text.ok_or_else(needs_string)?;
+                theme.lines = Lines::from_name(&text).ok_or_else(|| format!("unknown lines {:?}", text))?;
+            }
+            "keys.digits" => {
+                let text = text.ok_or_else(needs_string)?;
+                if text.is_empty() || text.chars().any(|c| !c.is_ascii_graphic()) {
+                    return Err(format!("invalid digit keys {:?}", text));
+                }
+                keys.digits = Some(text.chars().collect());
+            }
+            _ => {
+                let bound = name.strip_prefix("keys.").and_then(action)
+                    .ok_or_else(|| format!("unknown setting {}", name))?;
+                keys.bindings.retain(|binding| binding.1 != bound);
+                for key_name in names {
+                    let key = key(&key_name).ok_or_else(|| format!("unknown key {:?} of {}", key_name, name))?;
+                    keys.bindings.push((key, bound));
+                }
+            }
+        }
+    }
+    // A key does one thing.
+    for (i, &(key, bound)) in keys.bindings.iter().enumerate() {
+        let digit = match (key, &keys.digits) {
+            (Key::Char(c), Some(digits)) if digits.contains(&c) => Some(Action::Digit(0)),
+            _ => None,
+        };
+        let other = keys.bindings[i + 1 ..].iter().find(|binding| binding.0 == key && binding.1 != bound);
+        if let Some(other) = other.map(|binding| binding.1).or(digit) {
+            return Err(format!("the key {} is bound to both {} and {}", name(key), action_name(bound),
+                               action_name(other)));
+        }
+    }
+    return Ok(Config { theme, keys });
+}
+
+/// Return the config file: `sudoku-rs/config.toml` in
+/// `$XDG_CONFIG_HOME`, or else in `~/.config`; None without a home.
+pub fn path() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").filter(|dir| !dir.is_empty())?).join(".config"),
+    };
+    return Some(config.join("sudoku-rs").join("config.toml"));
+}
+
+/// Return the settings in the file at `path`, or those by default if
+/// there is none.
+pub fn read(path: Option<PathBuf>) -> Result<Config, String> {
+    let path = match path.filter(|path| path.exists()) {
+        Some(path) => path,
+        None => { return from_text(""); }
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    return from_text(&text).map_err(|msg| format!("{}: {}", path.display(), msg));
+}
+
+#[test]
+fn test_parse() {
+    let text = "# The keys.\n[keys]\nup = [\"up\", 'k', ] # Vim\nquit = \"\\\"\"\n\n[grid]\nlines = \"light\"\n";
+    assert_eq!(parse(text), Ok(vec![
+        ("keys.up".to_string(), Value::List(vec!["up".to_string(), "k".to_string()])),
+        ("keys.quit".to_string(), Value::Str("\"".to_string())),
+        ("grid.lines".to_string(), Value::Str("light".to_string())),
+    ]));
+    assert_eq!(parse("[keys]\nup = \"k"), Err("line 2: unclosed string".to_string()));
+    assert!(parse("up = \"k\" j").is_err() && parse("up = [\"k\" \"j\"]").is_err() && parse("up").is_err());
+}
+
+#[test]
+fn test_config() {
+    let config = from_text("[colors]\ncursor = \"bold on-cyan\"\n[grid]\nlines = \"light\"\n\
+                            [keys]\nleft = [\"h\", \"left\"]\nhint = \"?\"\ndigits = \"!@#$%^&*(\"\n").unwrap();
+    assert_eq!(config.theme.cursor, "\x1b[1;46m");
+    assert_eq!(config.theme.lines.border(Edge::Top, 3, 2), "┌───┬───┐");
+    let keys = config.keys;
+    assert_eq!((keys.action(Key::Char('h')), keys.action(Key::Left)), (Some(Action::Left), Some(Action::Left)));
+    assert_eq!((keys.action(Key::Char('?')), keys.action(Key::Char('#'))), (Some(Action::Hint), Some(Action::Digit(3))));
+    assert_eq!((keys.action(Key::Char('3')), keys.action(Key::Char('m'))), (None, Some(Action::Marks)));
+    assert!(keys.check(9).is_ok() && keys.check(16).is_err());
+    assert!(keys.help().starts_with("up/down/h/right: move  !..(: fill in  0: erase"));
+
+    let keys = from_text("").unwrap().keys;
+    assert_eq!((keys.action(Key::Char('G')), keys.action(Key::Char('g'))), (Some(Action::Digit(16)), None));
+    assert!(keys.help().starts_with("arrows: move  digits: fill in  0: erase  m: marks"));
+    // The h of the hint stays bound.
+    assert_eq!(from_text("[keys]\nleft = \"h\"\n").err(), Some("the key h is bound to both hint and left".to_string()));
+    assert!(from_text("[keys]\ndigits = \"asdfmghjk\"\n").is_err());
+    assert!(from_text("[keys]\njump = \"j\"\n").is_err() && from_text("[colors]\ngiven = \"plaid\"\n").is_err());
+}
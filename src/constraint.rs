@@ -0,0 +1,101 @@
+// The constraints of a puzzle, which all work the same way: they
+// remove from the cells the candidates that cannot satisfy them.
+
+use super::{CandidateSet, EMPTY_SET};
+
+/// A rule that the digits of some cells must satisfy.  Rules are
+/// shared by the threads of the generator.
+pub trait Constraint: Send + Sync {
+    /// Remove from `cells` the candidates that cannot satisfy the
+    /// constraint.  Return whether any candidate was removed.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool;
+}
+
+/// A set of cells whose digits must all be different, such as a
+/// row, a column or a box.
+pub struct AllDifferent {
+    pub cells: Vec<usize>,
+}
+
+impl AllDifferent {
+    pub fn new(cells: Vec<usize>) -> Self {
+        AllDifferent { cells }
+    }
+}
+
+impl Constraint for AllDifferent {
+    /// Remove the digits of the solved cells from the other cells;
+    /// cells solved with the same digit lose it.  When the cells
+    /// have as many candidates between them as there are cells,
+    /// every candidate must appear, so a candidate of a single cell
+    /// is that cell's digit (a hidden single); with fewer candidates
+    /// than cells, the cells cannot be filled at all.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        let mut solved = EMPTY_SET;
+        let mut repeated = EMPTY_SET;
+        let mut once = EMPTY_SET;
+        let mut twice = EMPTY_SET;
+        for &cell in self.cells.iter() {
+            let candidates = cells[cell];
+            if candidates.count_ones() == 1 {
+                repeated |= solved & candidates;
+                solved |= candidates;
+            }
+            twice |= once & candidates;
+            once |= candidates;
+        }
+        let ndigits = once.count_ones() as usize;
+        let hidden = if ndigits == self.cells.len() { once & !twice } else { EMPTY_SET };
+        let mut changed = false;
+        for &cell in self.cells.iter() {
+            let mut q = if ndigits < self.cells.len() {
+                EMPTY_SET
+            } else if cells[cell].count_ones() == 1 {
+                cells[cell] & !repeated
+            } else {
+                cells[cell] & !solved
+            };
+            if q & hidden != 0 {
+                q &= hidden;
+            }
+            changed = changed || q != cells[cell];
+            cells[cell] = q;
+        }
+        return changed;
+    }
+}
+
+/// A set of cells that may only hold some digits, e.g., the even ones.
+#[cfg(feature = "variants")]
+pub struct Restriction {
+    pub cells: Vec<usize>,
+    pub digits: CandidateSet,
+}
+
+#[cfg(feature = "variants")]
+impl Constraint for Restriction {
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        let mut changed = false;
+        for &cell in self.cells.iter() {
+            changed = changed || cells[cell] & !self.digits != 0;
+            cells[cell] &= self.digits;
+        }
+        return changed;
+    }
+}
+
+#[test]
+fn test_all_different() {
+    let unit = AllDifferent::new(vec![0, 1, 2, 3]);
+    let mut cells = vec![0b001, 0b011, 0b111, 0b1111];
+    assert!(unit.prune(&mut cells));
+    assert_eq!(cells, vec![0b001, 0b010, 0b110, 0b1000]);
+    let unit = AllDifferent::new(vec![0, 1, 2]);
+    let mut cells = vec![0b001, 0b001, 0b111];
+    assert!(unit.prune(&mut cells));
+    assert_eq!(cells, vec![0, 0, 0b110]);
+    // Only the last cell can hold a 3.
+    let mut cells = vec![0b011, 0b011, 0b111];
+    assert!(unit.prune(&mut cells));
+    assert_eq!(cells, vec![0b011, 0b011, 0b100]);
+}
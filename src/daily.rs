@@ -0,0 +1,61 @@
+// The daily puzzle: the seed of the generator is taken from the date,
+// in UTC, and the difficulty, so that every player gets the same
+// puzzle on the same day, and can share how they did without giving
+// it away.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use logic::Difficulty;
+use play;
+
+/// Return the days from 1970-01-01 to today, in UTC.
+pub fn today() -> u64 {
+    let since = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+    return since.as_secs() / 86400;
+}
+
+/// Return the date of the day `days` after 1970-01-01, e.g.,
+/// "2024-02-29".
+pub fn date(days: u64) -> String {
+    // The civil calendar from days, by eras of 400 years starting on
+    // March 1st (H. Hinnant, "chrono-compatible low-level date
+    // algorithms").
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{:04}-{:02}-{:02}", year, month, day);
+}
+
+/// Return the seed of the puzzle of the day `days` after 1970-01-01
+/// of `difficulty`, or of any difficulty.
+pub fn seed(days: u64, difficulty: Option<Difficulty>) -> u64 {
+    let level = difficulty.map_or(0, |difficulty| difficulty as u64 + 1);
+    return days * 8 + level;
+}
+
+/// Return the line a player shares once the puzzle of the day `days`
+/// after 1970-01-01 of `difficulty` is solved in `elapsed` with
+/// `mistakes` mistakes and `score` points.
+pub fn share(days: u64, difficulty: Option<Difficulty>, elapsed: Duration, mistakes: usize, score: u64) -> String {
+    return format!("sudoku-rs daily {}{}: solved in {}, {} mistake{}, {} points",
+                   date(days), difficulty.map_or(String::new(), |difficulty| format!(" ({})", difficulty.name())),
+                   play::clock(elapsed), mistakes, if mistakes == 1 { "" } else { "s" }, score);
+}
+
+#[test]
+fn test_daily() {
+    assert_eq!(date(0), "1970-01-01");
+    assert_eq!(date(11016), "2000-02-29");
+    assert_eq!(date(19782), "2024-02-29");
+    assert_eq!(date(20742), "2026-10-16");
+    assert_ne!(seed(20742, None), seed(20742, Some(Difficulty::Easy)));
+    assert_ne!(seed(20742, Some(Difficulty::Diabolical)), seed(20743, None));
+    assert_eq!(share(19782, Some(Difficulty::Hard), Duration::from_secs(754), 1, 2146),
+               "sudoku-rs daily 2024-02-29 (hard): solved in 12:34, 1 mistake, 2146 points");
+}
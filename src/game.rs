@@ -0,0 +1,377 @@
+// A game of sudoku in progress: the givens of a puzzle and the digits
+// and pencil marks a player has entered on it, whatever draws it on
+// the screen.  The givens cannot be changed, and the mistakes are
+// those found by the validator of `check`.  Every move is kept in a
+// journal, with what it changed, so that the moves can be undone and
+// redone whatever the front end.  The digits entered that disagree
+// with the solution are counted, and cost points.
+
+use std::time::Duration;
+
+use super::{CandidateSet, Shape, SudokuBoard, EMPTY_SET};
+use check::{self, Conflict};
+use logic;
+
+/// The points of a puzzle of each difficulty, from easy to diabolical.
+const POINTS: [u64; 5] = [1000, 2000, 3000, 4000, 5000];
+/// The points a digit disagreeing with the solution costs.
+const MISTAKE: u64 = 100;
+
+/// A puzzle being played.
+pub struct Game<'a> {
+    /// The givens of the puzzle.
+    puzzle: SudokuBoard<'a>,
+    /// The givens and the digits entered; the other cells are full.
+    grid: SudokuBoard<'a>,
+    /// The pencil marks of the cells without a digit.
+    marks: Vec<CandidateSet>,
+    /// The solution, if the puzzle has a unique one.
+    solution: Option<SudokuBoard<'a>>,
+    /// The digits entered that disagree with the solution, even those
+    /// erased or undone since.
+    mistakes: usize,
+    /// The moves made, the last one last.
+    history: Vec<Vec<Change>>,
+    /// The moves undone since the last move made, the last one undone
+    /// last.
+    undone: Vec<Vec<Change>>,
+}
+
+/// What a move changed in a cell: its candidates in the grid and its
+/// marks, before and after.
+#[derive(Clone, Copy)]
+struct Change {
+    cell: usize,
+    before: (CandidateSet, CandidateSet),
+    after: (CandidateSet, CandidateSet),
+}
+
+impl<'a> Game<'a> {
+    /// Start playing `puzzle`.
+    pub fn new(puzzle: SudokuBoard<'a>) -> Self {
+        let grid = puzzle.clone();
+        let marks = vec![EMPTY_SET; puzzle.cells.len()];
+        let solution = if puzzle.count_solutions(2) == 1 { puzzle.solve() } else { None };
+        return Game { puzzle, grid, marks, solution, mistakes: 0, history: Vec::new(), undone: Vec::new() };
+    }
+
+    /// Resume playing `puzzle`, with the digits of `grid` entered on
+    /// it, `marks` marked and `mistakes` made, as saved.  Return why
+    /// they do not fit the puzzle otherwise.
+    pub fn restore(puzzle: SudokuBoard<'a>, grid: SudokuBoard<'a>, marks: Vec<CandidateSet>, mistakes: usize)
+                   -> Result<Self, String> {
+        if (0 .. puzzle.cells.len()).any(|cell| puzzle.cell_solved(cell) && grid.cells[cell] != puzzle.cells[cell]) {
+            return Err("the digits entered change the givens".to_string());
+        }
+        let mut game = Game::new(puzzle);
+        game.grid = grid;
+        game.marks = marks;
+        game.mistakes = mistakes;
+        return Ok(game);
+    }
+
+    /// Return the givens of the puzzle, as a string of digits.
+    pub fn givens(&self) -> String {
+        return self.puzzle.to_str();
+    }
+
+    /// Return the givens and the digits entered, as a string of
+    /// digits.
+    pub fn entries(&self) -> String {
+        return self.grid.to_str();
+    }
+
+    /// Return the shape of the grid.
+    pub fn shape(&self) -> Shape {
+        return self.puzzle.rules.shape;
+    }
+
+    /// Whether `cell` holds a given.
+    pub fn given(&self, cell: usize) -> bool {
+        return self.puzzle.cell_solved(cell);
+    }
+
+    /// Return the digit in `cell`, counting from 1, if any.
+    pub fn digit(&self, cell: usize) -> Option<usize> {
+        if !self.grid.cell_solved(cell) {
+            return None;
+        }
+        return Some(self.grid.cells[cell].trailing_zeros() as usize + 1);
+    }
+
+    /// Return the pencil marks of `cell`.
+    pub fn marks(&self, cell: usize) -> CandidateSet {
+        return self.marks[cell];
+    }
+
+    /// Return the pencil marks of every cell.
+    pub fn all_marks(&self) -> &[CandidateSet] {
+        return &self.marks;
+    }
+
+    /// Enter `digit`, counting from 1, in `cell`, and take it off the
+    /// marks of the cells that must hold other digits.  Return whether
+    /// it was entered: the givens stay.
+    pub fn place(&mut self, cell: usize, digit: usize) -> bool {
+        if self.given(cell) || digit == 0 || digit > self.shape().side() {
+            return false;
+        }
+        let wrong = self.solution.as_ref().is_some_and(|solution| solution.cells[cell] != 1 << (digit - 1));
+        if wrong && self.digit(cell) != Some(digit) {
+            self.mistakes += 1;
+        }
+        self.journal(|game| {
+            game.grid.cells[cell] = 1 << (digit - 1);
+            game.marks[cell] = EMPTY_SET;
+            for &peer in game.puzzle.rules.neighbors[cell].iter() {
+                game.marks[peer] &= !(1 << (digit - 1));
+            }
+        });
+        return true;
+    }
+
+    /// Add `digit`, counting from 1, to the marks of `cell`, or take
+    /// it off.  Return whether the marks changed: cells with a digit
+    /// have none.
+    pub fn toggle_mark(&mut self, cell: usize, digit: usize) -> bool {
+        if self.digit(cell).is_some() || digit == 0 || digit > self.shape().side() {
+            return false;
+        }
+        self.journal(|game| game.marks[cell] ^= 1 << (digit - 1));
+        return true;
+    }
+
+    /// Mark in every cell without a digit the digits that no cell
+    /// which must hold another digit holds.  Unlike `propagate`, which
+    /// would solve many puzzles outright, only the digits of the grid
+    /// rule out marks.
+    pub fn fill_marks(&mut self) {
+        self.journal(|game| {
+            for cell in 0 .. game.marks.len() {
+                if game.digit(cell).is_none() {
+                    game.marks[cell] = game.puzzle.rules.neighbors[cell].iter()
+                        .filter_map(|&peer| game.digit(peer))
+                        .fold(game.shape().full_set(), |set, d| set & !(1 << (d - 1)));
+                }
+            }
+        });
+    }
+
+    /// Erase the digit entered in `cell`.  Return whether there was
+    /// one.
+    pub fn erase(&mut self, cell: usize) -> bool {
+        if self.given(cell) || self.digit(cell).is_none() {
+            return false;
+        }
+        let full = self.shape().full_set();
+        self.journal(|game| game.grid.cells[cell] = full);
+        return true;
+    }
+
+    /// Make a move with `play`, and keep what it changed in the
+    /// journal; the moves undone can no longer be redone.
+    fn journal<F: FnOnce(&mut Self)>(&mut self, play: F) {
+        let (cells, marks) = (self.grid.cells.clone(), self.marks.clone());
+        play(self);
+        let changes: Vec<Change> = (0 .. marks.len())
+            .filter(|&cell| cells[cell] != self.grid.cells[cell] || marks[cell] != self.marks[cell])
+            .map(|cell| Change {
+                cell,
+                before: (cells[cell], marks[cell]),
+                after: (self.grid.cells[cell], self.marks[cell]),
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.history.push(changes);
+            self.undone.clear();
+        }
+    }
+
+    /// Take back the last move made.  Return whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let changes = match self.history.pop() {
+            Some(changes) => changes,
+            None => { return false; }
+        };
+        for change in changes.iter() {
+            (self.grid.cells[change.cell], self.marks[change.cell]) = change.before;
+        }
+        self.undone.push(changes);
+        return true;
+    }
+
+    /// Make again the last move undone.  Return whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let changes = match self.undone.pop() {
+            Some(changes) => changes,
+            None => { return false; }
+        };
+        for change in changes.iter() {
+            (self.grid.cells[change.cell], self.marks[change.cell]) = change.after;
+        }
+        self.history.push(changes);
+        return true;
+    }
+
+    /// Return the number of digits entered that disagree with the
+    /// solution of the puzzle.
+    pub fn mistakes(&self) -> usize {
+        return self.mistakes;
+    }
+
+    /// Return the score of the game solved in `elapsed`: the points of
+    /// the difficulty of the puzzle, less a point a second and the
+    /// points of the mistakes.
+    pub fn score(&self, elapsed: Duration) -> u64 {
+        let points = POINTS[logic::rate(&self.puzzle) as usize];
+        return points.saturating_sub(elapsed.as_secs() + MISTAKE * self.mistakes as u64);
+    }
+
+    /// Return the next digit a human solver would enter on the grid,
+    /// as a step of the hardest technique it takes: the removals of
+    /// candidates on the way are followed until a cell is left with a
+    /// single digit, and their cells are those of the step.  Return
+    /// None if a digit entered is wrong, or if no technique applies.
+    pub fn hint(&self) -> Option<logic::Hint> {
+        if !self.conflicts().is_empty() {
+            return None;
+        }
+        let mut board = self.grid.clone();
+        let (mut technique, mut cells) = (logic::Technique::Singles, Vec::new());
+        loop {
+            let step = logic::hint(&board)?;
+            technique = technique.max(step.technique);
+            cells.extend(step.cells.iter().cloned());
+            let mut placements = step.placements;
+            for &(cell, digit) in step.eliminations.iter() {
+                board.cells[cell] &= !(1 << (digit - 1));
+                if board.cell_solved(cell) {
+                    placements.push((cell, board.cells[cell].trailing_zeros() as usize + 1));
+                }
+            }
+            if let Some(&(cell, digit)) = placements.first() {
+                cells.push(cell);
+                cells.sort();
+                cells.dedup();
+                return Some(logic::Hint {
+                    technique,
+                    cells,
+                    digits: vec![digit],
+                    eliminations: Vec::new(),
+                    placements: vec![(cell, digit)],
+                });
+            }
+        }
+    }
+
+    /// Return the mistakes among the digits of the grid.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        return check::conflicts(&self.puzzle, &self.grid);
+    }
+
+    /// Whether the grid is filled in and solves the puzzle.
+    pub fn complete(&self) -> bool {
+        return check::verify(&self.puzzle, &self.grid).is_ok();
+    }
+}
+
+#[test]
+fn test_game() {
+    use super::{standard_rules, SOLUTION};
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules));
+    assert!(!game.place(2, 1) && game.digit(2) == Some(3));
+    assert!(game.place(0, 8) && game.place(1, 4));
+    assert_eq!(game.conflicts().len(), 4);
+    assert!(!game.complete());
+    assert!(game.erase(0) && !game.erase(0) && game.digit(0).is_none());
+    assert!(game.place(0, 4) && game.place(1, 8));
+    assert!(game.conflicts().is_empty() && game.complete());
+}
+
+#[test]
+fn test_mistakes() {
+    use super::{standard_rules, SOLUTION};
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules));
+    // Entering the same wrong digit again is not a new mistake.
+    game.place(0, 8);
+    game.place(0, 8);
+    game.place(0, 4);
+    game.undo();
+    game.place(0, 1);
+    game.place(1, 8);
+    assert_eq!(game.mistakes(), 2);
+    assert_eq!(game.score(Duration::from_secs(60)), 1000 - 60 - 200);
+    assert_eq!(game.score(Duration::from_secs(3600)), 0);
+}
+
+#[test]
+fn test_marks() {
+    use super::{standard_rules, SOLUTION};
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("...{}", &solution[3 ..]), &rules));
+    assert!(game.toggle_mark(0, 4) && game.toggle_mark(0, 8) && game.toggle_mark(0, 3));
+    assert!(game.toggle_mark(0, 3) && !game.toggle_mark(3, 1));
+    assert_eq!(game.marks(0), 0b1000_1000);
+    // The columns leave a single candidate in each cell.
+    game.fill_marks();
+    assert_eq!((game.marks(0), game.marks(2)), (0b0000_1000, 0b0000_0100));
+    assert_eq!(game.marks(12), 0);
+    game.toggle_mark(1, 4);
+    // The 4 of r1c1 goes off the marks of r1c2.
+    assert!(game.place(0, 4));
+    assert_eq!((game.marks(0), game.marks(1)), (0, 0b1000_0000));
+}
+
+#[test]
+fn test_undo() {
+    use super::{standard_rules, SOLUTION};
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules));
+    assert!(!game.undo() && !game.redo());
+    game.toggle_mark(1, 8);
+    game.place(0, 8);
+    game.place(0, 4);
+    game.erase(0);
+    // Erasing, then placing the 4, then the 8 that took the mark of r1c2.
+    assert!(game.undo() && game.digit(0) == Some(4));
+    assert!(game.undo() && game.undo());
+    assert_eq!((game.digit(0), game.marks(1)), (None, 0b1000_0000));
+    assert!(game.redo() && game.digit(0) == Some(8) && game.marks(1) == 0);
+    // A new move drops the moves undone.
+    game.place(1, 4);
+    assert!(!game.redo());
+    assert!(game.undo() && game.undo() && game.undo() && !game.undo());
+    assert_eq!((game.digit(0), game.digit(1), game.marks(1)), (None, None, 0));
+}
+
+#[test]
+fn test_hint() {
+    use super::{standard_rules, SOLUTION};
+    use logic::Technique;
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules));
+    let hint = game.hint().unwrap();
+    assert_eq!((hint.technique, hint.cells, hint.placements), (Technique::Singles, vec![0], vec![(0, 4)]));
+    // No hint builds on a wrong digit.
+    game.place(1, 4);
+    assert!(game.hint().is_none());
+
+    // A pair takes the candidates that leave r3c4 a single 6.
+    let mut game = Game::new(SudokuBoard::from_str(
+        ".418.5..9..9.7..58.3............26..3...6..25...5...97.....69.2.1..4......7......", &rules));
+    let hint = game.hint().unwrap();
+    assert_eq!((hint.technique, hint.placements), (Technique::Pairs, vec![(21, 6)]));
+    assert!(hint.cells.len() > 1 && game.place(21, 6) && game.mistakes() == 0);
+}
@@ -0,0 +1,860 @@
+// Puzzle generation: fill a random grid, then dig out its clues
+// as long as the solution stays unique, the cells related by a
+// symmetry of the grid together.  A requested difficulty
+// puts clues back until the logic solver rates the puzzle no
+// harder, and throws away the puzzles that turn out easier;
+// requested techniques get clues back until they solve the puzzle.
+// A puzzle isomorphic to one already written, or to one of a file
+// of puzzles to avoid, is thrown away.  A pattern can fix the cells
+// that hold clues, those that stay blank, and those to dig.
+// Minimal puzzles, where every clue is needed, keep the clues
+// they are dug to.  Puzzles with too many or too few clues are
+// thrown away too.  Jigsaw puzzles get random regions of their own,
+// and killer puzzles random cages.
+// The search for the hardest puzzles evolves a puzzle instead,
+// keeping the mutations that rate no easier.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{error, random_jigsaw, techniques_value, threads_value, value, CandidateSet, Rules, Shape, SudokuBoard};
+#[cfg(feature = "variants")]
+use super::random_killer;
+use cells::Cells;
+use canonical::{canonical, Isomorphisms};
+use logic::{self, Difficulty, Technique};
+use rng::Rng;
+use symmetry::Symmetry;
+
+/// Number of puzzles tried for each one of a requested difficulty.
+const ATTEMPTS: usize = 1000;
+
+/// Number of mutations of a puzzle that rate no harder before the
+/// search for the hardest puzzles starts over from a new one.
+const STALE: usize = 200;
+
+/// Where the rules of generated puzzles come from.
+pub enum Source<'a> {
+    /// The same rules for every puzzle.
+    Shared(&'a Rules),
+    /// Random regions for every puzzle on a grid of the shape, and
+    /// the function making rules from their region map.
+    Jigsaw(Shape, &'a (dyn Fn(&str) -> Rules + Sync)),
+    /// Random cages for every puzzle on a 9x9 grid, and the function
+    /// making rules from their cage map and sums.
+    #[cfg(feature = "variants")]
+    Killer(&'a (dyn Fn(&str) -> Rules + Sync)),
+}
+
+impl<'a> Source<'a> {
+    fn shape(&self) -> Shape {
+        match *self {
+            Source::Shared(rules) => rules.shape,
+            Source::Jigsaw(shape, _) => shape,
+            #[cfg(feature = "variants")]
+            Source::Killer(_) => Shape::STANDARD,
+        }
+    }
+
+    /// Number of cells of the puzzles.
+    fn cells(&self) -> usize {
+        match *self {
+            Source::Shared(rules) => rules.neighbors.len(),
+            Source::Jigsaw(shape, _) => shape.cells(),
+            #[cfg(feature = "variants")]
+            Source::Killer(_) => Shape::STANDARD.cells(),
+        }
+    }
+}
+
+/// What a pattern allows in a cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mark {
+    /// The cell holds a clue.
+    Clue,
+    /// The cell may hold a clue.
+    Either,
+    /// The cell is blank.
+    Blank,
+}
+
+/// Read a pattern, one character per cell: `X` (or `#`) for a clue,
+/// `?` for a cell to dig and `.` for a blank cell.
+fn parse_pattern(pattern: &str) -> Option<Vec<Mark>> {
+    pattern.chars()
+        .map(|c| match c {
+            'X' | 'x' | '#' => Some(Mark::Clue),
+            '?' => Some(Mark::Either),
+            '.' => Some(Mark::Blank),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The settings of the `generate` command.
+pub struct Settings {
+    /// Number of puzzles to generate.
+    pub count: usize,
+    /// Seed of the random numbers; the clock gives one by default.
+    /// Every puzzle gets a seed of its own derived from it.
+    pub seed: Option<u64>,
+    /// Number of threads generating puzzles.
+    pub threads: usize,
+    /// Difficulty of the puzzles; any by default.
+    pub difficulty: Option<Difficulty>,
+    /// Techniques that must solve the puzzles; any by default.
+    pub techniques: Option<Vec<Technique>>,
+    /// Symmetry of the clues.
+    pub symmetry: Symmetry,
+    /// Whether every clue must be needed for a unique solution.
+    pub minimal: bool,
+    /// Least and greatest number of clues; any by default.
+    pub clues: Option<(usize, usize)>,
+    /// File of puzzles not to write again.
+    pub avoid: Option<String>,
+    /// Pattern of the clues; any cell can be dug by default.
+    pub pattern: Option<Vec<Mark>>,
+    /// Whether to search for the hardest puzzles instead.
+    pub maximize: bool,
+    /// Number of mutations of the search; no limit by default.
+    pub generations: Option<usize>,
+    /// Whether to write a summary of the run to the standard error.
+    pub summary: bool,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings { count: 1, seed: None, threads: 1, difficulty: None, techniques: None, symmetry: Symmetry::None, minimal: false, clues: None, avoid: None, pattern: None,
+                   maximize: false, generations: None, summary: false }
+    }
+
+    /// Read the generator option `arg`, taking its value from `args`.
+    /// Return false if `arg` is not a generator option.
+    pub fn parse_arg<I: Iterator<Item = String>>(&mut self, arg: &str, args: &mut I) -> bool {
+        match arg {
+            "--count" => { self.count = value(arg, args); }
+            "--seed" => { self.seed = Some(value(arg, args)); }
+            "--threads" => { self.threads = threads_value(arg, args); }
+            "--difficulty" => {
+                let name: String = value(arg, args);
+                self.difficulty = Some(Difficulty::from_name(&name)
+                    .unwrap_or_else(|| error(format!("unknown difficulty {:?}", name))));
+            }
+            "--solvable-with" => { self.techniques = Some(techniques_value(arg, args)); }
+            "--symmetry" => {
+                let name: String = value(arg, args);
+                self.symmetry = Symmetry::from_name(&name)
+                    .unwrap_or_else(|| error(format!("unknown symmetry {:?}", name)));
+            }
+            "--minimal" => { self.minimal = true; }
+            "--clues" => {
+                let range: String = value(arg, args);
+                self.clues = Some(parse_range(&range)
+                    .unwrap_or_else(|| error(format!("invalid number of clues {:?}", range))));
+            }
+            "--avoid" => { self.avoid = Some(value(arg, args)); }
+            "--pattern" => {
+                let pattern: String = value(arg, args);
+                self.pattern = Some(parse_pattern(&pattern)
+                    .unwrap_or_else(|| error(format!("invalid pattern {:?}", pattern))));
+            }
+            "--maximize-difficulty" => { self.maximize = true; }
+            "--generations" => { self.generations = Some(value(arg, args)); }
+            "--summary" => { self.summary = true; }
+            _ => { return false; }
+        }
+        return true;
+    }
+
+    /// Return what the pattern allows in `cell`.
+    fn mark(&self, cell: usize) -> Mark {
+        self.pattern.as_ref().map_or(Mark::Either, |pattern| pattern[cell])
+    }
+
+    /// Return the orbits of the symmetry on a grid of `shape` with
+    /// `cells` cells whose cells the pattern lets dig.
+    fn diggable(&self, shape: Shape, cells: usize) -> Vec<Vec<usize>> {
+        let mut orbits: Vec<Vec<usize>> = if self.symmetry == Symmetry::None {
+            (0 .. cells).map(|cell| vec![cell]).collect()
+        } else {
+            self.symmetry.orbits(shape)
+        };
+        orbits.retain(|orbit| orbit.iter().all(|&cell| self.mark(cell) == Mark::Either));
+        return orbits;
+    }
+
+    /// Whether a puzzle rated `rating` is no harder than the
+    /// difficulty and the techniques asked for.
+    fn easy_enough(&self, board: &SudokuBoard, rating: Difficulty) -> bool {
+        if self.difficulty.is_some_and(|difficulty| rating > difficulty) {
+            return false;
+        }
+        return match self.techniques {
+            Some(ref techniques) => logic::solve(board, techniques).0.solved(),
+            None => true,
+        };
+    }
+}
+
+/// Read a number, e.g., `24`, or an inclusive range, e.g., `22-26`.
+pub fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let mut bounds = range.splitn(2, '-').map(str::parse::<usize>);
+    let least = bounds.next()?.ok()?;
+    let greatest = match bounds.next() {
+        Some(bound) => bound.ok()?,
+        None => least,
+    };
+    if least > greatest {
+        return None;
+    }
+    return Some((least, greatest));
+}
+
+/// Return the number of clues of a puzzle.
+fn clue_count(digits: &str) -> usize {
+    digits.chars().filter(|&c| c != '.').count()
+}
+
+/// Generate a puzzle with a unique solution under `rules`: fill a
+/// random grid, blank the cells that the pattern leaves blank, then
+/// remove the other clues in a random order, an orbit of the symmetry
+/// at a time, putting back those whose removal allows a second
+/// solution, and stopping at the least number of clues.  Clues of
+/// the pattern stay.  Return the puzzle and its rating, or None if the rules
+/// admit no solution or it cannot be brought to the difficulty and
+/// number of clues of `settings`.
+pub fn puzzle(rules: &Rules, settings: &Settings, rng: &mut Rng) -> Option<(String, Difficulty)> {
+    let solution = SudokuBoard::random_solution(rules, rng)?;
+    return dig(rules, &solution.cells, settings, rng);
+}
+
+/// Dig a puzzle out of the `solution` grid, as `puzzle` does.
+fn dig(rules: &Rules, solution: &[CandidateSet], settings: &Settings, rng: &mut Rng) -> Option<(String, Difficulty)> {
+    let full_set = rules.shape.full_set();
+    let mut board = SudokuBoard { cells: Cells::from(solution), rules };
+    for cell in (0 .. board.cells.len()).filter(|&c| settings.mark(c) == Mark::Blank) {
+        board.cells[cell] = full_set;
+    }
+    if settings.pattern.is_some() && board.count_solutions(2) != 1 {
+        return None;
+    }
+
+    let mut orbits = settings.diggable(rules.shape, board.cells.len());
+    rng.shuffle(&mut orbits);
+    // Minimal puzzles dig as deep as they go.
+    let least = settings.clues.filter(|_| !settings.minimal).map_or(0, |(least, _)| least);
+    let mut clues = (0 .. board.cells.len()).filter(|&c| board.cell_solved(c)).count();
+    let mut removed = Vec::new();
+    for orbit in orbits {
+        if clues < least + orbit.len() {
+            continue;
+        }
+        for &cell in orbit.iter() {
+            board.cells[cell] = full_set;
+        }
+        if board.count_solutions(2) == 1 {
+            clues -= orbit.len();
+            removed.push(orbit);
+        } else {
+            for &cell in orbit.iter() {
+                board.cells[cell] = solution[cell];
+            }
+        }
+    }
+
+    // A clue of a symmetric puzzle may be needed only because
+    // its image is.
+    if settings.minimal && settings.symmetry != Symmetry::None {
+        let mut clues: Vec<usize> = (0 .. board.cells.len())
+            .filter(|&c| board.cell_solved(c) && settings.mark(c) == Mark::Either)
+            .collect();
+        rng.shuffle(&mut clues);
+        for cell in clues {
+            board.cells[cell] = full_set;
+            if board.count_solutions(2) != 1 {
+                board.cells[cell] = solution[cell];
+            }
+        }
+    }
+
+    // Clues only make a puzzle easier, so put them back while
+    // it is too hard, unless it must stay minimal.
+    let mut rating = logic::rate(&board);
+    if !settings.easy_enough(&board, rating) {
+        if settings.minimal {
+            return None;
+        }
+        rng.shuffle(&mut removed);
+        while !settings.easy_enough(&board, rating) {
+            // The blank cells of a pattern may keep it too hard.
+            for cell in removed.pop()? {
+                board.cells[cell] = solution[cell];
+            }
+            rating = logic::rate(&board);
+        }
+    }
+    if settings.difficulty.is_some_and(|difficulty| rating < difficulty) {
+        return None;
+    }
+    let digits = board.to_str();
+    if let Some((least, greatest)) = settings.clues {
+        let clues = clue_count(&digits);
+        if clues < least || clues > greatest {
+            return None;
+        }
+    }
+    return Some((digits, rating));
+}
+
+/// Generate a puzzle under the rules of `source`.  Return the
+/// puzzle, its region map or cages if it has random ones, and its
+/// rating.
+fn attempt(source: &Source, settings: &Settings, rng: &mut Rng) -> Option<(String, Option<String>, Difficulty)> {
+    match *source {
+        Source::Shared(rules) => {
+            let (digits, rating) = puzzle(rules, settings, rng)?;
+            return Some((digits, None, rating));
+        }
+        Source::Jigsaw(shape, make_rules) => {
+            let (regions, grid) = random_jigsaw(shape, rng);
+            let (digits, rating) = dig(&make_rules(&regions), &grid, settings, rng)?;
+            return Some((digits, Some(regions), rating));
+        }
+        #[cfg(feature = "variants")]
+        Source::Killer(make_rules) => {
+            let (cages, grid) = random_killer(rng)?;
+            let (digits, rating) = dig(&make_rules(&cages), &grid, settings, rng)?;
+            return Some((digits, Some(cages), rating));
+        }
+    }
+}
+
+/// Generate a puzzle under the rules of `source`, as `attempt`
+/// does, trying up to `ATTEMPTS` times.  Return the puzzle, its
+/// region map, its rating and the number of tries, or None if no
+/// try succeeds.
+pub fn one(source: &Source, settings: &Settings, rng: &mut Rng)
+           -> Option<(String, Option<String>, Difficulty, usize)> {
+    for tries in 1 ..= ATTEMPTS {
+        if let Some((digits, regions, rating)) = attempt(source, settings, rng) {
+            return Some((digits, regions, rating, tries));
+        }
+    }
+    return None;
+}
+
+/// Return the error message of a failed call to `one`.
+pub fn not_found() -> String {
+    format!("no puzzle with the requested settings found in {} attempts", ATTEMPTS)
+}
+
+/// Return the canonical form of a puzzle and its region map or
+/// cages.
+fn key(digits: &str, regions: Option<&str>, source: &Source, isomorphisms: Isomorphisms) -> String {
+    let shape = source.shape();
+    match regions {
+        // The cage sums tie the digits to their values.
+        #[cfg(feature = "variants")]
+        Some(cages) if matches!(*source, Source::Killer(_)) => format!("{} {}", digits, cages),
+        // The region map ties the digits to their cells.
+        Some(regions) => format!("{} {}", canonical(digits, shape, Isomorphisms::Digits),
+                                 canonical(regions, shape, Isomorphisms::Digits)),
+        None => canonical(digits, shape, isomorphisms),
+    }
+}
+
+/// Return the canonical forms of the puzzles of the file at `path`,
+/// one per line, written in digits and followed by their region map
+/// for jigsaw puzzles or their cages for killer puzzles.
+fn read_avoided(path: &str, source: &Source, isomorphisms: Isomorphisms) -> HashSet<String> {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| error(format!("cannot read {}: {}", path, e)));
+    return text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| !fields.is_empty())
+        .map(|fields| {
+            let regions = match *source {
+                Source::Shared(_) => None,
+                Source::Jigsaw(..) => fields.get(1).map(|regions| regions.to_string()),
+                #[cfg(feature = "variants")]
+                Source::Killer(_) => Some(fields[1 ..].join(" ")),
+            };
+            key(fields[0], regions.as_deref(), source, isomorphisms)
+        })
+        .collect();
+}
+
+/// Generate the puzzles asked for by `settings` on as many threads
+/// as it asks for, and print them in order, written out by `format`,
+/// each with its rating, and the number of its clues if it is
+/// minimal or their number is requested.  Puzzles isomorphic to
+/// earlier ones under `isomorphisms` are skipped.
+pub fn run(settings: &Settings, source: &Source, isomorphisms: Isomorphisms, format: &dyn Fn(&str) -> String) {
+    if let Source::Shared(rules) = *source {
+        let empty = SudokuBoard { cells: Cells::filled(rules.shape.full_set(), rules.neighbors.len()), rules };
+        if empty.solve().is_none() {
+            error("the rules admit no solution".to_string());
+        }
+    }
+    if settings.pattern.as_ref().is_some_and(|pattern| pattern.len() != source.cells()) {
+        error(format!("invalid pattern length; expected {}", source.cells()));
+    }
+    let mut seen = match settings.avoid {
+        Some(ref path) => read_avoided(path, source, isomorphisms),
+        None => HashSet::new(),
+    };
+    if settings.maximize {
+        maximize(settings, source, format);
+        return;
+    }
+    if settings.count == 0 {
+        return;
+    }
+    let seed = settings.seed.unwrap_or_else(Rng::clock_seed);
+    let start = Instant::now();
+    let mut summary = Summary::default();
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        // The threads generate puzzles until enough are written.
+        for first in 0 .. settings.threads {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                for index in (first ..).step_by(settings.threads) {
+                    let mut rng = Rng::derive(seed, index as u64);
+                    let (digits, regions, rating, tries) = one(source, settings, &mut rng)
+                        .unwrap_or_else(|| error(not_found()));
+                    if sender.send((index, (digits, regions, rating), tries)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // The threads finish their puzzles out of order.
+        let mut finished = BTreeMap::new();
+        let (mut next, mut written, mut repeats) = (0, 0, 0);
+        'receive: for (index, found, tries) in receiver.iter() {
+            finished.insert(index, (found, tries));
+            while let Some(((digits, regions, rating), tries)) = finished.remove(&next) {
+                next += 1;
+                summary.attempts += tries;
+                if !seen.insert(key(&digits, regions.as_deref(), source, isomorphisms)) {
+                    // Small grids run out of puzzles.
+                    summary.repeats += 1;
+                    repeats += 1;
+                    if repeats == ATTEMPTS {
+                        error(format!("no new puzzle found in {} attempts after {} puzzles", ATTEMPTS, written));
+                    }
+                    continue;
+                }
+                print(&digits, regions, report(settings, &digits, rating), format);
+                summary.add(&digits, rating);
+                repeats = 0;
+                written += 1;
+                if written == settings.count {
+                    break 'receive;
+                }
+            }
+        }
+        drop(receiver);
+    });
+    if settings.summary {
+        eprint!("{}", summary.text(start.elapsed()));
+    }
+}
+
+/// What a run generated: the number of puzzles of each number of
+/// clues and of each rating, the attempts made to dig them and the
+/// puzzles thrown away as isomorphic to earlier ones.
+#[derive(Default)]
+struct Summary {
+    clues: BTreeMap<usize, usize>,
+    ratings: BTreeMap<Difficulty, usize>,
+    attempts: usize,
+    repeats: usize,
+}
+
+impl Summary {
+    /// Count a puzzle written.
+    fn add(&mut self, digits: &str, rating: Difficulty) {
+        *self.clues.entry(clue_count(digits)).or_insert(0) += 1;
+        *self.ratings.entry(rating).or_insert(0) += 1;
+    }
+
+    /// Return the summary of a run that took `elapsed`: the puzzles
+    /// written and the time each took, the share of the puzzles
+    /// thrown away, and a histogram of the clues and the ratings.
+    fn text(&self, elapsed: Duration) -> String {
+        let written: usize = self.ratings.values().sum();
+        let mut text = format!("{} puzzles in {:.2} s", written, elapsed.as_secs_f64());
+        if written > 0 {
+            text += &format!(", {:.1} ms per puzzle", 1000.0 * elapsed.as_secs_f64() / written as f64);
+        }
+        text += "\n";
+        let rejected = self.attempts - written;
+        if self.attempts > 0 {
+            text += &format!("{} of {} puzzles rejected ({:.1}%), {} as repeats\n",
+                             rejected, self.attempts, 100.0 * rejected as f64 / self.attempts as f64, self.repeats);
+        }
+        let most = self.clues.values().chain(self.ratings.values()).cloned().max().unwrap_or(0);
+        // The bars are at most 50 characters long.
+        let bar = |count: usize| "#".repeat((50 * count).div_ceil(most.max(1)));
+        text += "clues:\n";
+        for (clues, &count) in self.clues.iter() {
+            text += &format!("{:>12} {:>6} {}\n", clues, count, bar(count));
+        }
+        text += "ratings:\n";
+        for (rating, &count) in self.ratings.iter() {
+            text += &format!("{:>12} {:>6} {}\n", rating.name(), count, bar(count));
+        }
+        return text;
+    }
+}
+
+/// A puzzle evolving toward harder ones, with the solution its
+/// mutations keep, the rules of its regions if it has its own, and
+/// its score.
+struct Specimen {
+    digits: String,
+    regions: Option<String>,
+    rules: Option<Rules>,
+    solution: Cells,
+    score: (Difficulty, usize),
+}
+
+/// Return the rating of `board` and, for puzzles beyond the logic
+/// solver, the number of guesses it takes, so that the harder of
+/// two puzzles scores higher.
+fn score(board: &SudokuBoard) -> (Difficulty, usize) {
+    let rating = logic::rate(board);
+    if rating == Difficulty::Diabolical {
+        return (rating, logic::guesses(board));
+    }
+    return (rating, 0);
+}
+
+impl Specimen {
+    /// Generate a new puzzle under the rules of `source`.
+    fn new(source: &Source, settings: &Settings, rng: &mut Rng) -> Self {
+        let (digits, regions, _, _) = one(source, settings, rng).unwrap_or_else(|| error(not_found()));
+        let rules = match *source {
+            Source::Shared(_) => None,
+            Source::Jigsaw(_, make_rules) => regions.as_deref().map(make_rules),
+            #[cfg(feature = "variants")]
+            Source::Killer(make_rules) => regions.as_deref().map(make_rules),
+        };
+        let mut specimen = Specimen { digits, regions, rules, solution: Cells::filled(0, 0), score: (Difficulty::Easy, 0) };
+        let board = SudokuBoard::from_str(&specimen.digits, specimen.rules(source));
+        let (solution, score) = (board.solve().unwrap().cells, score(&board));
+        specimen.solution = solution;
+        specimen.score = score;
+        return specimen;
+    }
+
+    fn rules<'b>(&'b self, source: &'b Source) -> &'b Rules {
+        match *source {
+            Source::Shared(rules) => rules,
+            Source::Jigsaw(..) => self.rules.as_ref().unwrap(),
+            #[cfg(feature = "variants")]
+            Source::Killer(_) => self.rules.as_ref().unwrap(),
+        }
+    }
+
+    /// Return a mutant of the puzzle and its score: put back a few
+    /// orbits of its solution, then dig the clues again in a random
+    /// order, so that the mutant has the same solution.
+    fn mutate(&self, source: &Source, settings: &Settings, rng: &mut Rng) -> (String, (Difficulty, usize)) {
+        let rules = self.rules(source);
+        let mut board = SudokuBoard::from_str(&self.digits, rules);
+        let (mut clues, mut blanks): (Vec<Vec<usize>>, Vec<Vec<usize>>) = settings
+            .diggable(rules.shape, board.cells.len())
+            .into_iter()
+            .partition(|orbit| board.cell_solved(orbit[0]));
+        rng.shuffle(&mut blanks);
+        blanks.truncate(1 + rng.below(3));
+        for &cell in blanks.iter().flatten() {
+            board.cells[cell] = self.solution[cell];
+        }
+        clues.extend(blanks);
+        rng.shuffle(&mut clues);
+        for orbit in clues {
+            for &cell in orbit.iter() {
+                board.cells[cell] = rules.shape.full_set();
+            }
+            if board.count_solutions(2) != 1 {
+                for &cell in orbit.iter() {
+                    board.cells[cell] = self.solution[cell];
+                }
+            }
+        }
+        return (board.to_str(), score(&board));
+    }
+}
+
+/// Search for the hardest puzzles: mutate a puzzle, keep the mutants
+/// that score no lower, and start over from a new puzzle when the
+/// mutants stop scoring higher.  Print every puzzle that scores
+/// higher than all before it, with its rating and number of guesses,
+/// for the number of mutations of `settings` or forever.
+fn maximize(settings: &Settings, source: &Source, format: &dyn Fn(&str) -> String) {
+    let mut rng = Rng::new(settings.seed.unwrap_or_else(Rng::clock_seed));
+    let mut specimen = Specimen::new(source, settings, &mut rng);
+    let mut best = None;
+    let mut stale = 0;
+    for _ in 0 .. settings.generations.unwrap_or(usize::MAX) {
+        if best.is_none_or(|best| specimen.score > best) {
+            best = Some(specimen.score);
+            let (rating, guesses) = specimen.score;
+            let report = format!("{} {} guesses", report(settings, &specimen.digits, rating), guesses);
+            print(&specimen.digits, specimen.regions.clone(), report, format);
+        }
+        if stale == STALE {
+            specimen = Specimen::new(source, settings, &mut rng);
+            stale = 0;
+            continue;
+        }
+        let (digits, score) = specimen.mutate(source, settings, &mut rng);
+        stale = if score > specimen.score { 0 } else { stale + 1 };
+        if score >= specimen.score {
+            specimen.digits = digits;
+            specimen.score = score;
+        }
+    }
+}
+
+/// Return the rating of a generated puzzle and, if asked for, the
+/// number of its clues.
+pub fn report(settings: &Settings, digits: &str, rating: Difficulty) -> String {
+    let mut report = rating.name().to_string();
+    if settings.minimal || settings.clues.is_some() {
+        report = format!("{} {} clues", report, clue_count(digits));
+    }
+    return report;
+}
+
+/// Print a generated puzzle, followed by the `fields` of its rules,
+/// such as its region map, if it has any, with its `report`.
+pub fn print(digits: &str, fields: Option<String>, report: String, format: &dyn Fn(&str) -> String) {
+    // The report follows the digits of one-line puzzles and
+    // has a line of its own after the others.
+    let mut text = format(digits);
+    if let Some(fields) = fields {
+        text = format!("{} {}", text, fields);
+    }
+    if text.trim_end().contains('\n') {
+        println!("{}\n{}\n", text.trim_end(), report);
+    } else {
+        println!("{} {}", text, report);
+    }
+}
+
+#[test]
+fn test_puzzle() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let (digits, _) = puzzle(&rules, &Settings::new(), &mut Rng::new(1)).unwrap();
+    let board = SudokuBoard::from_str(&digits, &rules);
+    assert_eq!(board.count_solutions(2), 1);
+    // Every clue is needed.
+    for cell in (0 .. digits.len()).filter(|&c| board.cell_solved(c)) {
+        let mut fewer = SudokuBoard::from_str(&digits, &rules);
+        fewer.cells[cell] = rules.shape.full_set();
+        assert_eq!(fewer.count_solutions(2), 2);
+    }
+}
+
+#[test]
+fn test_difficulty() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let mut settings = Settings::new();
+    settings.difficulty = Some(Difficulty::Hard);
+    let mut rng = Rng::new(1);
+    let (digits, rating) = (0 .. ATTEMPTS)
+        .filter_map(|_| puzzle(&rules, &settings, &mut rng))
+        .next()
+        .unwrap();
+    assert_eq!(rating, Difficulty::Hard);
+    let board = SudokuBoard::from_str(&digits, &rules);
+    assert_eq!(logic::rate(&board), Difficulty::Hard);
+    assert_eq!(board.count_solutions(2), 1);
+}
+
+#[test]
+fn test_symmetry() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let mut settings = Settings::new();
+    for &symmetry in [Symmetry::Rotational, Symmetry::Mirror, Symmetry::Diagonal].iter() {
+        settings.symmetry = symmetry;
+        let (digits, _) = puzzle(&rules, &settings, &mut Rng::new(1)).unwrap();
+        let clues: Vec<bool> = digits.chars().map(|c| c != '.').collect();
+        for cell in 0 .. clues.len() {
+            assert_eq!(clues[cell], clues[symmetry.image(rules.shape, cell)]);
+        }
+    }
+}
+
+#[test]
+fn test_minimal() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let mut settings = Settings::new();
+    settings.symmetry = Symmetry::Rotational;
+    settings.minimal = true;
+    let (digits, _) = puzzle(&rules, &settings, &mut Rng::new(3)).unwrap();
+    for cell in (0 .. digits.len()).filter(|&c| &digits[c .. c + 1] != ".") {
+        let mut fewer = SudokuBoard::from_str(&digits, &rules);
+        fewer.cells[cell] = rules.shape.full_set();
+        assert_eq!(fewer.count_solutions(2), 2);
+    }
+}
+
+#[test]
+fn test_clues() {
+    use super::standard_rules;
+
+    assert_eq!(parse_range("24"), Some((24, 24)));
+    assert_eq!(parse_range("22-26"), Some((22, 26)));
+    assert_eq!(parse_range("26-22"), None);
+    let rules = standard_rules();
+    let mut settings = Settings::new();
+    settings.clues = Some((30, 32));
+    let mut rng = Rng::new(1);
+    let (digits, _) = (0 .. ATTEMPTS)
+        .filter_map(|_| puzzle(&rules, &settings, &mut rng))
+        .next()
+        .unwrap();
+    assert!((30 ..= 32).contains(&clue_count(&digits)));
+}
+
+#[test]
+fn test_jigsaw() {
+    use super::{parse_regions, Variant};
+
+    let make_rules = |regions: &str| Rules::for_puzzle(Shape::STANDARD, Variant::Jigsaw, &[], &[regions]).unwrap();
+    let source = Source::Jigsaw(Shape::STANDARD, &make_rules);
+    let (digits, regions, _) = attempt(&source, &Settings::new(), &mut Rng::new(1)).unwrap();
+    let regions = regions.unwrap();
+    // The regions are connected, but no longer the boxes.
+    parse_regions(Shape::STANDARD, &regions).unwrap();
+    let boxes = ["111222333", "444555666", "777888999"].iter().map(|band| band.repeat(3)).collect::<String>();
+    assert!(regions != boxes);
+    let rules = make_rules(&regions);
+    assert_eq!(SudokuBoard::from_str(&digits, &rules).count_solutions(2), 1);
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_killer() {
+    use super::Variant;
+
+    let make_rules = |fields: &str| {
+        let fields: Vec<&str> = fields.split_whitespace().collect();
+        Rules::for_puzzle(Shape::STANDARD, Variant::Killer, &[], &fields).unwrap()
+    };
+    let source = Source::Killer(&make_rules);
+    let (digits, cages, _) = attempt(&source, &Settings::new(), &mut Rng::new(1)).unwrap();
+    let rules = make_rules(&cages.unwrap());
+    assert_eq!(SudokuBoard::from_str(&digits, &rules).count_solutions(2), 1);
+}
+
+#[test]
+fn test_solvable_with() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let mut settings = Settings::new();
+    settings.techniques = Some(vec![Technique::Singles, Technique::Pairs]);
+    for seed in 0 .. 5 {
+        let (digits, _) = puzzle(&rules, &settings, &mut Rng::new(seed)).unwrap();
+        let board = SudokuBoard::from_str(&digits, &rules);
+        assert!(logic::solve(&board, &[Technique::Pairs]).0.solved());
+    }
+}
+
+#[test]
+fn test_avoid() {
+    use std::env;
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let (digits, _) = puzzle(&rules, &Settings::new(), &mut Rng::new(1)).unwrap();
+    // The same puzzle with its bands in reverse order.
+    let moved: String = digits[54 ..].chars().chain(digits[27 .. 54].chars()).chain(digits[.. 27].chars()).collect();
+    let path = env::temp_dir().join("sudoku-test-avoid.txt");
+    fs::write(&path, format!("{} hard\n", moved)).unwrap();
+    let avoided = read_avoided(path.to_str().unwrap(), &Source::Shared(&rules), Isomorphisms::Sudoku);
+    fs::remove_file(&path).unwrap();
+    assert!(avoided.contains(&key(&digits, None, &Source::Shared(&rules), Isomorphisms::Sudoku)));
+}
+
+#[test]
+fn test_pattern() {
+    use super::standard_rules;
+
+    assert_eq!(parse_pattern("X?."), Some(vec![Mark::Clue, Mark::Either, Mark::Blank]));
+    assert_eq!(parse_pattern("X?a"), None);
+    let rules = standard_rules();
+    let mut settings = Settings::new();
+    // Clues in the first and last rows, blanks in the middle rows.
+    let pattern = format!("{}{}{}", "X".repeat(9), "?".repeat(63), "X".repeat(9));
+    settings.pattern = parse_pattern(&pattern);
+    let mut rng = Rng::new(1);
+    let (digits, _) = (0 .. ATTEMPTS)
+        .filter_map(|_| puzzle(&rules, &settings, &mut rng))
+        .next()
+        .unwrap();
+    assert!(digits[.. 9].chars().chain(digits[72 ..].chars()).all(|c| c != '.'));
+    // The first box blank.
+    let pattern: String = (0 .. 81).map(|c| if rules.shape.group(c) == 0 { '.' } else { '?' }).collect();
+    settings.pattern = parse_pattern(&pattern);
+    let (digits, _) = (0 .. ATTEMPTS)
+        .filter_map(|_| puzzle(&rules, &settings, &mut rng))
+        .next()
+        .unwrap();
+    assert!((0 .. 81).filter(|&c| rules.shape.group(c) == 0).all(|c| &digits[c .. c + 1] == "."));
+}
+
+#[test]
+fn test_maximize() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let source = Source::Shared(&rules);
+    let mut settings = Settings::new();
+    settings.symmetry = Symmetry::Rotational;
+    let mut rng = Rng::new(1);
+    let specimen = Specimen::new(&source, &settings, &mut rng);
+    for _ in 0 .. 5 {
+        let (digits, score) = specimen.mutate(&source, &settings, &mut rng);
+        let board = SudokuBoard::from_str(&digits, &rules);
+        assert_eq!(board.solve().unwrap().cells, specimen.solution);
+        assert_eq!(board.count_solutions(2), 1);
+        assert_eq!(score.0, logic::rate(&board));
+        let clues: Vec<bool> = digits.chars().map(|c| c != '.').collect();
+        assert!((0 .. 81).all(|c| clues[c] == clues[80 - c]));
+    }
+}
+
+#[test]
+fn test_summary() {
+    let mut summary = Summary::default();
+    summary.add("1.3", Difficulty::Easy);
+    summary.add("1..", Difficulty::Hard);
+    summary.add("..3", Difficulty::Hard);
+    summary.attempts = 5;
+    summary.repeats = 1;
+    let text = summary.text(Duration::from_millis(300));
+    assert!(text.starts_with("3 puzzles in 0.30 s, 100.0 ms per puzzle\n2 of 5 puzzles rejected (40.0%), 1 as repeats\n"));
+    assert!(text.contains("           1      2 " ));
+    assert!(text.ends_with("        hard      2 ##################################################\n"));
+}
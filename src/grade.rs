@@ -0,0 +1,59 @@
+// Grading collections of puzzles: the number of puzzles of each
+// rating, drawn as a histogram, and the ratings at a few percentiles,
+// so that a pack can be balanced between easy and hard puzzles.
+
+/// The percentiles of the table.
+const PERCENTILES: [usize; 5] = [10, 25, 50, 75, 90];
+
+/// The width of the longest bar of the histogram.
+const WIDTH: usize = 40;
+
+/// Return the histogram and the table of percentiles of `grades`,
+/// the ratings of the puzzles of a collection, each given by a rank
+/// ordering it and its name, e.g., `(0.0, "easy")`.
+pub fn report(grades: &[(f64, String)]) -> String {
+    if grades.is_empty() {
+        return "No puzzles".to_string();
+    }
+    let mut sorted = grades.to_vec();
+    sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for (_, name) in sorted.iter() {
+        match counts.last_mut() {
+            Some(&mut (last, ref mut count)) if last == name => { *count += 1; }
+            _ => { counts.push((name, 1)); }
+        }
+    }
+    let most = counts.iter().map(|&(_, count)| count).max().unwrap();
+    let name_width = counts.iter().map(|&(name, _)| name.len()).max().unwrap();
+    let count_width = most.to_string().len();
+
+    let mut lines: Vec<String> = counts.iter().map(|&(name, count)| {
+        let bar = "#".repeat((count * WIDTH).div_ceil(most));
+        format!("{:<nw$}  {:>cw$}  {}", name, count, bar, nw = name_width, cw = count_width)
+    }).collect();
+    lines.push(String::new());
+    for &p in PERCENTILES.iter() {
+        // The nearest rank: the grade of which p% of the puzzles are at most.
+        let rank = (p * sorted.len()).div_ceil(100).max(1);
+        lines.push(format!("{:>3}%  {}", p, sorted[rank - 1].1));
+    }
+    return lines.join("\n");
+}
+
+#[test]
+fn test_report() {
+    let grades: Vec<(f64, String)> = [(1.0, "medium"), (0.0, "easy"), (0.0, "easy"), (4.0, "diabolical")].iter()
+        .map(|&(rank, name)| (rank, name.to_string()))
+        .collect();
+    assert_eq!(report(&grades), "easy        2  ########################################\n\
+                                 medium      1  ####################\n\
+                                 diabolical  1  ####################\n\
+                                 \n \
+                                 10%  easy\n \
+                                 25%  easy\n \
+                                 50%  easy\n \
+                                 75%  medium\n \
+                                 90%  diabolical");
+    assert_eq!(report(&[]), "No puzzles");
+}
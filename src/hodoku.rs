@@ -0,0 +1,103 @@
+// Export to HoDoKu: a position is written as a line of its library
+// format, which tells the givens from the digits placed since and
+// lists the candidates deleted, e.g.,
+//
+//     :0000:x:4+83921657...:218 319::
+//
+// and as a grid of pencil marks for reading, which HoDoKu also pastes
+// but with every digit as a given.  Either way a solve can be carried
+// on there.
+
+use super::SudokuBoard;
+
+/// Return the library line of `position`, reached from `puzzle`, both
+/// standard boards: the givens of `puzzle`, the digits placed since
+/// marked with `+`, and the candidates deleted from the other cells,
+/// as digit, row and column, past those of the digits they see.
+pub fn library_line(puzzle: &SudokuBoard, position: &SudokuBoard) -> String {
+    let digit = |cell: usize| (position.cells[cell].trailing_zeros() + 1).to_string();
+    let mut givens = String::new();
+    let mut deleted = Vec::new();
+    for cell in 0 .. 81 {
+        if puzzle.cell_solved(cell) {
+            givens.push_str(&digit(cell));
+        } else if position.cell_solved(cell) {
+            givens.push('+');
+            givens.push_str(&digit(cell));
+        } else {
+            givens.push('.');
+            let seen = position.rules.neighbors[cell].iter()
+                .filter(|&&other| position.cell_solved(other))
+                .fold(0, |seen, &other| seen | position.cells[other]);
+            for d in (0 .. 9).filter(|d| (position.cells[cell] | seen) & (1 << d) == 0) {
+                deleted.push(format!("{}{}{}", d + 1, cell / 9 + 1, cell % 9 + 1));
+            }
+        }
+    }
+    return format!(":0000:x:{}:{}::", givens, deleted.join(" "));
+}
+
+/// Return the candidates of the cells of `board`, a standard board,
+/// as a grid of pencil marks.
+pub fn pm_grid(board: &SudokuBoard) -> String {
+    let marks: Vec<String> = board.cells.iter()
+        .map(|&set| (0 .. 9).filter(|d| set & (1 << d) != 0).map(|d| (d + 1).to_string()).collect())
+        .collect();
+    let widths: Vec<usize> = (0 .. 9)
+        .map(|col| (0 .. 9).map(|row| marks[9 * row + col].len()).max().unwrap())
+        .collect();
+    // The width of each band of columns, with the spaces between them.
+    let bands: Vec<usize> = widths.chunks(3).map(|w| w.iter().sum::<usize>() + 2 * 3).collect();
+    let border = |left: char, middle: char, right: char, fill: char| {
+        let segments: Vec<String> = bands.iter().map(|&w| fill.to_string().repeat(w)).collect();
+        return format!("{}{}{}", left, segments.join(&middle.to_string()), right);
+    };
+
+    let mut lines = vec![border('.', '.', '.', '-')];
+    for row in 0 .. 9 {
+        if row == 3 || row == 6 {
+            lines.push(border(':', '+', ':', '-'));
+        }
+        let mut line = String::from("|");
+        for col in 0 .. 9 {
+            line.push_str(&format!(" {:width$} ", marks[9 * row + col], width = widths[col]));
+            if col % 3 == 2 {
+                line.push('|');
+            }
+        }
+        lines.push(line);
+    }
+    lines.push(border('\'', '\'', '\'', '-'));
+    return lines.join("\n");
+}
+
+#[test]
+fn test_pm_grid() {
+    use super::{standard_rules, SOLUTION};
+
+    let rules = standard_rules();
+    let mut board = SudokuBoard::from_str(SOLUTION, &rules);
+    board.cells[1] |= 1 << 2;
+    let grid = pm_grid(&board);
+    let lines: Vec<&str> = grid.lines().collect();
+    assert_eq!(lines.len(), 13);
+    assert_eq!(lines[0], ".----------.---------.---------.");
+    assert_eq!(lines[1], "| 4  38  3 | 9  2  1 | 6  5  7 |");
+    assert_eq!(lines[4], ":----------+---------+---------:");
+    assert!(lines.iter().all(|line| line.chars().count() == lines[0].chars().count()));
+}
+
+#[test]
+fn test_library_line() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let puzzle = SudokuBoard::from_str(&".".repeat(81), &rules);
+    let mut position = puzzle.clone();
+    position.cells[0] = 1 << 3;
+    // The 4 of r1c2 goes with the 4 placed beside it, the others
+    // but 3 and 8 are deleted.
+    position.cells[1] = (1 << 2) | (1 << 7);
+    let line = library_line(&puzzle, &position);
+    assert_eq!(line, format!(":0000:x:+4{}:112 212 512 612 712 912::", ".".repeat(80)));
+}
@@ -0,0 +1,75 @@
+// Greater-than sudoku: inequality signs between adjacent cells,
+// read from a grid where the signs are written between the digits:
+//
+//     4<7>2 8 ...
+//     ^   v
+//     5>3<9 ...
+//
+// `<` and `>` compare a cell with the one on its right; `^` and `v`
+// compare a cell with the one below it, pointing at the smaller one.
+
+use super::N;
+use relation::{Link, Relation};
+
+/// Number of lines of a puzzle: the rows of cells and the lines
+/// of signs between them.
+pub const LINES: usize = 2 * N - 1;
+
+/// Convert the LINES lines of a greater-than puzzle to a linear
+/// representation of its cells and the links of its signs.
+/// A space means that there is no sign between two cells, and the
+/// cells past the end of a short row are blank.
+pub fn parse(lines: &[&str]) -> Result<(String, Vec<Link>), String> {
+    if lines.len() != LINES {
+        return Err(format!("invalid greater-than puzzle; expected {} lines, got {}", LINES, lines.len()));
+    }
+    let mut digits = String::with_capacity(N * N);
+    let mut links = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.len() > LINES {
+            return Err(format!("invalid greater-than line length; expected {}, got {}",
+                               LINES, chars.len()));
+        }
+        let at = |j: usize| chars.get(j).cloned().unwrap_or(' ');
+        let r = i / 2;
+        for c in 0 .. N {
+            let cell = N * r + c;
+            if i % 2 == 0 {
+                digits.push(chars.get(2 * c).cloned().unwrap_or('.'));
+                if c + 1 == N {
+                    continue;
+                }
+                match at(2 * c + 1) {
+                    '<' => { links.push(Link::new(cell, cell + 1, Relation::Less)); }
+                    '>' => { links.push(Link::new(cell + 1, cell, Relation::Less)); }
+                    ' ' | '|' => {}
+                    sign => { return Err(format!("invalid sign {:?} on line {}", sign, i + 1)); }
+                }
+            } else {
+                match at(2 * c) {
+                    '^' => { links.push(Link::new(cell, cell + N, Relation::Less)); }
+                    'v' | 'V' => { links.push(Link::new(cell + N, cell, Relation::Less)); }
+                    ' ' | '-' => {}
+                    sign => { return Err(format!("invalid sign {:?} on line {}", sign, i + 1)); }
+                }
+            }
+        }
+    }
+    return Ok((digits, links));
+}
+
+#[test]
+fn test_parse() {
+    let mut lines: Vec<&str> = (0 .. LINES).map(|i| if i % 2 == 0 { "1<2>3" } else { "" }).collect();
+    lines[1] = "^ v";
+    let (digits, links) = parse(&lines).unwrap();
+    assert_eq!(digits.len(), N * N);
+    assert_eq!(&digits[0 .. N], "123......");
+    let pairs: Vec<(usize, usize)> = links.iter().take(4).map(|l| (l.first, l.second)).collect();
+    assert_eq!(pairs, vec![(0, 1), (2, 1), (0, N), (N + 1, 1)]);
+
+    lines[1] = "^ x";
+    assert!(parse(&lines).is_err());
+    assert!(parse(&lines[1 ..]).is_err());
+}
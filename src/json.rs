@@ -0,0 +1,302 @@
+// A small JSON parser, enough for the extended puzzle formats, and
+// the writer of the JSON answers of the server.
+
+use std::fmt;
+
+/// The deepest nesting of arrays and objects read, past which a value
+/// is refused rather than overflow the stack of the parser.
+const MAX_DEPTH: usize = 64;
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Return the object of `pairs`.
+    pub fn object(pairs: Vec<(&str, Json)>) -> Json {
+        Json::Object(pairs.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    /// Return the value of `key` if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref pairs) => pairs.iter().find(|p| p.0 == key).map(|p| &p.1),
+            _ => None,
+        }
+    }
+
+    #[cfg(any(feature = "variants", feature = "serve"))]
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Return the value of this number if it is a non-negative integer.
+    #[cfg(feature = "variants")]
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self {
+            Json::Number(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => Some(n as u32),
+            _ => None,
+        }
+    }
+
+    /// Return the value of this number if it is a non-negative integer
+    /// that a double holds exactly.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Json::Number(n) if n >= 0.0 && n.fract() == 0.0 && n <= (1u64 << 53) as f64 => Some(n as u64),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "variants")]
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Write `s` as a JSON string.
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    return f.write_str("\"");
+}
+
+/// JSON values are written on a single line.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(ref pairs) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Parse `text`, which must hold exactly one JSON value.
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.chars.len() {
+        return Err(parser.unexpected());
+    }
+    return Ok(value);
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// The arrays and objects open at `pos`.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn unexpected(&self) -> String {
+        match self.peek() {
+            Some(c) => format!("unexpected {:?} at offset {} in JSON", c, self.pos),
+            None => "unexpected end of JSON".to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c`, after optional whitespace.
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        return Ok(());
+    }
+
+    /// Consume `word` if it comes next.
+    fn keyword(&mut self, word: &str) -> bool {
+        let end = self.pos + word.len();
+        if end <= self.chars.len() && self.chars[self.pos .. end].iter().cloned().eq(word.chars()) {
+            self.pos = end;
+            return true;
+        }
+        return false;
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ if self.keyword("null") => Ok(Json::Null),
+            _ if self.keyword("true") => Ok(Json::Bool(true)),
+            _ if self.keyword("false") => Ok(Json::Bool(false)),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parse the comma-separated items between `open` and `close`.
+    fn items<T, F>(&mut self, open: char, close: char, mut item: F) -> Result<Vec<T>, String>
+        where F: FnMut(&mut Parser) -> Result<T, String>
+    {
+        self.expect(open)?;
+        if self.depth == MAX_DEPTH {
+            return Err("too deeply nested".to_string());
+        }
+        self.depth += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() != Some(close) {
+            loop {
+                items.push(item(self)?);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(',') => { self.pos += 1; }
+                    Some(c) if c == close => { break; }
+                    _ => { return Err(self.unexpected()); }
+                }
+            }
+        }
+        self.pos += 1;
+        self.depth -= 1;
+        return Ok(items);
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        let pairs = self.items('{', '}', |p| {
+            p.skip_whitespace();
+            let key = p.string()?;
+            p.expect(':')?;
+            let value = p.value()?;
+            Ok((key, value))
+        })?;
+        return Ok(Json::Object(pairs));
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        return self.items('[', ']', |p| p.value()).map(Json::Array);
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let c = self.peek().ok_or_else(|| self.unexpected())?;
+            self.pos += 1;
+            match c {
+                '"' => { return Ok(s); }
+                '\\' => {
+                    let escaped = self.peek().ok_or_else(|| self.unexpected())?;
+                    self.pos += 1;
+                    match escaped {
+                        '"' | '\\' | '/' => s.push(escaped),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'u' => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16)
+                                .map_err(|_| format!("invalid escape \\u{} in JSON", hex))?;
+                            self.pos += 4;
+                            s.push(::std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        _ => { return Err(format!("invalid escape \\{} in JSON", escaped)); }
+                    }
+                }
+                _ => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c)) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start .. self.pos].iter().collect();
+        return text.parse().map(Json::Number)
+            .map_err(|_| format!("invalid number {:?} in JSON", text));
+    }
+}
+
+#[test]
+fn test_parse() {
+    let value = parse(r#" {"a": [1, -2.5e1, "x\"y"], "b": {}, "c": [true, false, null]} "#);
+    assert_eq!(value, Ok(Json::Object(vec![
+        ("a".to_string(), Json::Array(vec![
+            Json::Number(1.0), Json::Number(-25.0), Json::String("x\"y".to_string())
+        ])),
+        ("b".to_string(), Json::Object(vec![])),
+        ("c".to_string(), Json::Array(vec![Json::Bool(true), Json::Bool(false), Json::Null])),
+    ])));
+    assert!(parse("[1, 2").is_err());
+    assert!(parse("{} x").is_err());
+
+    // Nesting is capped, however little of the input it takes.
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(parse(&nested(MAX_DEPTH)).is_ok());
+    assert_eq!(parse(&nested(MAX_DEPTH + 1)), Err("too deeply nested".to_string()));
+    assert_eq!(parse(&"[".repeat(60000)), Err("too deeply nested".to_string()));
+}
+
+#[test]
+fn test_display() {
+    let text = r#"{"a": [1, -25, "x\"y\n"], "b": {}, "c": [true, false, null]}"#;
+    let value = parse(text).unwrap();
+    assert_eq!(value.to_string(), text);
+    assert_eq!(parse(&value.to_string()), Ok(value));
+}
@@ -0,0 +1,149 @@
+// Puzzles in JSON: a grid of digits plus the clues that do not fit
+// on a line of digits, e.g.,
+//
+//     {"grid": "..3.....",
+//      "thermometers": [["r1c1", "r1c2", "r2c3"]],
+//      "sandwiches": {"rows": [null, 12, ...], "columns": [...]},
+//      "x": [["r1c1", "r1c2"]], "v": [["r4c4", "r5c4"]], "xv_negative": true,
+//      "arrows": [{"circle": "r5c5", "cells": ["r4c4", "r3c3"]}],
+//      "little_killers": [{"start": "r1c2", "direction": "down-right", "sum": 40}]}
+//
+// Cells are named by their 1-based row and column.
+
+use super::{move_pairs, parse_cell, Shape, N, ORTHOGONAL_MOVES};
+use arrow::Arrow;
+use sum::LittleKiller;
+use json::{self, Json};
+use relation::{Link, Relation};
+use sandwich::Sandwich;
+
+/// The clues of a JSON puzzle.
+pub struct Clues {
+    pub links: Vec<Link>,
+    pub sandwiches: Vec<Sandwich>,
+    pub arrows: Vec<Arrow>,
+    pub little_killers: Vec<LittleKiller>,
+}
+
+/// A puzzle read from JSON.
+pub struct JsonPuzzle {
+    pub grid: String,
+    pub clues: Clues,
+}
+
+/// Return the cells named by the strings of `value`, a JSON array.
+pub fn cells(value: &Json, what: &str) -> Result<Vec<usize>, String> {
+    let names = value.as_array()
+        .ok_or_else(|| format!("{} must be an array of cells", what))?;
+    return names.iter()
+        .map(|name| name.as_str()
+             .and_then(|name| parse_cell(name).ok())
+             .ok_or_else(|| format!("invalid cell {:?} in {}", name, what)))
+        .collect();
+}
+
+/// Return the elements of the array `key` of `value`, or nothing
+/// if `value` does not have that key.
+pub fn list<'a>(value: &'a Json, key: &str) -> Result<&'a [Json], String> {
+    match value.get(key) {
+        None => Ok(&[]),
+        Some(items) => items.as_array()
+            .ok_or_else(|| format!("{:?} must be an array", key)),
+    }
+}
+
+/// Return the puzzle in `text`, or why it cannot be read.
+pub fn parse(text: &str) -> Result<JsonPuzzle, String> {
+    let value = json::parse(text)?;
+    let grid = value.get("grid").and_then(Json::as_str)
+        .ok_or_else(|| "missing \"grid\" in JSON puzzle".to_string())?;
+    return Ok(JsonPuzzle { grid: grid.to_string(), clues: clues(&value)? });
+}
+
+/// Read the clues of `value`, a JSON object; the keys that are
+/// not clues are ignored.
+pub fn clues(value: &Json) -> Result<Clues, String> {
+    let mut links = Vec::new();
+    // The digits of a thermometer strictly increase from its bulb.
+    for thermometer in list(value, "thermometers")? {
+        let thermo_cells = cells(thermometer, "thermometer")?;
+        for pair in thermo_cells.windows(2) {
+            links.push(Link::new(pair[0], pair[1], Relation::Less));
+        }
+    }
+
+    // X and V mark adjacent cells adding up to 10 and 5; with the
+    // negative constraint, no other adjacent cells add up to either.
+    let mut marked = Vec::new();
+    for &(key, sum) in [("x", 10), ("v", 5)].iter() {
+        for pair in list(value, key)? {
+            let mut pair_cells = cells(pair, key)?;
+            pair_cells.sort();
+            if pair_cells.len() != 2 || !move_pairs(Shape::STANDARD, &ORTHOGONAL_MOVES).contains(&pair_cells) {
+                return Err(format!("{:?} must mark two adjacent cells, not {:?}", key, pair));
+            }
+            links.push(Link::new(pair_cells[0], pair_cells[1], Relation::Sum(sum)));
+            marked.push(pair_cells);
+        }
+    }
+    if value.get("xv_negative") == Some(&Json::Bool(true)) {
+        for pair in move_pairs(Shape::STANDARD, &ORTHOGONAL_MOVES).into_iter().filter(|p| !marked.contains(p)) {
+            links.push(Link::new(pair[0], pair[1], Relation::NotSum(10)));
+            links.push(Link::new(pair[0], pair[1], Relation::NotSum(5)));
+        }
+    }
+
+    // Sandwich clues are given for each row and column, from the
+    // top and the left, with null for the lines without a clue.
+    let mut sandwiches = Vec::new();
+    if let Some(clues) = value.get("sandwiches") {
+        for &(key, rows) in [("rows", true), ("columns", false)].iter() {
+            for (i, clue) in list(clues, key)?.iter().enumerate().take(N) {
+                if *clue == Json::Null {
+                    continue;
+                }
+                let sum = clue.as_u32()
+                    .ok_or_else(|| format!("invalid sandwich clue {:?}", clue))?;
+                let line = (0 .. N).map(|j| if rows { N * i + j } else { N * j + i }).collect();
+                sandwiches.push(Sandwich::new(line, sum)?);
+            }
+        }
+    }
+
+    let mut arrows = Vec::new();
+    for arrow in list(value, "arrows")? {
+        let circle = arrow.get("circle").and_then(Json::as_str)
+            .ok_or_else(|| format!("missing circle in arrow {:?}", arrow))?;
+        let arrow_cells = arrow.get("cells")
+            .ok_or_else(|| format!("missing cells in arrow {:?}", arrow))?;
+        arrows.push(Arrow { circle: parse_cell(circle)?, cells: cells(arrow_cells, "arrow")? });
+    }
+
+    // A little killer clue sums the diagonal that runs from its
+    // start cell in its direction to the edge of the grid.
+    let mut little_killers = Vec::new();
+    for clue in list(value, "little_killers")? {
+        let start = clue.get("start").and_then(Json::as_str)
+            .ok_or_else(|| format!("missing start in little killer {:?}", clue))?;
+        let (dr, dc) = match clue.get("direction").and_then(Json::as_str) {
+            Some("down-right") => (1, 1),
+            Some("down-left") => (1, -1),
+            Some("up-right") => (-1, 1),
+            Some("up-left") => (-1, -1),
+            _ => { return Err(format!("invalid direction in little killer {:?}", clue)); }
+        };
+        let sum = clue.get("sum").and_then(Json::as_u32)
+            .ok_or_else(|| format!("missing sum in little killer {:?}", clue))?;
+        let start = parse_cell(start)?;
+        let (mut r, mut c) = ((start / N) as isize, (start % N) as isize);
+        let mut diagonal = Vec::new();
+        while r >= 0 && r < N as isize && c >= 0 && c < N as isize {
+            diagonal.push(N * r as usize + c as usize);
+            r += dr;
+            c += dc;
+        }
+        little_killers.push(LittleKiller { cells: diagonal, sum });
+    }
+
+    return Ok(Clues { links, sandwiches, arrows, little_killers });
+}
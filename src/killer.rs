@@ -0,0 +1,197 @@
+// Killer sudoku: cages of cells whose digits must add up to a given sum.
+
+use std::collections::BTreeMap;
+
+use super::{CandidateSet, FULL_SET, N, NSQ};
+#[cfg(feature = "generate")]
+use super::{move_pairs, Shape, ORTHOGONAL_MOVES};
+use constraint::Constraint;
+#[cfg(feature = "generate")]
+use rng::Rng;
+
+/// Labels of the cages of a random cage map, in order.
+#[cfg(feature = "generate")]
+const LABELS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// A cage is a set of cells whose digits are all different
+/// and add up to a given sum.
+pub struct Cage {
+    pub cells: Vec<usize>,
+    /// The sets of digits that could fill the cage.
+    combinations: Vec<CandidateSet>,
+}
+
+/// Return the sum of the digits in `set`.
+fn digit_sum(set: CandidateSet) -> u32 {
+    (0 .. N as u32).filter(|d| set & (1 << d) != 0).map(|d| d + 1).sum()
+}
+
+impl Cage {
+    /// Return the cage of `cells` adding up to `sum`, or why there
+    /// is none.
+    pub fn new(cells: Vec<usize>, sum: u32) -> Result<Self, String> {
+        let size = cells.len() as u32;
+        let combinations: Vec<CandidateSet> = (1 ..= FULL_SET)
+            .filter(|s| s.count_ones() == size && digit_sum(*s) == sum)
+            .collect();
+        if combinations.is_empty() {
+            return Err(format!("no {} distinct digits add up to {}", size, sum));
+        }
+        return Ok(Cage { cells, combinations });
+    }
+}
+
+impl Constraint for Cage {
+    /// Remove from the cells of the cage the candidates that do not
+    /// belong to any combination that could still fill the cage.
+    /// A combination is possible if every cell has a candidate in it
+    /// and every digit of the combination is a candidate of some cell.
+    /// Return whether any candidate was removed.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        let mut allowed = vec![0; self.cells.len()];
+        for &combination in self.combinations.iter() {
+            let mut covered = 0;
+            let mut possible = true;
+            for &cell in self.cells.iter() {
+                let candidates = cells[cell] & combination;
+                possible = possible && candidates != 0;
+                covered |= candidates;
+            }
+            if possible && covered == combination {
+                for (i, &cell) in self.cells.iter().enumerate() {
+                    allowed[i] |= cells[cell] & combination;
+                }
+            }
+        }
+
+        let mut changed = false;
+        for (i, &cell) in self.cells.iter().enumerate() {
+            changed = changed || cells[cell] & !allowed[i] != 0;
+            cells[cell] &= allowed[i];
+        }
+        return changed;
+    }
+}
+
+/// Parse the cages of a killer puzzle.  `labels` has one label per
+/// cell, cells with the same label forming a cage and cells labelled
+/// with a dot belonging to no cage; `sums` is a comma-separated list
+/// of `label=sum` pairs, e.g., `A=3,B=15`.  Return the cages, or why
+/// they cannot be read.
+pub fn parse_cages(labels: &str, sums: &str) -> Result<Vec<Cage>, String> {
+    if labels.chars().count() != NSQ {
+        return Err(format!("invalid cage map length; expected {}, got {}",
+                           NSQ, labels.chars().count()));
+    }
+    let mut cells: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+    for (cell, label) in labels.chars().enumerate().filter(|&(_, l)| l != '.') {
+        cells.entry(label).or_default().push(cell);
+    }
+
+    let mut cages = Vec::with_capacity(cells.len());
+    for pair in sums.split(',') {
+        let mut chars = pair.chars();
+        let label = chars.next();
+        let sum = match (chars.next(), chars.as_str().parse::<u32>()) {
+            (Some('='), Ok(sum)) => sum,
+            _ => { return Err(format!("invalid cage sum {:?}", pair)); }
+        };
+        match label.and_then(|l| cells.remove(&l)) {
+            Some(cage_cells) => { cages.push(Cage::new(cage_cells, sum)?); }
+            None => { return Err(format!("no cage for sum {:?}", pair)); }
+        }
+    }
+    if let Some(label) = cells.keys().next() {
+        return Err(format!("missing sum for cage {:?}", label));
+    }
+    return Ok(cages);
+}
+
+/// Return a random cage map over the solved `grid` and the sums of
+/// its cages, as `parse_cages` reads them, or None if it has more
+/// cages than labels.  Each cage grows from a random cell through
+/// orthogonally adjacent cells of other digits, up to two to four
+/// cells, so that the grid solves the cages.
+#[cfg(feature = "generate")]
+pub fn random_cages(grid: &[CandidateSet], rng: &mut Rng) -> Option<(String, String)> {
+    let mut adjacent = vec![Vec::new(); NSQ];
+    for pair in move_pairs(Shape::STANDARD, &ORTHOGONAL_MOVES) {
+        adjacent[pair[0]].push(pair[1]);
+        adjacent[pair[1]].push(pair[0]);
+    }
+    let mut starts: Vec<usize> = (0 .. NSQ).collect();
+    rng.shuffle(&mut starts);
+    let mut caged = [false; NSQ];
+    let mut cages: Vec<Vec<usize>> = Vec::new();
+    for start in starts {
+        if caged[start] {
+            continue;
+        }
+        let size = 2 + rng.below(3);
+        let mut cage = vec![start];
+        caged[start] = true;
+        while cage.len() < size {
+            let free: Vec<usize> = cage.iter()
+                .flat_map(|&cell| adjacent[cell].iter().cloned())
+                .filter(|&next| !caged[next] && cage.iter().all(|&cell| grid[cell] != grid[next]))
+                .collect();
+            if free.is_empty() {
+                break;
+            }
+            let next = free[rng.below(free.len())];
+            caged[next] = true;
+            cage.push(next);
+        }
+        cages.push(cage);
+    }
+    if cages.len() > LABELS.len() {
+        return None;
+    }
+
+    let mut labels = vec!['.'; NSQ];
+    let mut sums = Vec::with_capacity(cages.len());
+    for (cage, label) in cages.iter().zip(LABELS.chars()) {
+        for &cell in cage.iter() {
+            labels[cell] = label;
+        }
+        let sum: u32 = cage.iter().map(|&cell| digit_sum(grid[cell])).sum();
+        sums.push(format!("{}={}", label, sum));
+    }
+    return Some((labels.into_iter().collect(), sums.join(",")));
+}
+
+#[test]
+fn test_prune() {
+    // Two cells adding up to 3 can only hold 1 and 2.
+    let cage = Cage::new(vec![0, 1], 3).unwrap();
+    let mut cells = vec![FULL_SET, FULL_SET];
+    assert!(cage.prune(&mut cells));
+    assert_eq!(cells, vec![0b11, 0b11]);
+
+    // A 1 must be completed by a 9 in a cage adding up to 10.
+    let cage = Cage::new(vec![0, 1], 10).unwrap();
+    let mut cells = vec![0b1, FULL_SET];
+    assert!(cage.prune(&mut cells));
+    assert_eq!(cells, vec![0b1, 0b1_0000_0001]);
+    assert!(!cage.prune(&mut cells));
+
+    assert!(Cage::new(vec![0, 1], 99).is_err());
+}
+
+#[cfg(feature = "generate")]
+#[test]
+fn test_random_cages() {
+    use super::{standard_rules, SudokuBoard};
+
+    let rules = standard_rules();
+    let mut rng = Rng::new(1);
+    let grid = SudokuBoard::random_solution(&rules, &mut rng).unwrap().cells;
+    let (labels, sums) = random_cages(&grid, &mut rng).unwrap();
+    // Every cell is caged, and the grid adds up to the sums.
+    assert!(!labels.contains('.'));
+    let cages = parse_cages(&labels, &sums).unwrap();
+    let mut cells = grid.to_vec();
+    for cage in cages.iter() {
+        assert!(!cage.prune(&mut cells));
+    }
+}
@@ -0,0 +1,183 @@
+// Multi-grid sudoku: several 9x9 grids laid out on a rectangle and
+// overlapping each other, like the five grids of samurai sudoku,
+// the center grid sharing one corner box with each of the others.
+
+use std::collections::BTreeSet;
+
+use super::{N, NSQRT};
+
+/// The placement of the grids of a multi-grid puzzle.
+#[derive(Debug, PartialEq)]
+pub struct Layout {
+    /// Upper-left (row, column) of every grid on the rectangle.
+    pub grids: &'static [(usize, usize)],
+}
+
+/// Five grids, the center one sharing a corner box with each of the others.
+pub const SAMURAI: Layout = Layout {
+    grids: &[(0, 0), (0, N + NSQRT), (N - NSQRT, N - NSQRT), (N + NSQRT, 0), (N + NSQRT, N + NSQRT)]
+};
+
+/// Two grids sharing a corner box.
+const TWIN: Layout = Layout { grids: &[(0, 0), (N - NSQRT, N - NSQRT)] };
+
+/// Three grids in a diagonal chain, sharing corner boxes.
+const TRIPLE: Layout = Layout {
+    grids: &[(0, 0), (N - NSQRT, N - NSQRT), (2 * (N - NSQRT), 2 * (N - NSQRT))]
+};
+
+/// Four grids on a 12x12 square, each sharing four boxes with the others.
+const BUTTERFLY: Layout = Layout {
+    grids: &[(0, 0), (0, NSQRT), (NSQRT, 0), (NSQRT, NSQRT)]
+};
+
+/// Five grids, the center one sharing six boxes with each of the others.
+const FLOWER: Layout = Layout {
+    grids: &[(0, NSQRT), (NSQRT, 0), (NSQRT, NSQRT), (NSQRT, 2 * NSQRT), (2 * NSQRT, NSQRT)]
+};
+
+impl Layout {
+    /// Return the layout called `name`, or the layout described by
+    /// `name`, the upper-left cells of its grids joined by `+`,
+    /// e.g., `r1c1+r7c7` for twin grids.
+    pub fn from_name(name: &str) -> Option<&'static Layout> {
+        match name {
+            "samurai" => Some(&SAMURAI),
+            "twin" => Some(&TWIN),
+            "triple" => Some(&TRIPLE),
+            "butterfly" => Some(&BUTTERFLY),
+            "flower" => Some(&FLOWER),
+            _ => {
+                let mut grids = Vec::new();
+                for corner in name.split('+') {
+                    let (r, c) = parse_position(corner)?;
+                    if grids.contains(&(r, c)) {
+                        return None;
+                    }
+                    grids.push((r, c));
+                }
+                // Layouts last as long as the program.
+                Some(Box::leak(Box::new(Layout { grids: Box::leak(grids.into_boxed_slice()) })))
+            }
+        }
+    }
+
+    /// Number of rows of the rectangle.
+    pub fn height(&self) -> usize {
+        self.grids.iter().map(|&(r, _)| r + N).max().unwrap_or(0)
+    }
+
+    /// Number of columns of the rectangle.
+    pub fn width(&self) -> usize {
+        self.grids.iter().map(|&(_, c)| c + N).max().unwrap_or(0)
+    }
+
+    /// Whether the position (`r`, `c`) of the rectangle belongs to a grid.
+    fn live(&self, r: usize, c: usize) -> bool {
+        self.grids.iter().any(|&(gr, gc)| r >= gr && r < gr + N && c >= gc && c < gc + N)
+    }
+
+    /// Return the cell index of every position of the rectangle,
+    /// or None for the positions outside the grids.  Cells are
+    /// numbered from left to right and top to bottom.
+    fn cell_indices(&self) -> Vec<Option<usize>> {
+        let mut next = 0;
+        let mut indices = Vec::with_capacity(self.height() * self.width());
+        for r in 0 .. self.height() {
+            for c in 0 .. self.width() {
+                if self.live(r, c) {
+                    indices.push(Some(next));
+                    next += 1;
+                } else {
+                    indices.push(None);
+                }
+            }
+        }
+        return indices;
+    }
+
+    /// Return the units of the grids.  The units shared by
+    /// several grids appear only once.
+    pub fn units(&self) -> Vec<Vec<usize>> {
+        let indices = self.cell_indices();
+        let width = self.width();
+        let cell = |r: usize, c: usize| indices[width * r + c].unwrap();
+        let mut all_units: BTreeSet<Vec<usize>> = BTreeSet::new();
+        for &(gr, gc) in self.grids.iter() {
+            for i in 0 .. N {
+                all_units.insert((0 .. N).map(|j| cell(gr + i, gc + j)).collect());
+                all_units.insert((0 .. N).map(|j| cell(gr + j, gc + i)).collect());
+                let (br, bc) = (gr + NSQRT * (i / NSQRT), gc + NSQRT * (i % NSQRT));
+                all_units.insert((0 .. N).map(|j| cell(br + j / NSQRT, bc + j % NSQRT)).collect());
+            }
+        }
+        return all_units.into_iter().collect();
+    }
+
+    /// Convert the lines of a multi-grid puzzle, one per row of the
+    /// rectangle, to a linear representation of its cells.  Positions
+    /// outside the grids must be blank (a space or a dash) or missing
+    /// at the end of a line.
+    pub fn parse(&self, lines: &[&str]) -> Result<String, String> {
+        let (height, width) = (self.height(), self.width());
+        if lines.len() != height {
+            return Err(format!("invalid multi-grid puzzle; expected {} lines, got {}", height, lines.len()));
+        }
+        let mut digits = String::new();
+        for (r, line) in lines.iter().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.len() > width {
+                return Err(format!("invalid multi-grid line length; expected {}, got {}", width, chars.len()));
+            }
+            for c in 0 .. width {
+                let ch = chars.get(c).cloned().unwrap_or(' ');
+                if self.live(r, c) {
+                    digits.push(ch);
+                } else if ch != ' ' && ch != '-' {
+                    return Err(format!("unexpected {:?} outside the grids on line {}", ch, r + 1));
+                }
+            }
+        }
+        return Ok(digits);
+    }
+
+    /// Lay out the linear representation of a board on the rows of the rectangle.
+    pub fn format(&self, digits: &str) -> String {
+        let indices = self.cell_indices();
+        let chars: Vec<char> = digits.chars().collect();
+        let width = self.width();
+        let mut lines = Vec::with_capacity(self.height());
+        for r in 0 .. self.height() {
+            let line: String = (0 .. width)
+                .map(|c| indices[width * r + c].map_or(' ', |i| chars[i]))
+                .collect();
+            lines.push(line.trim_end().to_string());
+        }
+        return lines.join("\n");
+    }
+}
+
+/// Return the 0-based (row, column) of a position named like a
+/// cell, e.g., `r7c13`, on a rectangle of any size.
+fn parse_position(name: &str) -> Option<(usize, usize)> {
+    let mut rc = name.strip_prefix('r')?.splitn(2, 'c');
+    let r = rc.next()?.parse::<usize>().ok()?;
+    let c = rc.next()?.parse::<usize>().ok()?;
+    if r == 0 || c == 0 {
+        return None;
+    }
+    return Some((r - 1, c - 1));
+}
+
+#[test]
+fn test_units() {
+    let all_units = SAMURAI.units();
+    // 5 grids of 27 units, minus the 4 shared boxes.
+    assert_eq!(all_units.len(), 5 * 27 - 4);
+    assert_eq!(SAMURAI.cell_indices().iter().filter(|i| i.is_some()).count(), 5 * N * N - 4 * N);
+    assert_eq!(SAMURAI.height(), 21);
+    assert_eq!(Layout::from_name("r1c1+r7c7"), Some(&TWIN));
+    assert_eq!(TWIN.units().len(), 2 * 27 - 1);
+    // The center of the flower shares six boxes with each petal.
+    assert_eq!(FLOWER.cell_indices().iter().filter(|i| i.is_some()).count(), 15 * 15 - 4 * N);
+}
@@ -0,0 +1,69 @@
+// Logs of batch solves, so that the slow puzzles of a large run can be
+// found by filtering the log rather than solving them again: a span
+// for each puzzle, written on the standard error in the layout of the
+// `tracing` crate, its name and fields first, then an event for each
+// strategy of the solver, and last the time it took, e.g.:
+//
+//     puzzle{id=3 clues=17 outcome=solved}: propagation placed=213 eliminated=1804
+//     puzzle{id=3 clues=17 outcome=solved}: guessing guesses=14 backtracks=13 depth=5
+//     puzzle{id=3 clues=17 outcome=solved}: close elapsed_us=2210
+//
+// The lines of a span are written together, so that the spans of
+// puzzles solved on several threads do not mix.
+
+use std::time::{Duration, Instant};
+
+use super::{Limits, SudokuBoard};
+use trace::{self, SolveStep};
+
+/// Return the lines of the span of the puzzle `id` of `board`, whose
+/// search took `steps` in `elapsed` to the `outcome`.
+pub fn span(id: usize, board: &SudokuBoard, outcome: &str, steps: &[SolveStep], elapsed: Duration) -> String {
+    let clues = (0 .. board.cells.len()).filter(|&cell| board.cell_solved(cell)).count();
+    let (mut placed, mut eliminated, mut guesses, mut backtracks, mut depth) = (0, 0, 0, 0, 0);
+    for step in steps.iter() {
+        match *step {
+            SolveStep::Place { .. } => { placed += 1; }
+            SolveStep::Eliminate { ref digits, .. } => { eliminated += digits.len(); }
+            SolveStep::Guess { depth: guessed, .. } => {
+                guesses += 1;
+                depth = depth.max(guessed);
+            }
+            SolveStep::Backtrack { .. } => { backtracks += 1; }
+        }
+    }
+    let name = format!("puzzle{{id={} clues={} outcome={}}}", id, clues, outcome);
+    return format!("{}: propagation placed={} eliminated={}\n{}: guessing guesses={} backtracks={} depth={}\n\
+                    {}: close elapsed_us={}\n",
+                   name, placed, eliminated, name, guesses, backtracks, depth, name, elapsed.as_micros());
+}
+
+/// Solve `board`, the puzzle `id`, as `SudokuBoard::solve` does,
+/// within `limits`, and log its span; a search given up is, e.g.,
+/// "timed-out".
+pub fn solve<'a>(id: usize, board: &SudokuBoard<'a>, limits: &Limits) -> Option<SudokuBoard<'a>> {
+    let start = Instant::now();
+    let (solution, steps) = trace::solve(board, limits);
+    let outcome = match limits.exceeded.get() {
+        Some(exceeded) => exceeded.reason().replace(' ', "-"),
+        None if solution.is_some() => "solved".to_string(),
+        None => "unsolvable".to_string(),
+    };
+    eprint!("{}", span(id, board, &outcome, &steps, start.elapsed()));
+    return solution;
+}
+
+#[test]
+fn test_span() {
+    use super::{standard_rules, SOLUTION};
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let board = SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules);
+    let (solved, steps) = trace::solve(&board, &Limits::none());
+    assert!(solved.is_some());
+    assert_eq!(span(7, &board, "solved", &steps, Duration::from_micros(1500)),
+               "puzzle{id=7 clues=79 outcome=solved}: propagation placed=2 eliminated=0\n\
+                puzzle{id=7 clues=79 outcome=solved}: guessing guesses=0 backtracks=0 depth=0\n\
+                puzzle{id=7 clues=79 outcome=solved}: close elapsed_us=1500\n");
+}
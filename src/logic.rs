@@ -0,0 +1,538 @@
+// Solving without guessing: the constraints place the singles, and
+// human techniques working on the houses remove the candidates that
+// the singles leave.  The hardest technique needed rates a puzzle.
+
+use super::{CandidateSet, Rules, Shape, SudokuBoard, EMPTY_SET};
+
+/// The techniques of the logic solver, from the simplest to the hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    /// Naked and hidden singles, and whatever the constraints remove.
+    Singles,
+    /// Candidates of a house confined to another house (pointing
+    /// and claiming).
+    Intersections,
+    /// Naked and hidden pairs.
+    Pairs,
+    /// Naked and hidden triples.
+    Triples,
+    /// X-wings: a digit confined to two cells in each of two houses.
+    XWings,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 5] = [
+        Technique::Singles, Technique::Intersections, Technique::Pairs,
+        Technique::Triples, Technique::XWings,
+    ];
+
+    #[cfg(any(feature = "generate", feature = "render"))]
+    pub fn from_name(name: &str) -> Option<Technique> {
+        match name {
+            "singles" => Some(Technique::Singles),
+            "intersections" => Some(Technique::Intersections),
+            "pairs" => Some(Technique::Pairs),
+            "triples" => Some(Technique::Triples),
+            "x-wings" => Some(Technique::XWings),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::Singles => "singles",
+            Technique::Intersections => "intersections",
+            Technique::Pairs => "pairs",
+            Technique::Triples => "triples",
+            Technique::XWings => "x-wings",
+        }
+    }
+
+    /// Apply the technique once to the houses of `rules`.
+    /// Return whether any candidate was removed.
+    fn apply(self, rules: &Rules, cells: &mut [CandidateSet]) -> bool {
+        match self {
+            Technique::Singles => false,
+            Technique::Intersections => intersections(rules, cells),
+            Technique::Pairs => subsets(rules, cells, 2),
+            Technique::Triples => subsets(rules, cells, 3),
+            Technique::XWings => x_wings(rules, cells),
+        }
+    }
+}
+
+/// How hard a puzzle is to solve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// Singles only.
+    Easy,
+    /// Intersections.
+    Medium,
+    /// Pairs and triples.
+    Hard,
+    /// X-wings.
+    Fiendish,
+    /// Beyond the techniques of the logic solver.
+    Diabolical,
+}
+
+impl Difficulty {
+    #[cfg(feature = "generate")]
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            "fiendish" => Some(Difficulty::Fiendish),
+            "diabolical" => Some(Difficulty::Diabolical),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Fiendish => "fiendish",
+            Difficulty::Diabolical => "diabolical",
+        }
+    }
+
+    fn of(technique: Technique) -> Difficulty {
+        match technique {
+            Technique::Singles => Difficulty::Easy,
+            Technique::Intersections => Difficulty::Medium,
+            Technique::Pairs | Technique::Triples => Difficulty::Hard,
+            Technique::XWings => Difficulty::Fiendish,
+        }
+    }
+}
+
+/// Solve `board` as far as `techniques` go, always trying the simplest
+/// technique first.  Return the board reached and the hardest
+/// technique used.
+pub fn solve<'a>(board: &SudokuBoard<'a>, techniques: &[Technique]) -> (SudokuBoard<'a>, Technique) {
+    let rules = board.rules;
+    let mut board = board.clone();
+    board.propagate();
+    let mut hardest = Technique::Singles;
+    while !board.solved() && board.solvable() {
+        let used = Technique::ALL.iter().cloned()
+            .filter(|t| techniques.contains(t))
+            .find(|t| t.apply(rules, &mut board.cells));
+        match used {
+            Some(technique) => {
+                hardest = hardest.max(technique);
+                board.propagate();
+            }
+            None => { break; }
+        }
+    }
+    return (board, hardest);
+}
+
+/// Rate `board` by the hardest technique needed to solve it.
+pub fn rate(board: &SudokuBoard) -> Difficulty {
+    let (solved, hardest) = solve(board, &Technique::ALL);
+    if solved.solved() {
+        return Difficulty::of(hardest);
+    }
+    return Difficulty::Diabolical;
+}
+
+/// Return the rating of `board`, a standard puzzle, on the scale of
+/// Sudoku Explainer: the rating of the hardest step of a solve that
+/// always takes the easiest step, among hidden singles in a box
+/// (1.2) or a line (1.5), naked singles (2.3), intersections (2.6),
+/// pairs (3.0), X-wings (3.2) and triples (3.6).  Hidden subsets are
+/// rated as naked ones.  Return None if the techniques get stuck:
+/// Sudoku Explainer rates those puzzles higher, with chains.
+pub fn se_rating(board: &SudokuBoard) -> Option<f64> {
+    let rules = board.rules;
+    let shape = rules.shape;
+    let place = |cells: &mut [CandidateSet], cell: usize, digit: CandidateSet| {
+        cells[cell] = digit;
+        for &neighbor in rules.neighbors[cell].iter() {
+            cells[neighbor] &= !digit;
+        }
+    };
+    let mut cells = board.cells.clone();
+    for cell in (0 .. cells.len()).filter(|&c| board.cell_solved(c)) {
+        place(&mut cells, cell, board.cells[cell]);
+    }
+    let boxes: Vec<bool> = rules.houses.iter()
+        .map(|house| house.iter().all(|&c| shape.group(c) == shape.group(house[0])))
+        .collect();
+    let techniques = [
+        (Technique::Intersections, 2.6), (Technique::Pairs, 3.0),
+        (Technique::XWings, 3.2), (Technique::Triples, 3.6),
+    ];
+
+    let mut hardest: f64 = 1.0;
+    loop {
+        if cells.contains(&EMPTY_SET) {
+            return None;
+        }
+        if cells.iter().all(|c| c.count_ones() == 1) {
+            return Some(hardest);
+        }
+        // The hidden singles of the boxes, then of the other houses,
+        // then the naked singles: the cells left with one candidate
+        // that their neighbors still have.
+        let hidden = |in_box: bool| {
+            let houses = rules.houses.iter().zip(boxes.iter()).filter(|&(_, &b)| b == in_box);
+            for (house, _) in houses {
+                for d in 0 .. shape.side() {
+                    if let [cell] = places(house, &cells, 1 << d)[..] {
+                        if cells[cell].count_ones() > 1 {
+                            return Some((if in_box { 1.2 } else { 1.5 }, cell, 1 << d));
+                        }
+                    }
+                }
+            }
+            return None;
+        };
+        let naked = || (0 .. cells.len())
+            .find(|&c| cells[c].count_ones() == 1 && rules.neighbors[c].iter().any(|&n| cells[n] & cells[c] != 0))
+            .map(|cell| (2.3, cell, cells[cell]));
+        let single = hidden(true).or_else(|| hidden(false)).or_else(naked);
+        if let Some((rating, cell, digit)) = single {
+            hardest = hardest.max(rating);
+            place(&mut cells, cell, digit);
+            continue;
+        }
+        match techniques.iter().find(|&&(technique, _)| technique.apply(rules, &mut cells)) {
+            Some(&(_, rating)) => { hardest = hardest.max(rating); }
+            None => { return None; }
+        }
+    }
+}
+
+/// Return the number of guesses a solver makes on `board` when the
+/// techniques run dry, over its whole search tree, so that the
+/// hardest puzzles can be told apart.
+#[cfg(feature = "generate")]
+pub fn guesses(board: &SudokuBoard) -> usize {
+    let (board, _) = solve(board, &Technique::ALL);
+    if board.solved() || !board.solvable() {
+        return 0;
+    }
+    let mut count = 0;
+    if let Some(cell) = board.most_promising() {
+        let mut guess = board.clone();
+        for d in (0 .. board.rules.shape.side()).filter(|d| board.cells[cell] & (1 << d) != 0) {
+            guess.cells[cell] = 1 << d;
+            count += 1 + guesses(&guess);
+        }
+    }
+    return count;
+}
+
+/// A step of a human solver: the technique it takes, and the digits
+/// it places or the candidates it removes.  Cells are indices and
+/// digits count from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub technique: Technique,
+    /// The cells that the step changes.
+    pub cells: Vec<usize>,
+    /// The digits that the step places or removes.
+    pub digits: Vec<usize>,
+    pub eliminations: Vec<(usize, usize)>,
+    pub placements: Vec<(usize, usize)>,
+}
+
+impl Hint {
+    /// Return the step in the notation of players, on a grid of
+    /// `shape`: the technique, then `r5c6=4` for a digit placed and
+    /// `r2c7<>3` for a candidate removed.
+    pub fn text(&self, shape: Shape) -> String {
+        let name = |cell: usize| format!("r{}c{}", shape.row(cell) + 1, shape.col(cell) + 1);
+        let mut words = vec![self.technique.name().to_string()];
+        words.extend(self.placements.iter().map(|&(cell, digit)| format!("{}={}", name(cell), digit)));
+        words.extend(self.eliminations.iter().map(|&(cell, digit)| format!("{}<>{}", name(cell), digit)));
+        return words.join(" ");
+    }
+}
+
+/// Return the next step from the position of `board`, whose solved
+/// cells are the digits placed so far: a single if there is one,
+/// else the first removal of the simplest technique that removes
+/// anything.  The candidates that the digits placed see are left out,
+/// as in the pencil marks of a player.  Return None if no technique
+/// applies or the position is wrong.
+pub fn hint(board: &SudokuBoard) -> Option<Hint> {
+    let rules = board.rules;
+    let mut cells = board.cells.clone();
+    for cell in (0 .. cells.len()).filter(|&c| board.cell_solved(c)) {
+        for &neighbor in rules.neighbors[cell].iter() {
+            cells[neighbor] &= !board.cells[cell];
+        }
+    }
+    if cells.contains(&EMPTY_SET) {
+        return None;
+    }
+    let digit = |set: CandidateSet| set.trailing_zeros() as usize + 1;
+    let placement = |cell: usize, set: CandidateSet| Hint {
+        technique: Technique::Singles,
+        cells: vec![cell],
+        digits: vec![digit(set)],
+        eliminations: Vec::new(),
+        placements: vec![(cell, digit(set))],
+    };
+
+    let open = |c: &usize| !board.cell_solved(*c);
+    if let Some(cell) = (0 .. cells.len()).filter(open).find(|&c| cells[c].count_ones() == 1) {
+        return Some(placement(cell, cells[cell]));
+    }
+    for house in rules.houses.iter() {
+        for d in 0 .. rules.shape.side() {
+            if let [cell] = places(house, &cells, 1 << d)[..] {
+                if open(&cell) {
+                    return Some(placement(cell, 1 << d));
+                }
+            }
+        }
+    }
+
+    for &technique in Technique::ALL[1 ..].iter() {
+        let mut after = cells.clone();
+        if !technique.apply(rules, &mut after) {
+            continue;
+        }
+        let mut eliminations = Vec::new();
+        for (cell, (&set, &left)) in cells.iter().zip(after.iter()).enumerate() {
+            for d in (0 .. rules.shape.side()).filter(|d| set & !left & (1 << d) != 0) {
+                eliminations.push((cell, d + 1));
+            }
+        }
+        let mut changed: Vec<usize> = eliminations.iter().map(|e| e.0).collect();
+        let mut digits: Vec<usize> = eliminations.iter().map(|e| e.1).collect();
+        changed.dedup();
+        digits.sort();
+        digits.dedup();
+        return Some(Hint { technique, cells: changed, digits, eliminations, placements: Vec::new() });
+    }
+    return None;
+}
+
+/// Return the cells of `board` that the techniques leave open, whose
+/// digits only a search finds, and the greatest depth of the guesses
+/// of a search applying every technique before each guess, until it
+/// finds a solution.
+pub fn guessed(board: &SudokuBoard) -> (Vec<usize>, usize) {
+    fn search(board: &SudokuBoard, depth: usize, deepest: &mut usize) -> bool {
+        let (board, _) = solve(board, &Technique::ALL);
+        *deepest = (*deepest).max(depth);
+        if board.solved() || !board.solvable() {
+            return board.solved();
+        }
+        if let Some(cell) = board.most_promising() {
+            let mut guess = board.clone();
+            for d in (0 .. board.rules.shape.side()).filter(|d| board.cells[cell] & (1 << d) != 0) {
+                guess.cells[cell] = 1 << d;
+                if search(&guess, depth + 1, deepest) {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    let (position, _) = solve(board, &Technique::ALL);
+    let open = (0 .. position.cells.len()).filter(|&c| !position.cell_solved(c)).collect();
+    let mut deepest = 0;
+    search(board, 0, &mut deepest);
+    return (open, deepest);
+}
+
+/// Return the cells of `house` that have `digit` as a candidate.
+fn places(house: &[usize], cells: &[CandidateSet], digit: CandidateSet) -> Vec<usize> {
+    house.iter().cloned().filter(|&c| cells[c] & digit != 0).collect()
+}
+
+/// Remove `digit` from `targets`; return whether any cell lost it.
+fn eliminate(targets: &[usize], cells: &mut [CandidateSet], digit: CandidateSet) -> bool {
+    let mut changed = false;
+    for &cell in targets.iter() {
+        changed = changed || cells[cell] & digit != 0;
+        cells[cell] &= !digit;
+    }
+    return changed;
+}
+
+/// When the candidates of a digit in a house all lie in a second
+/// house, the digit goes in the second house there, so its other
+/// cells lose it.
+fn intersections(rules: &Rules, cells: &mut [CandidateSet]) -> bool {
+    let houses = &rules.houses;
+    for base in houses.iter() {
+        for other in houses.iter().filter(|h| *h != base) {
+            for d in 0 .. rules.shape.side() {
+                let digit = 1 << d;
+                let base_places = places(base, cells, digit);
+                if base_places.len() < 2 || !base_places.iter().all(|c| other.contains(c)) {
+                    continue;
+                }
+                let rest: Vec<usize> = other.iter().cloned().filter(|c| !base.contains(c)).collect();
+                if eliminate(&rest, cells, digit) {
+                    return true;
+                }
+            }
+        }
+    }
+    return false;
+}
+
+/// Naked and hidden subsets of `size` digits in a house: when as
+/// many cells as digits hold nothing but those digits, the other
+/// cells lose them; when the digits can only go in as many cells,
+/// those cells lose their other candidates.
+fn subsets(rules: &Rules, cells: &mut [CandidateSet], size: usize) -> bool {
+    for house in rules.houses.iter() {
+        let open: Vec<usize> = house.iter().cloned().filter(|&c| cells[c].count_ones() > 1).collect();
+        if open.len() <= size {
+            continue;
+        }
+        let candidates = open.iter().fold(EMPTY_SET, |acc, &c| acc | cells[c]);
+        for set in digit_sets(candidates, size) {
+            let naked: Vec<usize> = open.iter().cloned().filter(|&c| cells[c] & !set == 0).collect();
+            if naked.len() == size {
+                let others: Vec<usize> = open.iter().cloned().filter(|c| !naked.contains(c)).collect();
+                if eliminate(&others, cells, set) {
+                    return true;
+                }
+            }
+            let hidden: Vec<usize> = open.iter().cloned().filter(|&c| cells[c] & set != 0).collect();
+            if hidden.len() == size && hidden.iter().any(|&c| cells[c] & !set != 0) {
+                for &cell in hidden.iter() {
+                    cells[cell] &= set;
+                }
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+/// Return every set of `size` digits taken from `digits`.
+fn digit_sets(digits: CandidateSet, size: usize) -> Vec<CandidateSet> {
+    if size == 0 {
+        return vec![EMPTY_SET];
+    }
+    if (digits.count_ones() as usize) < size {
+        return Vec::new();
+    }
+    let lowest = digits & digits.wrapping_neg();
+    let mut sets: Vec<CandidateSet> = digit_sets(digits & !lowest, size - 1).into_iter()
+        .map(|set| set | lowest)
+        .collect();
+    sets.extend(digit_sets(digits & !lowest, size));
+    return sets;
+}
+
+/// When the candidates of a digit in two disjoint houses lie in two
+/// other houses, the digit goes twice in those, at the candidates,
+/// so their other cells lose it.
+fn x_wings(rules: &Rules, cells: &mut [CandidateSet]) -> bool {
+    let houses = &rules.houses;
+    let disjoint = |a: &[usize], b: &[usize]| !a.iter().any(|c| b.contains(c));
+    for d in 0 .. rules.shape.side() {
+        let digit = 1 << d;
+        for (i, first) in houses.iter().enumerate() {
+            for second in houses[i + 1 ..].iter().filter(|h| disjoint(first, h)) {
+                let mut base = places(first, cells, digit);
+                if base.len() != 2 {
+                    continue;
+                }
+                base.extend(places(second, cells, digit));
+                if base.len() != 4 {
+                    continue;
+                }
+                // The cover houses each hold one candidate of each base house.
+                let covers: Vec<&Vec<usize>> = houses.iter()
+                    .filter(|h| h.contains(&base[0]) || h.contains(&base[1]))
+                    .filter(|h| base.iter().filter(|c| h.contains(c)).count() == 2)
+                    .collect();
+                for (j, a) in covers.iter().enumerate() {
+                    for b in covers[j + 1 ..].iter().filter(|b| disjoint(a, b)) {
+                        if !base.iter().all(|c| a.contains(c) || b.contains(c)) {
+                            continue;
+                        }
+                        let rest: Vec<usize> = a.iter().chain(b.iter()).cloned()
+                            .filter(|c| !base.contains(c))
+                            .collect();
+                        if eliminate(&rest, cells, digit) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    return false;
+}
+
+#[test]
+fn test_rate() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let rating = |digits: &str| rate(&SudokuBoard::from_str(digits, &rules));
+    assert_eq!(rating(PUZZLE),
+               Difficulty::Easy);
+    // The first puzzle of Project Euler's problem 96 with its
+    // last two rows empty can no longer be solved without guessing.
+    assert_eq!(rating(&format!("{}{}", &PUZZLE[.. 63], ".".repeat(18))),
+               Difficulty::Diabolical);
+}
+
+#[test]
+fn test_hint() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let board = SudokuBoard::from_str(PUZZLE, &rules);
+    let first = hint(&board).unwrap();
+    assert_eq!(first.technique, Technique::Singles);
+    assert_eq!(first.placements.len(), 1);
+    let (cell, digit) = first.placements[0];
+    assert_eq!(board.solve().unwrap().cells[cell], 1 << (digit - 1));
+    assert_eq!(first.text(Shape::STANDARD), "singles r5c6=4");
+
+    // Where singles get stuck, a harder technique removes candidates.
+    let board = SudokuBoard::from_str("4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......", &rules);
+    let (stuck, _) = solve(&board, &[Technique::Singles]);
+    let next = hint(&stuck).unwrap();
+    assert!(next.technique > Technique::Singles);
+    assert!(next.placements.is_empty() && !next.eliminations.is_empty());
+    assert!(next.eliminations.iter().all(|&(cell, digit)| stuck.cells[cell] & (1 << (digit - 1)) != 0));
+}
+
+#[test]
+fn test_se_rating() {
+    use super::{standard_rules, INKALA};
+
+    let rules = standard_rules();
+    let rating = |digits: &str| se_rating(&SudokuBoard::from_str(digits, &rules));
+    assert_eq!(rating("4.8..56....27.4......8..9.567...8.9.......37....3....17......6...6.5.1..2...9...."), Some(2.6));
+    assert_eq!(rating(".6......75......9..37.418....6.7.......5.6..9.....21....3...2...8....57.4.573...."), Some(3.2));
+    assert_eq!(rating(INKALA), None);
+}
+
+#[cfg(feature = "generate")]
+#[test]
+fn test_guesses() {
+    use super::{standard_rules, INKALA, PUZZLE};
+
+    let rules = standard_rules();
+    let guessed = |digits: &str| guesses(&SudokuBoard::from_str(digits, &rules));
+    assert_eq!(guessed(PUZZLE), 0);
+    // Arto Inkala's "world's hardest sudoku".
+    let inkala = SudokuBoard::from_str(INKALA, &rules);
+    assert!(guesses(&inkala) > 0);
+    let (open, depth) = super::logic::guessed(&inkala);
+    assert!(!open.is_empty() && depth > 0);
+    assert!(open.iter().all(|&c| !inkala.cell_solved(c)));
+}
@@ -1,6 +1,98 @@
-use std::collections::BTreeSet;
-use std::io;
+#![allow(clippy::needless_return)]
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::iter;
+use std::mem;
 use std::process;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+mod alphabet;
+mod analyze;
+#[cfg(feature = "variants")]
+mod arrow;
+mod bench;
+#[cfg(feature = "play")]
+mod campaign;
+mod canonical;
+mod checkpoint;
+mod cells;
+mod check;
+#[cfg(feature = "play")]
+mod config;
+mod constraint;
+#[cfg(feature = "play")]
+mod daily;
+#[cfg(feature = "play")]
+mod game;
+#[cfg(feature = "generate")]
+mod generate;
+mod grade;
+#[cfg(feature = "render")]
+mod hodoku;
+#[cfg(feature = "variants")]
+mod inequality;
+mod json;
+#[cfg(feature = "variants")]
+mod json_puzzle;
+#[cfg(feature = "variants")]
+mod killer;
+mod relation;
+mod rng;
+#[cfg(feature = "variants")]
+mod rules_file;
+mod sample;
+#[cfg(feature = "play")]
+mod save;
+#[cfg(feature = "variants")]
+mod layout;
+mod log;
+mod logic;
+mod marks;
+#[cfg(feature = "serve")]
+mod metrics;
+#[cfg(feature = "generate")]
+mod mutate;
+mod output;
+mod pack;
+mod pipeline;
+#[cfg(feature = "play")]
+mod play;
+mod possible;
+#[cfg(feature = "render")]
+mod qr;
+#[cfg(feature = "variants")]
+mod sandwich;
+mod stats;
+mod symmetry;
+#[cfg(feature = "serve")]
+mod server;
+#[cfg(feature = "variants")]
+mod sum;
+#[cfg(feature = "play")]
+mod terminal;
+mod trace;
+mod transform;
+#[cfg(feature = "serve")]
+mod websocket;
+
+use alphabet::Alphabet;
+use canonical::Isomorphisms;
+use checkpoint::{Checkpoint, Outcome};
+use cells::Cells;
+use constraint::{AllDifferent, Constraint};
+#[cfg(feature = "variants")]
+use json_puzzle::Clues;
+#[cfg(feature = "variants")]
+use layout::Layout;
+use relation::{Link, Relation};
+use rng::Rng;
+#[cfg(feature = "generate")]
+use symmetry::Symmetry;
 
 // Sudoku board constants
 const NSQRT: usize = 3;
@@ -10,89 +102,783 @@ const NSQ: usize = N*N;
 // Set constants
 type CandidateSet = u32;
 const EMPTY_SET: CandidateSet = 0;
+#[cfg(any(feature = "variants", test))]
 const FULL_SET: CandidateSet = 0x1FF;
 
 fn error(msg: String) -> ! {
+    output::flush();
     println!("error: {}", msg);
     process::exit(1);
 }
 
-/// Return the 0-based row index of `cell`.
-fn row(cell: usize) -> usize {
-    cell / N
+/// Largest grid side: every digit needs a bit in a CandidateSet.
+const MAX_SIDE: usize = CandidateSet::BITS as usize;
+
+/// The shape of a grid: its boxes have `box_rows` rows of
+/// `box_cols` cells, and the grid has as many rows, columns
+/// and boxes as a box has cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Shape {
+    box_rows: usize,
+    box_cols: usize,
+}
+
+impl Shape {
+    /// The shape of the classic 9x9 grid.
+    const STANDARD: Shape = Shape { box_rows: NSQRT, box_cols: NSQRT };
+
+    /// Return the shape of a Latin square of order `side`, which
+    /// has no boxes; its rows stand in for them.
+    fn latin(side: usize) -> Option<Shape> {
+        if side == 0 || side > MAX_SIDE {
+            return None;
+        }
+        return Some(Shape { box_rows: 1, box_cols: side });
+    }
+
+    /// Return the shape of a grid with `side` rows; its boxes are
+    /// as square as possible and wider than tall, e.g., 2x3 for 6.
+    fn from_side(side: usize) -> Option<Shape> {
+        if side > MAX_SIDE {
+            return None;
+        }
+        let box_rows = (2 .. side)
+            .take_while(|r| r * r <= side)
+            .filter(|r| side.is_multiple_of(*r))
+            .last()?;
+        return Some(Shape { box_rows, box_cols: side / box_rows });
+    }
+
+    /// Number of rows, columns, boxes and digits.
+    fn side(self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    /// Number of cells of the grid.
+    fn cells(self) -> usize {
+        self.side() * self.side()
+    }
+
+    /// The set of all the digits.
+    fn full_set(self) -> CandidateSet {
+        CandidateSet::MAX >> (MAX_SIDE - self.side())
+    }
+
+    /// Return the 0-based row index of `cell`.
+    fn row(self, cell: usize) -> usize {
+        cell / self.side()
+    }
+
+    /// Return the 0-based column index of `cell`.
+    fn col(self, cell: usize) -> usize {
+        cell % self.side()
+    }
+
+    /// Return the 0-based index of the upper-left cell of `cell`'s group.
+    fn group(self, cell: usize) -> usize {
+        let r = self.row(cell);
+        let c = self.col(cell);
+        (self.side() * (r - r % self.box_rows)) + (c - c % self.box_cols)
+    }
+}
+
+/// Return the row and column, counting from 0, of the cell named
+/// `name` in a grid of `side` rows, e.g., (0, 8) for `r1c9`.
+fn cell_position(name: &str, side: usize) -> Option<(usize, usize)> {
+    let mut rc = name.strip_prefix('r')?.splitn(2, 'c');
+    let r = rc.next()?.parse::<usize>().ok()?;
+    let c = rc.next()?.parse::<usize>().ok()?;
+    if (1 ..= side).contains(&r) && (1 ..= side).contains(&c) {
+        return Some((r - 1, c - 1));
+    }
+    return None;
+}
+
+/// Return the value of the option `arg`, the next of `args`.
+fn value<T: FromStr, I: Iterator<Item = String>>(arg: &str, args: &mut I) -> T {
+    let value = args.next().unwrap_or_else(|| error(format!("{} needs a value", arg)));
+    return value.parse()
+        .unwrap_or_else(|_| error(format!("invalid value {:?} for {}", value, arg)));
 }
 
-/// Return the 0-based row index of `cell`.
-fn col(cell: usize) -> usize {
-    cell % N
+/// Return the number of threads given as the value of the option
+/// `arg`, the next of `args`.
+fn threads_value<I: Iterator<Item = String>>(arg: &str, args: &mut I) -> usize {
+    let threads = value(arg, args);
+    if threads == 0 {
+        error(format!("{} needs at least one thread", arg));
+    }
+    return threads;
 }
 
-/// Return the 0-based index of the upper-left cell of `cell`'s group.
-fn group(cell: usize) -> usize {
-    let r = row(cell);
-    let c = col(cell);
-    (N * (r - r % NSQRT)) + (c - c % NSQRT)
+/// Return the techniques named, separated by commas, in the value of
+/// the option `arg`, the next of `args`.
+#[cfg(any(feature = "generate", feature = "render"))]
+fn techniques_value<I: Iterator<Item = String>>(arg: &str, args: &mut I) -> Vec<logic::Technique> {
+    let names: String = value(arg, args);
+    return names.split(',')
+        .map(|name| logic::Technique::from_name(name)
+             .unwrap_or_else(|| error(format!("unknown technique {:?}", name))))
+        .collect();
 }
 
-/// Return the neighbors (indices) of `cell`:
-/// - The cells on the same row;
-/// - The cells on the same column;
-/// - The same in the same group.
-/// Note: `cell` is not a neighbor of itself.
-fn neighbors_of(cell: usize) -> Vec<usize> {
-    let mut all_neighbors: BTreeSet<usize> = BTreeSet::new();
+/// Return the bytes of a size such as `512K`, `64M` or `2G`, in
+/// powers of 1024, or plain bytes without a suffix.
+fn parse_size(text: &str) -> Option<usize> {
+    let (number, shift) = match text.chars().last()? {
+        'K' | 'k' => (&text[.. text.len() - 1], 10),
+        'M' | 'm' => (&text[.. text.len() - 1], 20),
+        'G' | 'g' => (&text[.. text.len() - 1], 30),
+        _ => (text, 0),
+    };
+    return number.parse::<usize>().ok()?.checked_mul(1 << shift);
+}
+
+/// Return the cell named `name`, e.g., `r1c9` for the last
+/// cell of the first row.
+#[cfg(any(feature = "variants", test))]
+fn parse_cell(name: &str) -> Result<usize, String> {
+    return cell_position(name, N).map(|(r, c)| N * r + c)
+        .ok_or_else(|| format!("invalid cell {:?}", name));
+}
+
+/// Upper-left cells of the four extra windows of hyper sudoku.
+const HYPER_WINDOWS: [usize; 4] = [10, 14, 46, 50];
+
+/// The rule sets understood by the solver.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variant {
+    Standard,
+    Hyper,
+    Jigsaw,
+    #[cfg(feature = "variants")]
+    Killer,
+    /// Overlapping grids, such as those of samurai sudoku.
+    #[cfg(feature = "variants")]
+    MultiGrid(&'static Layout),
+    #[cfg(feature = "variants")]
+    GreaterThan,
+    Latin,
+}
+
+/// Extra rules that can be layered onto the standard grids.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Extra {
+    AntiKnight,
+    AntiKing,
+    Asterisk,
+    /// X-sudoku: the two main diagonals.
+    Diagonals,
+    NonConsecutive,
+}
+
+impl Extra {
+    fn from_name(name: &str) -> Option<Extra> {
+        match name {
+            "anti-knight" => Some(Extra::AntiKnight),
+            "anti-king" | "no-touch" => Some(Extra::AntiKing),
+            "asterisk" => Some(Extra::Asterisk),
+            "diagonals" | "x" => Some(Extra::Diagonals),
+            "non-consecutive" => Some(Extra::NonConsecutive),
+            _ => None,
+        }
+    }
+
+    /// Whether the rule applies to grids of any shape; the
+    /// others only make sense on a 9x9 grid.
+    fn any_shape(self) -> bool {
+        self != Extra::Asterisk
+    }
+
+    /// Return the units this rule adds on a grid of `shape`, e.g.,
+    /// the pairs of cells that it forbids from holding the same digit.
+    fn units(self, shape: Shape) -> Vec<Vec<usize>> {
+        match self {
+            Extra::AntiKnight => move_pairs(shape, &KNIGHT_MOVES),
+            Extra::AntiKing => move_pairs(shape, &DIAGONAL_MOVES),
+            Extra::Asterisk => vec![ASTERISK.to_vec()],
+            Extra::Diagonals => {
+                let side = shape.side();
+                vec![(0 .. side).map(|i| side * i + i).collect(),
+                     (0 .. side).map(|i| side * i + side - 1 - i).collect()]
+            }
+            Extra::NonConsecutive => Vec::new(),
+        }
+    }
+
+    /// Return the links this rule adds between cells on a grid of `shape`.
+    fn links(self, shape: Shape) -> Vec<Link> {
+        match self {
+            Extra::NonConsecutive => {
+                move_pairs(shape, &ORTHOGONAL_MOVES).into_iter()
+                    .map(|pair| Link::new(pair[0], pair[1], Relation::NonConsecutive))
+                    .collect()
+            }
+            Extra::AntiKnight | Extra::AntiKing | Extra::Asterisk | Extra::Diagonals => Vec::new(),
+        }
+    }
+}
+
+/// The cells of the asterisk: r2c5, r3c3, r3c7, r5c2, r5c5, r5c8,
+/// r7c3, r7c7 and r8c5.
+const ASTERISK: [usize; 9] = [13, 20, 24, 37, 40, 43, 56, 60, 67];
+
+/// Offsets (rows, columns) of the moves of a chess knight.
+const KNIGHT_MOVES: [(isize, isize); 8] = [
+    (-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)
+];
+
+/// Offsets of the diagonal moves of a chess king; its other
+/// moves stay within a row or a column.
+const DIAGONAL_MOVES: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+/// Offsets of the orthogonally adjacent cells.
+const ORTHOGONAL_MOVES: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Return the pairs of cells of a grid of `shape` that are one of
+/// `moves` apart.  Every pair appears once, with its smaller cell first.
+fn move_pairs(shape: Shape, moves: &[(isize, isize)]) -> Vec<Vec<usize>> {
+    let side = shape.side() as isize;
+    let mut pairs = Vec::new();
+    for cell in 0 .. shape.cells() {
+        for &(dr, dc) in moves.iter() {
+            let r = shape.row(cell) as isize + dr;
+            let c = shape.col(cell) as isize + dc;
+            if r < 0 || r >= side || c < 0 || c >= side {
+                continue;
+            }
+            let other = (side * r + c) as usize;
+            if other > cell {
+                pairs.push(vec![cell, other]);
+            }
+        }
+    }
+    return pairs;
+}
+
+impl Variant {
+    fn from_name(name: &str) -> Option<Variant> {
+        match name {
+            "standard" => Some(Variant::Standard),
+            "hyper" | "windoku" => Some(Variant::Hyper),
+            "jigsaw" => Some(Variant::Jigsaw),
+            #[cfg(feature = "variants")]
+            "killer" => Some(Variant::Killer),
+            #[cfg(feature = "variants")]
+            "samurai" => Some(Variant::MultiGrid(&layout::SAMURAI)),
+            #[cfg(feature = "variants")]
+            "greater-than" => Some(Variant::GreaterThan),
+            "latin" => Some(Variant::Latin),
+            _ => None,
+        }
+    }
+
+    /// Whether the variant applies to grids of any shape; the
+    /// others only make sense on 9x9 grids.
+    fn any_shape(self) -> bool {
+        match self {
+            Variant::Standard | Variant::Jigsaw | Variant::Latin => true,
+            Variant::Hyper => false,
+            #[cfg(feature = "variants")]
+            Variant::Killer | Variant::MultiGrid(_) | Variant::GreaterThan => false,
+        }
+    }
+
+    /// Whether the variant has several overlapping grids.
+    #[cfg(feature = "variants")]
+    fn multi_grid(self) -> bool {
+        self.layout().is_some()
+    }
+
+    /// Whether the variant has several overlapping grids; none does
+    /// without the variants.
+    #[cfg(not(feature = "variants"))]
+    fn multi_grid(self) -> bool {
+        false
+    }
+
+    /// The layout of the grids of a multi-grid variant.
+    #[cfg(feature = "variants")]
+    fn layout(self) -> Option<&'static Layout> {
+        match self {
+            Variant::MultiGrid(layout) => Some(layout),
+            _ => None,
+        }
+    }
+
+    /// Number of lines taken by a puzzle of this variant.
+    fn lines(self) -> usize {
+        match self {
+            #[cfg(feature = "variants")]
+            Variant::MultiGrid(layout) => layout.height(),
+            #[cfg(feature = "variants")]
+            Variant::GreaterThan => inequality::LINES,
+            _ => 1,
+        }
+    }
+}
+
+/// Return the cells of the NSQRT x NSQRT square whose upper-left cell is `leader`.
+fn square(leader: usize) -> Vec<usize> {
+    let (row, col) = (leader / N, leader % N);
+    let mut cells = Vec::with_capacity(N);
+    for r in row .. row + NSQRT {
+        for c in col .. col + NSQRT {
+            cells.push(N * r + c);
+        }
+    }
+    return cells;
+}
+
+/// Return the region table of a board of `shape`: the region
+/// of a cell is identified by the upper-left cell of its group.
+fn standard_regions(shape: Shape) -> Vec<usize> {
+    (0 .. shape.cells()).map(|cell| shape.group(cell)).collect()
+}
+
+/// Create a region table from a string with one label per cell;
+/// cells with the same label belong to the same region.
+/// There must be as many labels as the side of `shape`, each
+/// used by that many connected cells.
+fn parse_regions(shape: Shape, labels: &str) -> Result<Vec<usize>, String> {
+    let (side, ncells) = (shape.side(), shape.cells());
+    if labels.chars().count() != ncells {
+        return Err(format!("invalid region map length; expected {}, got {}",
+                           ncells, labels.chars().count()));
+    }
+    let mut ids: BTreeMap<char, usize> = BTreeMap::new();
+    let mut sizes: Vec<usize> = Vec::with_capacity(side);
+    let mut regions = Vec::with_capacity(ncells);
+    for label in labels.chars() {
+        let next_id = ids.len();
+        let id = *ids.entry(label).or_insert(next_id);
+        if id == sizes.len() {
+            sizes.push(0);
+        }
+        sizes[id] += 1;
+        regions.push(id);
+    }
+    if sizes.len() != side || sizes.iter().any(|&size| size != side) {
+        return Err(format!("invalid region map; expected {} regions of {} cells", side, side));
+    }
+    if !connected(shape, &regions) {
+        return Err("invalid region map; every region must be connected".to_string());
+    }
+    return Ok(regions);
+}
+
+/// Return the region map of a random jigsaw grid of `shape`, with
+/// one label per cell, and a solution of it.  The regions start as
+/// the groups of a random grid, then trade cells holding the same
+/// digit along their borders as long as they stay connected, so
+/// that the grid still solves them.
+#[cfg(feature = "generate")]
+fn random_jigsaw(shape: Shape, rng: &mut Rng) -> (String, Cells) {
+    let rules = Rules::new(shape, units(shape, Variant::Standard, &standard_regions(shape)));
+    let grid = SudokuBoard::random_solution(&rules, rng).unwrap().cells;
 
-    // Neighbors in row and column
-    for i in 0..N {
-        all_neighbors.insert((N * row(cell)) + i);
-        all_neighbors.insert((N * i) + col(cell));
+    let mut regions = standard_regions(shape);
+    let borders = move_pairs(shape, &ORTHOGONAL_MOVES);
+    for _ in 0 .. 100 * shape.cells() {
+        let pair = &borders[rng.below(borders.len())];
+        let side = rng.below(2);
+        let (cell, from, to) = (pair[side], regions[pair[side]], regions[pair[1 - side]]);
+        if from == to {
+            continue;
+        }
+        let other = (0 .. shape.cells()).find(|&c| regions[c] == to && grid[c] == grid[cell]).unwrap();
+        regions[cell] = to;
+        regions[other] = from;
+        if !connected(shape, &regions) {
+            regions[cell] = from;
+            regions[other] = to;
+        }
+    }
+    let labels: BTreeMap<usize, char> = standard_regions(shape).into_iter()
+        .collect::<BTreeSet<usize>>().into_iter()
+        .enumerate()
+        .map(|(i, region)| (region, num_to_char(i as u32 + 1)))
+        .collect();
+    return (regions.iter().map(|region| labels[region]).collect(), grid);
+}
+
+/// Return the cage map and cage sums of a random killer grid, and a
+/// solution of it, or None if the cages run out of labels.
+#[cfg(all(feature = "generate", feature = "variants"))]
+fn random_killer(rng: &mut Rng) -> Option<(String, Cells)> {
+    let shape = Shape::STANDARD;
+    let rules = Rules::new(shape, units(shape, Variant::Standard, &standard_regions(shape)));
+    let grid = SudokuBoard::random_solution(&rules, rng).unwrap().cells;
+    let (labels, sums) = killer::random_cages(&grid, rng)?;
+    return Some((format!("{} {}", labels, sums), grid));
+}
+
+/// Whether every region of a grid of `shape` is in one piece.  The
+/// regions are flooded through orthogonally adjacent cells, so there
+/// are as many floods as regions if they are all connected.
+fn connected(shape: Shape, regions: &[usize]) -> bool {
+    let ncells = shape.cells();
+    let mut adjacent = vec![Vec::new(); ncells];
+    for pair in move_pairs(shape, &ORTHOGONAL_MOVES) {
+        adjacent[pair[0]].push(pair[1]);
+        adjacent[pair[1]].push(pair[0]);
+    }
+    let mut flooded = vec![false; ncells];
+    let mut floods = 0;
+    for start in 0 .. ncells {
+        if flooded[start] {
+            continue;
+        }
+        floods += 1;
+        flooded[start] = true;
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            for &next in adjacent[cell].iter() {
+                if !flooded[next] && regions[next] == regions[cell] {
+                    flooded[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
     }
+    return floods == shape.side();
+}
 
-    // Neighbors in group
-    let leader = group(cell);
-    for r in row(leader) .. row(leader) + NSQRT {
-        for c in col(leader) .. col(leader) + NSQRT {
-            all_neighbors.insert(N * r + c);
+/// Return the units of `variant` on a grid of `shape`, i.e.,
+/// the sets of cells that must all contain different digits:
+///   - The rows and columns of every sudoku;
+///   - The regions described by `regions`, a table mapping
+///     every cell to a region identifier;
+///   - The four shaded windows of hyper sudoku.
+fn units(shape: Shape, variant: Variant, regions: &[usize]) -> Vec<Vec<usize>> {
+    let side = shape.side();
+    let mut all_units = Vec::new();
+    for i in 0 .. side {
+        all_units.push((0 .. side).map(|c| side * i + c).collect());
+        all_units.push((0 .. side).map(|r| side * r + i).collect());
+    }
+    let mut by_region: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (cell, &region) in regions.iter().enumerate() {
+        by_region.entry(region).or_default().push(cell);
+    }
+    all_units.extend(by_region.into_values());
+    if variant == Variant::Hyper {
+        for &leader in HYPER_WINDOWS.iter() {
+            all_units.push(square(leader));
         }
     }
+    return all_units;
+}
+
+/// Return the neighbors (indices) of `cell`, i.e., the cells
+/// that share at least one unit with it.
+/// Note: `cell` is not a neighbor of itself.
+fn neighbors_of(cell: usize, units: &[Vec<usize>]) -> Vec<usize> {
+    let mut all_neighbors: BTreeSet<usize> = BTreeSet::new();
+
+    for unit in units.iter().filter(|u| u.contains(&cell)) {
+        all_neighbors.extend(unit.iter().cloned());
+    }
 
     all_neighbors.remove(&cell);
     return all_neighbors.into_iter().collect();
 }
 
+/// Return the neighbors of every cell of a board with the given units.
+/// The board is assumed to end with the last cell of the units.
+fn neighbors(units: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let ncells = units.iter().flat_map(|u| u.iter()).max().map_or(0, |&c| c + 1);
+    (0 .. ncells).map(|cell| neighbors_of(cell, units)).collect()
+}
+
+
+/// The rules of a puzzle: the shape of its grid, the neighbors
+/// of every cell, which must hold different digits, the houses,
+/// i.e., the units that hold every digit, and the constraints
+/// that the digits must satisfy.
+struct Rules {
+    shape: Shape,
+    neighbors: Vec<Vec<usize>>,
+    houses: Vec<Vec<usize>>,
+    constraints: Vec<Box<dyn Constraint>>,
+}
+
+impl Rules {
+    /// Create the rules of a grid of `shape` whose `units` must
+    /// hold different digits.
+    fn new(shape: Shape, units: Vec<Vec<usize>>) -> Self {
+        let neighbors = neighbors(&units);
+        let mut rules = Rules { shape, neighbors, houses: Vec::new(), constraints: Vec::new() };
+        rules.add_houses(&units);
+        rules.extend(units.into_iter().map(AllDifferent::new).collect());
+        return rules;
+    }
+
+    /// Add `constraints` to the rules.
+    fn extend<C: Constraint + 'static>(&mut self, constraints: Vec<C>) {
+        for constraint in constraints {
+            self.constraints.push(Box::new(constraint));
+        }
+    }
+
+    /// Add `units` whose cells must hold different digits.
+    #[cfg(feature = "variants")]
+    fn add_units(&mut self, units: Vec<Vec<usize>>) {
+        for unit in units.iter() {
+            for &cell in unit.iter() {
+                let mut all_neighbors: BTreeSet<usize> = self.neighbors[cell].iter().cloned().collect();
+                all_neighbors.extend(unit.iter().cloned().filter(|&n| n != cell));
+                self.neighbors[cell] = all_neighbors.into_iter().collect();
+            }
+        }
+        self.add_houses(&units);
+        self.extend(units.into_iter().map(AllDifferent::new).collect());
+    }
+
+    /// Record the `units` that hold every digit as houses.
+    fn add_houses(&mut self, units: &[Vec<usize>]) {
+        let side = self.shape.side();
+        self.houses.extend(units.iter().filter(|unit| unit.len() == side).cloned());
+    }
+
+    /// Add the clues of a JSON puzzle.
+    #[cfg(feature = "variants")]
+    fn add_clues(&mut self, clues: Clues) {
+        self.extend(clues.links);
+        self.extend(clues.sandwiches);
+        self.extend(clues.arrows);
+        self.extend(clues.little_killers);
+    }
+
+    /// Create the rules of a puzzle of `variant` on a grid of `shape`
+    /// with the `extras` rules on top.  `fields` are the
+    /// whitespace-separated fields that follow the digits on the
+    /// puzzle's line: the region map of a jigsaw puzzle, the cage
+    /// map and cage sums of a killer puzzle.  Return why the rules
+    /// cannot be created, if they cannot.
+    fn for_puzzle(shape: Shape, variant: Variant, extras: &[Extra], fields: &[&str]) -> Result<Self, String> {
+        Rules::check(shape, variant, extras)?;
+        let field = |i: usize, what: &str| -> Result<&str, String> {
+            return fields.get(i).cloned().ok_or_else(|| format!("missing {}", what));
+        };
+        let mut all_units = match variant {
+            Variant::Standard | Variant::Hyper => units(shape, variant, &standard_regions(shape)),
+            Variant::Jigsaw => {
+                let regions = parse_regions(shape, field(0, "region map")?)?;
+                units(shape, variant, &regions)
+            }
+            // A Latin square has no boxes.
+            Variant::Latin => units(shape, variant, &[]),
+            #[cfg(feature = "variants")]
+            Variant::Killer | Variant::GreaterThan => units(shape, variant, &standard_regions(shape)),
+            #[cfg(feature = "variants")]
+            Variant::MultiGrid(layout) => layout.units(),
+        };
+        // The digits of a cage never repeat, and a pair of cells
+        // that must differ is a unit of two cells.
+        #[cfg(feature = "variants")]
+        let cages = match variant {
+            Variant::Killer => killer::parse_cages(field(0, "cage map")?, field(1, "cage sums")?)?,
+            _ => Vec::new(),
+        };
+        #[cfg(feature = "variants")]
+        all_units.extend(cages.iter().map(|cage| cage.cells.clone()));
+        let mut links = Vec::new();
+        for extra in extras.iter() {
+            all_units.extend(extra.units(shape));
+            links.extend(extra.links(shape));
+        }
+        let mut rules = Rules::new(shape, all_units);
+        #[cfg(feature = "variants")]
+        rules.extend(cages);
+        rules.extend(links);
+        return Ok(rules);
+    }
+
+    /// Return why puzzles of `variant` on a grid of `shape`, with the
+    /// `extras` rules, cannot be, if they cannot; it does not depend
+    /// on the puzzles.
+    fn check(shape: Shape, variant: Variant, extras: &[Extra]) -> Result<(), String> {
+        if shape != Shape::STANDARD {
+            if !variant.any_shape() {
+                return Err(format!("{:?} puzzles must be 9x9", variant));
+            }
+            if let Some(extra) = extras.iter().find(|extra| !extra.any_shape()) {
+                return Err(format!("the {:?} rule only applies to 9x9 grids", extra));
+            }
+        }
+        if variant.multi_grid() && !extras.is_empty() {
+            return Err("extra rules are not supported for multi-grid puzzles".to_string());
+        }
+        return Ok(());
+    }
+
+    /// Whether the rules depend on data supplied with each puzzle.
+    fn per_puzzle(variant: Variant) -> bool {
+        match variant {
+            Variant::Jigsaw => true,
+            Variant::Standard | Variant::Hyper | Variant::Latin => false,
+            #[cfg(feature = "variants")]
+            Variant::Killer | Variant::GreaterThan => true,
+            #[cfg(feature = "variants")]
+            Variant::MultiGrid(_) => false,
+        }
+    }
+}
+
+
+/// The limit a search went past.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Exceeded {
+    Time,
+    Memory,
+}
+
+impl Exceeded {
+    /// Return why the search gave up, e.g., "timed out".
+    fn reason(self) -> &'static str {
+        match self {
+            Exceeded::Time => "timed out",
+            Exceeded::Memory => "out of memory",
+        }
+    }
+
+    /// Return the line written for a puzzle whose search gave up,
+    /// e.g., "Timed out".
+    fn status(self) -> &'static str {
+        match self {
+            Exceeded::Time => "Timed out",
+            Exceeded::Memory => "Out of memory",
+        }
+    }
+}
+
+/// The limits of a search, past which it gives up rather than find
+/// the solution.
+struct Limits {
+    /// When to give up, if ever.
+    deadline: Option<Instant>,
+    /// The bytes the search may hold, if bounded: its boards, one
+    /// per guess deep, and the steps it records, not the rest.
+    memory: Option<usize>,
+    /// The bytes the search holds.
+    used: Cell<usize>,
+    /// The limit the search went past, if any.
+    exceeded: Cell<Option<Exceeded>>,
+    /// The times the limits were checked.
+    checks: Cell<usize>,
+}
+
+/// Memory held by a search, given back when dropped.
+struct Held<'a> {
+    limits: &'a Limits,
+    bytes: usize,
+}
+
+impl Drop for Held<'_> {
+    fn drop(&mut self) {
+        self.limits.used.set(self.limits.used.get() - self.bytes);
+    }
+}
+
+impl Limits {
+    /// Return the limits of a search that gives up after `timeout`
+    /// or past `memory` bytes, if any.
+    fn new(timeout: Option<Duration>, memory: Option<usize>) -> Self {
+        return Limits { deadline: timeout.map(|timeout| Instant::now() + timeout), memory, used: Cell::new(0),
+                        exceeded: Cell::new(None), checks: Cell::new(0) };
+    }
+
+    /// Return the limits of a search that never gives up.
+    fn none() -> Self {
+        return Limits::new(None, None);
+    }
+
+    /// Whether the search must give up.  The clock is read every so
+    /// many checks, as reading it costs more than a guess.
+    fn exceeded(&self) -> bool {
+        if self.exceeded.get().is_some() {
+            return true;
+        }
+        let checks = self.checks.get();
+        self.checks.set(checks + 1);
+        if checks.is_multiple_of(64) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.exceeded.set(Some(Exceeded::Time));
+            return true;
+        }
+        return false;
+    }
+
+    /// Count `bytes` more held by the search for good; return false,
+    /// holding nothing more, if that is past the budget.
+    fn charge(&self, bytes: usize) -> bool {
+        let used = self.used.get() + bytes;
+        if self.memory.is_some_and(|memory| used > memory) {
+            self.exceeded.set(Some(Exceeded::Memory));
+            return false;
+        }
+        self.used.set(used);
+        return true;
+    }
+
+    /// Hold `bytes` more until the value returned is dropped, if
+    /// that is within the budget.
+    fn hold(&self, bytes: usize) -> Option<Held<'_>> {
+        if !self.charge(bytes) {
+            return None;
+        }
+        return Some(Held { limits: self, bytes });
+    }
+}
 
-/// A sudoku board is represented by a vector of u32's.
+/// A sudoku board is represented by an array of u32's.
+#[derive(Clone)]
 struct SudokuBoard<'a> {
-    cells: Vec<CandidateSet>,
-    neighbors: &'a Vec<Vec<usize>>,
+    cells: Cells,
+    rules: &'a Rules,
 }
 
 
 impl <'a> SudokuBoard<'a> {
     /// Create a new sudoku board from a string.
-    /// A non-zero digit stands for itself,
+    /// A non-zero digit stands for itself, the letters
+    /// A, B, C, ... stand for 10, 11, 12, ... on larger grids,
     /// a dot stands for a blank cell,
     /// anything else is an error.
-    fn from_str(digits: &str, neighbors: &'a Vec<Vec<usize>>) -> Self {
-        if digits.len() != NSQ {
-            error(format!("invalid puzzle length; expected {}, got {}",
-                          NSQ, digits.len()));
+    fn from_str(digits: &str, rules: &'a Rules) -> Self {
+        return SudokuBoard::parse(digits, rules).unwrap_or_else(|msg| error(msg));
+    }
+
+    /// Create a new sudoku board from a string, as `from_str` does;
+    /// return why it cannot be read otherwise.
+    fn parse(digits: &str, rules: &'a Rules) -> Result<Self, String> {
+        let ncells = rules.neighbors.len();
+        if digits.len() != ncells {
+            return Err(format!("invalid puzzle length; expected {}, got {}", ncells, digits.len()));
         }
-        let mut cells = Vec::with_capacity(NSQ);
-        for d in digits.chars() {
+        let side = rules.shape.side() as u32;
+        let mut cells = Cells::filled(rules.shape.full_set(), ncells);
+        for (i, d) in digits.chars().enumerate() {
             match d {
-                '.' => {
-                    cells.push(FULL_SET);
-                }
-                '1' ... '9' => {
-                    let n = d.to_digit(10).unwrap() as usize;
-                    cells.push(1 << (n - 1));
+                '.' => {}
+                _ => match d.to_digit(36) {
+                    Some(n) if (1 ..= side).contains(&n) => { cells[i] = 1 << (n - 1); }
+                    _ => { return Err(format!("invalid digit ({:?}) in string", d)); }
                 }
-                _ => { error(format!("invalid digit ({:?}) in string", d)); }
             }
         }
 
-        return SudokuBoard { cells, neighbors };
+        return Ok(SudokuBoard { cells, rules });
+    }
+
+    /// Return a random solved board under `rules`, or None if they
+    /// admit no solution.  The empty board is solved with the digits
+    /// of every cell tried in a random order, so any grid can come out,
+    /// though not all equally often.
+    #[cfg(feature = "generate")]
+    fn random_solution(rules: &'a Rules, rng: &mut Rng) -> Option<Self> {
+        let empty = SudokuBoard { cells: Cells::filled(rules.shape.full_set(), rules.neighbors.len()), rules };
+        return empty.random_solve(rng);
     }
 
     /// A cell is solved if its set of candidates is a singleton.
@@ -110,36 +896,20 @@ impl <'a> SudokuBoard<'a> {
         self.cells.iter().all(|c| *c != 0)
     }
 
-    /// The non-candidates of a cell are the solved values in
-    /// the cell's neighbors.
-    fn non_candidates(&self, cell: usize) -> u32 {
-        let mut set: u32 = EMPTY_SET;
-        for &n in self.neighbors[cell].iter() {
-            set |= self.cells[n] * (self.cell_solved(n) as u32);
-        }
-        return set;
-    }
-
-    /// Remove non-candidates from the cells of the board
-    /// until a fixed point is reached, i.e., no more non-
-    /// candidates can be removed anymore.
-    fn propagate(&self) -> Self {
-        let mut output = SudokuBoard {
-            cells: self.cells.clone(),
-            neighbors: self.neighbors
-        };
+    /// Remove from the cells of the board the candidates that
+    /// cannot satisfy a constraint, until a fixed point is reached,
+    /// i.e., no more candidates can be removed anymore.  The board
+    /// is changed in place, so propagating allocates nothing.
+    fn propagate(&mut self) {
         loop {
             let mut candidates_changed = false;
-            for i in 0 .. NSQ {
-                let q = output.cells[i] & !output.non_candidates(i);
-                candidates_changed = candidates_changed || (q != output.cells[i]);
-                output.cells[i] = q;
+            for constraint in self.rules.constraints.iter() {
+                candidates_changed = constraint.prune(&mut self.cells) || candidates_changed;
             }
             if !candidates_changed {
                 break;
             }
         }
-        return output;
     }
 
     /// Find the index of the unsolved cell with the
@@ -147,10 +917,11 @@ impl <'a> SudokuBoard<'a> {
     /// up the solving process by making the search tree
     /// narrower.
     fn most_promising(&self) -> Option<usize> {
-        let mut min_len = N;
-        let mut min_index = NSQ;
+        let ncells = self.cells.len();
+        let mut min_len = self.rules.shape.side() + 1;
+        let mut min_index = ncells;
 
-        for i in 0 .. NSQ {
+        for i in 0 .. ncells {
             if self.cell_solved(i) {
                 continue;
             }
@@ -158,10 +929,14 @@ impl <'a> SudokuBoard<'a> {
             if len < min_len {
                 min_index = i;
                 min_len = len;
+                // No unsolved cell has fewer than two candidates.
+                if len == 2 {
+                    break;
+                }
             }
         }
 
-        if min_index == NSQ {
+        if min_index == ncells {
             None
         } else {
             Some(min_index)
@@ -169,42 +944,134 @@ impl <'a> SudokuBoard<'a> {
     }
 
     /// Solve the Sudoku board:
-    /// 1. Propagate the set constraints
-    /// 2a. If the board is solved, terminate.
-    /// 2b. If the board is unsolvable, backtrack.
-    /// 3. Pick the most promising cell and brute-force it.
+    ///  1. Propagate the set constraints
+    ///  2. If the board is solved, terminate;
+    ///     if the board is unsolvable, backtrack.
+    ///  3. Pick the most promising cell and brute-force it.
     fn solve(&self) -> Option<Self> {
-        let mut newboard = self.propagate();
+        self.search(None, &mut 0, &Limits::none())
+    }
+
+
+    /// Solve the board like `solve`; also return the number of
+    /// guesses made, the candidates tried in the cells brute-forced.
+    #[cfg(feature = "serve")]
+    fn solve_counting(&self) -> (Option<Self>, usize) {
+        let mut guesses = 0;
+        let solution = self.search(None, &mut guesses, &Limits::none());
+        return (solution, guesses);
+    }
+
+    /// Solve the board like `solve`, but try the candidates of the
+    /// cells in a random order.
+    #[cfg(feature = "generate")]
+    fn random_solve(&self, rng: &mut Rng) -> Option<Self> {
+        self.search(Some(rng), &mut 0, &Limits::none())
+    }
+
+    fn search(&self, mut rng: Option<&mut Rng>, guesses: &mut usize, limits: &Limits) -> Option<Self> {
+        if limits.exceeded() { return None; }
+
+        let mut newboard = self.clone();
+        let _held = limits.hold(newboard.footprint())?;
+        newboard.propagate();
 
         if newboard.solved() { return Some(newboard); }
 
         if !newboard.solvable() { return None; }
 
+        if let Some(cell) = newboard.most_promising() {
+            let cell_candidates = newboard.cells[cell];
+            let mut digits: Vec<usize> = (0 .. self.rules.shape.side())
+                .filter(|c| cell_candidates & (1 << c) != 0)
+                .collect();
+            if let Some(ref mut rng) = rng {
+                rng.shuffle(&mut digits);
+            }
+
+            for c in digits {
+                newboard.cells[cell] = 1 << c;
+                *guesses += 1;
+                if let Some(solved_board) = newboard.search(rng.as_deref_mut(), guesses, limits) {
+                    return Some(solved_board);
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// Return the bytes the board takes.
+    fn footprint(&self) -> usize {
+        return mem::size_of::<Self>() + self.cells.heap_size();
+    }
+
+    /// Solve the board like `solve`; also return the steps taken.
+    fn solve_traced(&self) -> (Option<Self>, Vec<trace::SolveStep>) {
+        trace::solve(self, &Limits::none())
+    }
+
+    /// Return the next step of a human solver from the position of
+    /// the board, if any; see `logic::hint`.
+    fn hint(&self) -> Option<logic::Hint> {
+        logic::hint(self)
+    }
+
+    /// Return the digits that can still go in the cell at `row` and
+    /// `col`, counting from 0; see `possible::possible_digits`.
+    fn possible_digits(&self, row: usize, col: usize, reach: possible::Reach) -> Vec<usize> {
+        possible::possible_digits(self, row, col, reach)
+    }
+
+    /// Return the minimal-lexicographic canonical form of the board
+    /// under the symmetries of plain sudoku: the smallest of the digit
+    /// strings of the boards isomorphic to it, blanks coming first.
+    fn canonical_form(&self) -> String {
+        canonical::canonical(&self.to_str(), self.rules.shape, Isomorphisms::Sudoku)
+    }
+
+    /// Return the fingerprint of the board: a 64-bit hash of its
+    /// canonical form, the same for isomorphic boards.
+    fn fingerprint(&self) -> u64 {
+        canonical::fingerprint(&self.canonical_form())
+    }
+
+    /// Count the solutions of the board, stopping at `limit`;
+    /// a limit of 2 tells whether the solution is unique.
+    fn count_solutions(&self, limit: usize) -> usize {
+        let mut newboard = self.clone();
+        newboard.propagate();
+
+        if newboard.solved() { return 1; }
+
+        if !newboard.solvable() { return 0; }
+
+        let mut count = 0;
         if let Some(cell) = newboard.most_promising() {
             let cell_candidates = newboard.cells[cell];
 
-            for c in 0 .. N {
+            for c in 0 .. self.rules.shape.side() {
                 if cell_candidates & (1 << c) == 0 {
                     continue;
                 }
 
                 newboard.cells[cell] = 1 << c;
-                match newboard.solve() {
-                    Some(solved_board) => { return Some(solved_board); }
-                    None => { }
+                count += newboard.count_solutions(limit - count);
+                if count >= limit {
+                    break;
                 }
             }
         }
 
-        return None;
+        return count;
     }
 
     /// Convert the board to a linear textual representation.
     fn to_str(&self) -> String {
-        let mut output = String::with_capacity(NSQ);
-        for i in 0 .. NSQ {
+        let mut output = String::with_capacity(self.cells.len());
+        for i in 0 .. self.cells.len() {
             if self.cell_solved(i) {
-                output.push_str(&format!("{}", set_to_num(self.cells[i])));
+                output.push(num_to_char(set_to_num(self.cells[i])));
             } else {
                 output.push('.');
             }
@@ -224,56 +1091,1741 @@ fn set_to_num(mut s: CandidateSet) -> u32 {
 }
 
 
-fn main() {
-    let stdin = io::stdin();
-    let mut buf = String::with_capacity(NSQ);
+/// Return the character of the digit `n`: 1 to 9, then A, B, C, ...
+fn num_to_char(n: u32) -> char {
+    std::char::from_digit(n, 36).unwrap().to_ascii_uppercase()
+}
 
-    // Neighbor indices never change, so we compute them once,
-    // and store them in the struct.
-    let mut neighbors: Vec<Vec<usize>> = Vec::with_capacity(NSQ);
-    for i in 0 .. NSQ {
-        neighbors.push(neighbors_of(i));
-    }
 
-    loop {
-        buf.clear();
-        match stdin.read_line(&mut buf) {
-            Err(e) => { error(format!("I/O error, {:?}", e)); }
-            Ok(0) => { return; }
-            Ok(_) => { /* pass through */ }
-        }
-        let sb = SudokuBoard::from_str(&buf.trim(), &neighbors);
-        match sb.solve() {
-            Some(solution) => { println!("{}", solution.to_str()); }
-            None => { println!("No solution"); }
-        }
+/// Append the next line of `stdin` to `buf`; return false at the end of the input.
+fn read_line<R: BufRead>(input: &mut R, buf: &mut String) -> bool {
+    match input.read_line(buf) {
+        Err(e) => { error(format!("I/O error, {:?}", e)); }
+        Ok(0) => false,
+        Ok(_) => true,
     }
 }
 
-#[test]
-fn test_row_col() {
-    assert_eq!(row(11), 1);
-    assert_eq!(col(11), 2);
+
+/// The arguments of a command, after its name.
+type Args = iter::Peekable<iter::Skip<env::Args>>;
+
+/// The options that say what the puzzles read are: their variant,
+/// size, symbols, regions and extra rules.  Every command takes them.
+struct Options {
+    size: Option<String>,
+    symbols: Option<String>,
+    rule_set: Option<json::Json>,
+    region_map: Option<String>,
+    variant: Variant,
+    variant_name: String,
+    extras: Vec<Extra>,
 }
 
-#[test]
-fn test_group() {
-    assert_eq!(group(0), 0);
-    assert_eq!(group(1), 0);
-    assert_eq!(group(2), 0);
-    assert_eq!(group(9), 0);
-    assert_eq!(group(10), 0);
-    assert_eq!(group(11), 0);
-    assert_eq!(group(18), 0);
-    assert_eq!(group(19), 0);
-    assert_eq!(group(20), 0);
-    assert_eq!(group(60), 60);
-    assert_eq!(group(61), 60);
-    assert_eq!(group(62), 60);
-    assert_eq!(group(69), 60);
-    assert_eq!(group(70), 60);
-    assert_eq!(group(71), 60);
-    assert_eq!(group(78), 60);
-    assert_eq!(group(79), 60);
-    assert_eq!(group(80), 60);
+impl Options {
+    fn new() -> Self {
+        Options { size: None, symbols: None, rule_set: None, region_map: None,
+                  variant: Variant::Standard, variant_name: "standard".to_string(), extras: Vec::new() }
+    }
+
+    /// Read the puzzle option `arg`, taking its value from `args`.
+    /// Return false if `arg` is not a puzzle option.
+    fn parse_arg<I: Iterator<Item = String>>(&mut self, arg: &str, args: &mut I) -> bool {
+        match arg {
+            "--variant" => {
+                let name = args.next().unwrap_or_else(|| error("--variant needs a value".to_string()));
+                #[cfg(not(feature = "variants"))]
+                if let "killer" | "samurai" | "greater-than" = name.as_str() {
+                    error(format!("no {} puzzles; build with --features variants", name));
+                }
+                self.variant = Variant::from_name(&name)
+                    .unwrap_or_else(|| error(format!("unknown variant {:?}", name)));
+                self.variant_name = name;
+            }
+            "--size" => {
+                self.size = Some(args.next().unwrap_or_else(|| error("--size needs a value".to_string())));
+            }
+            "--alphabet" => {
+                self.symbols = Some(args.next().unwrap_or_else(|| error("--alphabet needs a value".to_string())));
+            }
+            "--regions" => {
+                self.region_map = Some(args.next().unwrap_or_else(|| error("--regions needs a value".to_string())));
+            }
+            #[cfg(feature = "variants")]
+            "--layout" => {
+                let name = args.next().unwrap_or_else(|| error("--layout needs a value".to_string()));
+                self.variant = Variant::MultiGrid(Layout::from_name(&name)
+                    .unwrap_or_else(|| error(format!("unknown layout {:?}", name))));
+            }
+            #[cfg(feature = "variants")]
+            "--rules" => {
+                let path = args.next().unwrap_or_else(|| error("--rules needs a value".to_string()));
+                let rule_set = rules_file::read(&path);
+                self.extras.extend(rules_file::extras(&rule_set));
+                self.rule_set = Some(rule_set);
+            }
+            #[cfg(not(feature = "variants"))]
+            "--layout" | "--rules" => {
+                error(format!("no {} option; build with --features variants", arg));
+            }
+            "--extra" => {
+                let name = args.next().unwrap_or_else(|| error("--extra needs a value".to_string()));
+                self.extras.push(Extra::from_name(&name)
+                    .unwrap_or_else(|| error(format!("unknown extra rule {:?}", name))));
+            }
+            _ => { return false; }
+        }
+        return true;
+    }
+
+    /// Return what it takes to read the puzzles, once the options
+    /// are all read.
+    fn puzzles(self) -> Puzzles {
+        let Options { size, symbols, rule_set, region_map, mut variant, variant_name, extras } = self;
+        // Latin squares have no boxes, so they come in any order.
+        let shape = match size {
+            None => Shape::STANDARD,
+            Some(size) => {
+                let side = size.parse().ok();
+                let shape = if variant == Variant::Latin {
+                    side.and_then(Shape::latin)
+                } else {
+                    side.and_then(Shape::from_side)
+                };
+                shape.unwrap_or_else(|| error(format!("unsupported grid size {:?}", size)))
+            }
+        };
+
+        if rule_set.is_some() && (shape != Shape::STANDARD || variant.multi_grid()) {
+            error("rule files only apply to 9x9 grids".to_string());
+        }
+        // A region map given once makes every puzzle a jigsaw puzzle
+        // with the same regions.
+        if region_map.is_some() {
+            if variant != Variant::Standard {
+                error("--regions only applies to standard puzzles".to_string());
+            }
+            variant = Variant::Jigsaw;
+        }
+        let per_puzzle = Rules::per_puzzle(variant) && region_map.is_none();
+        Rules::check(shape, variant, &extras).unwrap_or_else(|msg| error(msg));
+
+        // Only the rows, columns and boxes of plain sudoku and Latin
+        // squares can be reordered without changing the rules.
+        let plain = variant == Variant::Standard || variant == Variant::Latin;
+        let isomorphisms = if plain && extras.is_empty() && rule_set.is_none() {
+            Isomorphisms::Sudoku
+        } else {
+            Isomorphisms::Digits
+        };
+        let alphabet = symbols.map(|symbols| Alphabet::new(&symbols, shape.side()));
+        let mut puzzles = Puzzles { shape, variant, variant_name, extras, rule_set, region_map, alphabet,
+                                    per_puzzle, shared_rules: None, isomorphisms };
+        // Neighbor indices never change, so we compute them once,
+        // and store them in the struct.  Jigsaw and killer puzzles
+        // bring their own regions and cages, so their rules are
+        // created for each puzzle.
+        if !per_puzzle {
+            puzzles.shared_rules = Some(puzzles.make_rules(&[]).unwrap_or_else(|msg| error(msg)));
+        }
+        return puzzles;
+    }
+}
+
+/// Return the puzzle options of a command that takes no others.
+fn only_options(mut args: Args) -> Options {
+    let mut options = Options::new();
+    while let Some(arg) = args.next() {
+        if !options.parse_arg(&arg, &mut args) {
+            error(format!("unknown argument {:?}", arg));
+        }
+    }
+    return options;
+}
+
+/// What it takes to read the puzzles described by `Options`.
+struct Puzzles {
+    shape: Shape,
+    variant: Variant,
+    variant_name: String,
+    extras: Vec<Extra>,
+    rule_set: Option<json::Json>,
+    region_map: Option<String>,
+    alphabet: Option<Alphabet>,
+    /// Whether every puzzle brings its own rules.
+    per_puzzle: bool,
+    /// The rules of every puzzle, unless they bring their own.
+    shared_rules: Option<Rules>,
+    /// The isomorphisms that keep the rules.
+    isomorphisms: Isomorphisms,
+}
+
+impl Puzzles {
+    /// The rules of a puzzle, given the fields that follow its digits,
+    /// or why they cannot be made.
+    fn make_rules(&self, fields: &[&str]) -> Result<Rules, String> {
+        let fields: Vec<&str> = self.region_map.iter().map(String::as_str)
+            .chain(fields.iter().cloned())
+            .collect();
+        #[cfg_attr(not(feature = "variants"), allow(unused_mut))]
+        let mut rules = Rules::for_puzzle(self.shape, self.variant, &self.extras, &fields)?;
+        #[cfg(feature = "variants")]
+        if let Some(ref rule_set) = self.rule_set {
+            rules_file::apply(rule_set, &mut rules);
+        }
+        return Ok(rules);
+    }
+
+    /// Write out a board in the symbols and layout of the puzzles.
+    fn format(&self, digits: &str) -> String {
+        let text = match self.alphabet {
+            Some(ref alphabet) => alphabet.to_symbols(digits),
+            None => digits.to_string(),
+        };
+        #[cfg(feature = "variants")]
+        if let Some(layout) = self.variant.layout() {
+            return format!("{}\n", layout.format(&text));
+        }
+        return text;
+    }
+
+    /// Return a reader of the puzzles on the standard input, or of
+    /// the grids of marks if `marks` is set.
+    fn reader(&self, marks: bool) -> Reader {
+        Reader {
+            input: BufReader::with_capacity(1 << 16, io::stdin()),
+            buf: String::with_capacity(NSQ),
+            marks: if marks { Some(self.shape.cells()) } else { None },
+            lines: self.variant.lines(),
+        }
+    }
+
+    /// Return the digits of a puzzle and, for puzzles whose rules
+    /// depend on the puzzle, their own rules and the fields that
+    /// follow their digits, or why its text cannot be read.
+    fn try_parse(&self, text: &str) -> Result<(String, Option<Rules>, Option<String>), String> {
+        let mut puzzle_fields = None;
+        let (digits, puzzle_rules) = match self.try_parse_variant(text)? {
+            Some(parsed) => parsed,
+            None => {
+                let fields: Vec<&str> = text.split_whitespace().collect();
+                let rules = if self.per_puzzle {
+                    puzzle_fields = Some(fields[1 ..].join(" "));
+                    Some(self.make_rules(&fields[1 ..])?)
+                } else {
+                    None
+                };
+                (fields[0].to_string(), rules)
+            }
+        };
+        let digits = match self.alphabet {
+            Some(ref alphabet) => alphabet.to_digits(&digits)?,
+            None => digits,
+        };
+        return Ok((digits, puzzle_rules, puzzle_fields));
+    }
+
+    /// Return the digits and rules of a JSON, multi-grid or
+    /// greater-than puzzle, None for the puzzles on a line of digits,
+    /// or why its text cannot be read.
+    #[cfg(feature = "variants")]
+    fn try_parse_variant(&self, text: &str) -> Result<Option<(String, Option<Rules>)>, String> {
+        if text.trim_start().starts_with('{') {
+            if self.variant.multi_grid() {
+                return Err("JSON puzzles are not supported for multi-grid puzzles".to_string());
+            }
+            if self.shape != Shape::STANDARD {
+                return Err("JSON puzzles must be 9x9".to_string());
+            }
+            let puzzle = json_puzzle::parse(text)?;
+            let mut rules = self.make_rules(&[])?;
+            rules.add_clues(puzzle.clues);
+            return Ok(Some((puzzle.grid, Some(rules))));
+        }
+        if let Some(layout) = self.variant.layout() {
+            return Ok(Some((layout.parse(&text.lines().collect::<Vec<_>>())?, None)));
+        }
+        if self.variant == Variant::GreaterThan {
+            let (digits, links) = inequality::parse(&text.lines().collect::<Vec<_>>())?;
+            let mut rules = self.make_rules(&[])?;
+            rules.extend(links);
+            return Ok(Some((digits, Some(rules))));
+        }
+        return Ok(None);
+    }
+
+    /// Refuse JSON puzzles, which take the variants; the others are
+    /// on a line of digits.
+    #[cfg(not(feature = "variants"))]
+    fn try_parse_variant(&self, text: &str) -> Result<Option<(String, Option<Rules>)>, String> {
+        if text.trim_start().starts_with('{') {
+            return Err("no JSON puzzles; build with --features variants".to_string());
+        }
+        return Ok(None);
+    }
+
+    /// Parse a puzzle as `try_parse` does, stopping the run if its
+    /// text cannot be read.
+    fn parse(&self, text: &str) -> (String, Option<Rules>, Option<String>) {
+        self.try_parse(text).unwrap_or_else(|msg| error(msg))
+    }
+}
+
+/// Reads the text of the puzzles from the standard input, in large
+/// blocks, each taking its lock once: a JSON object on one line, as
+/// many lines as a puzzle of the variant takes, or as many as the
+/// marks of a grid take.
+struct Reader {
+    input: BufReader<io::Stdin>,
+    buf: String,
+    /// Number of cells of the grids of marks, if marks are read.
+    marks: Option<usize>,
+    /// Number of lines of a puzzle.
+    lines: usize,
+}
+
+impl Iterator for Reader {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            self.buf.clear();
+            if !read_line(&mut self.input, &mut self.buf) {
+                return None;
+            }
+            // The borders of grids of marks hold no cells.
+            if if self.marks.is_some() { marks::count(&self.buf) > 0 } else { !self.buf.trim().is_empty() } {
+                break;
+            }
+        }
+        if let Some(cells) = self.marks {
+            // The marks of a grid may span several lines.
+            while marks::count(&self.buf) < cells && read_line(&mut self.input, &mut self.buf) {}
+        } else if !self.buf.trim_start().starts_with('{') {
+            // Multi-grid and greater-than puzzles span several lines.
+            while self.buf.lines().count() < self.lines && read_line(&mut self.input, &mut self.buf) {}
+        }
+        return Some(self.buf.clone());
+    }
+}
+
+
+fn main() {
+    let mut args = env::args().skip(1).peekable();
+    // The `generate` command writes puzzles instead of solving them,
+    // the `mutate` command derives new puzzles from those read, and
+    // the `bench` command times the solver, the `serve` command
+    // answers HTTP requests, the `render` command draws puzzles, the
+    // `export` command writes them for other programs, the `pack`
+    // and `unpack` commands write and read them in a binary form, the
+    // `hint` command gives the next step of a human solver, the `rate`
+    // command rates puzzles, the `stats` command describes them, the
+    // `conflicts` command finds the mistakes of a solve in progress,
+    // the `check` command checks solutions handed in and the
+    // `compare` command tells whether two puzzles are isomorphic, the
+    // `canonical` command writes their canonical forms, the
+    // `fingerprint` command writes hashes of them, the `analyze`
+    // command analyzes them for setters, the `sample` command draws
+    // solutions of puzzles with several at random and the `transform`
+    // command transposes, reorders and relabels puzzles, the
+    // `possible` command gives the digits that can go in a cell and
+    // the `play` command is a game in the terminal.  Each command
+    // reads its own options.
+    let run: fn(Args) = match args.peek().map(String::as_str) {
+        #[cfg(feature = "generate")]
+        Some("generate") => run_generate,
+        #[cfg(feature = "generate")]
+        Some("mutate") => run_mutate,
+        #[cfg(not(feature = "generate"))]
+        Some("generate") | Some("mutate") => { error("no generator; build with --features generate".to_string()); }
+        Some("bench") => run_bench,
+        #[cfg(feature = "serve")]
+        Some("serve") => run_serve,
+        #[cfg(not(feature = "serve"))]
+        Some("serve") => { error("no server; build with --features serve".to_string()); }
+        #[cfg(feature = "render")]
+        Some("render") => run_render,
+        #[cfg(feature = "render")]
+        Some("export") => run_export,
+        #[cfg(not(feature = "render"))]
+        Some("render") | Some("export") => { error("no rendering; build with --features render".to_string()); }
+        Some("pack") => run_pack,
+        Some("unpack") => run_unpack,
+        Some("hint") => run_hint,
+        Some("rate") => run_rate,
+        Some("stats") => run_stats,
+        Some("conflicts") => |args| run_check(args, false),
+        Some("check") => |args| run_check(args, true),
+        Some("compare") => run_compare,
+        Some("canonical") => |args| run_canonical(args, false),
+        Some("fingerprint") => |args| run_canonical(args, true),
+        Some("analyze") => run_analyze,
+        Some("sample") => run_sample,
+        Some("transform") => run_transform,
+        Some("possible") => run_possible,
+        #[cfg(feature = "play")]
+        Some("play") => run_play,
+        #[cfg(not(feature = "play"))]
+        Some("play") => { error("no game; build with --features play".to_string()); }
+        // Without a command, the puzzles read are solved.
+        _ => {
+            run_solve(args);
+            return;
+        }
+    };
+    args.next();
+    run(args);
+}
+
+/// Solve the puzzles read, one per line, or answer the JSON requests
+/// read with `--protocol jsonl`.
+fn run_solve(mut args: Args) {
+    let mut options = Options::new();
+    let mut threads = 1;
+    let mut flush_every = None;
+    let mut jsonl = false;
+    let mut tracing = false;
+    let mut logging = false;
+    let mut show_guessed = false;
+    let mut with_marks = false;
+    let mut timeout = None;
+    let mut max_memory = None;
+    let mut rejects = None;
+    let mut checkpointing = None;
+    let mut resumed = false;
+    let mut unordered = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--trace" => { tracing = true; }
+            "--log" => { logging = true; }
+            "--guessed" => { show_guessed = true; }
+            "--marks" => { with_marks = true; }
+            "--timeout" => {
+                let ms = args.next().unwrap_or_else(|| error("--timeout needs a value".to_string()));
+                timeout = Some(Duration::from_millis(ms.parse()
+                    .unwrap_or_else(|_| error(format!("invalid timeout {:?}", ms)))));
+            }
+            "--max-memory" => {
+                let size = args.next().unwrap_or_else(|| error("--max-memory needs a value".to_string()));
+                max_memory = Some(parse_size(&size).unwrap_or_else(|| error(format!("invalid size {:?}", size))));
+            }
+            "--checkpoint" => {
+                checkpointing = Some(args.next().unwrap_or_else(|| error("--checkpoint needs a value".to_string())));
+            }
+            // A job resumed goes on keeping its checkpoint.
+            "--resume" => {
+                checkpointing = Some(args.next().unwrap_or_else(|| error("--resume needs a value".to_string())));
+                resumed = true;
+            }
+            "--unordered" => { unordered = true; }
+            "--rejects" => {
+                rejects = Some(args.next().unwrap_or_else(|| error("--rejects needs a value".to_string())));
+            }
+            "--protocol" => {
+                let name = args.next().unwrap_or_else(|| error("--protocol needs a value".to_string()));
+                if name != "jsonl" {
+                    error(format!("unknown protocol {:?}", name));
+                }
+                jsonl = true;
+            }
+            "--flush-every" => {
+                let lines = args.next().unwrap_or_else(|| error("--flush-every needs a value".to_string()));
+                flush_every = Some(lines.parse()
+                    .unwrap_or_else(|_| error(format!("invalid number of lines {:?}", lines))));
+            }
+            "--threads" => { threads = threads_value(&arg, &mut args); }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+
+    let variant = options.variant;
+    if (tracing || show_guessed) && variant.multi_grid() {
+        error("--trace and --guessed only apply to single grids".to_string());
+    }
+    if with_marks && (variant.multi_grid() || Rules::per_puzzle(variant)) {
+        error("--marks only applies to single grids with the same rules".to_string());
+    }
+    if with_marks && (tracing || show_guessed || logging) {
+        error("--marks cannot be traced".to_string());
+    }
+    if (timeout.is_some() || max_memory.is_some() || rejects.is_some()) && (tracing || show_guessed || with_marks) {
+        error("--timeout, --max-memory and --rejects do not apply to --trace, --guessed or --marks".to_string());
+    }
+    // The puzzles done are those before the first line not written.
+    if unordered && checkpointing.is_some() {
+        error("--unordered runs cannot be checkpointed".to_string());
+    }
+    if jsonl && !cfg!(feature = "serve") {
+        error("no JSON-Lines protocol; build with --features serve".to_string());
+    }
+    let puzzles = options.puzzles();
+
+    // Every line is a JSON request answered on a line of its own, at
+    // once, since the process is driven by another one.
+    #[cfg(feature = "serve")]
+    if jsonl {
+        let rules = puzzles.shared_rules.as_ref()
+            .unwrap_or_else(|| error(format!("cannot answer requests on {:?} puzzles", puzzles.variant)));
+        output::start(1);
+        server::answer_lines(&mut BufReader::with_capacity(1 << 16, io::stdin()), rules, output::write_line)
+            .unwrap_or_else(|e| error(format!("I/O error, {:?}", e)));
+        return;
+    }
+
+    // A puzzle that cannot be read stops the run, unless the puzzles
+    // rejected are kept apart.
+    let rejected = |msg: String| -> String {
+        if rejects.is_none() {
+            error(msg);
+        }
+        return msg;
+    };
+    // Return the cells of a puzzle found by guessing, and the
+    // depth of the guesses.
+    let guesses = |text: &str| -> String {
+        let (digits, puzzle_rules, _) = puzzles.parse(text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        let (open, depth) = logic::guessed(&SudokuBoard::from_str(&digits, rules));
+        let names: Vec<String> = open.iter()
+            .map(|&cell| format!("r{}c{}", rules.shape.row(cell) + 1, rules.shape.col(cell) + 1))
+            .collect();
+        return format!("guessed {}, depth {}", if names.is_empty() { "none".to_string() } else { names.join(" ") }, depth);
+    };
+    // Return the line written for the puzzle `id` of the input,
+    // counting from 1, logging its span if need be, or why it was
+    // rejected if it cannot be read or its search gives up.
+    let solve_limited = |id: usize, text: &str| -> (Outcome, Result<String, String>) {
+        let (digits, puzzle_rules, _) = match puzzles.try_parse(text) {
+            Ok(parsed) => parsed,
+            Err(msg) => { return (Outcome::Rejected, Err(rejected(msg))); }
+        };
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        let board = match SudokuBoard::parse(&digits, rules) {
+            Ok(board) => board,
+            Err(msg) => { return (Outcome::Rejected, Err(rejected(msg))); }
+        };
+        let limits = Limits::new(timeout, max_memory);
+        let solution = if logging { log::solve(id, &board, &limits) } else { board.search(None, &mut 0, &limits) };
+        if let Some(exceeded) = limits.exceeded.get() {
+            // Without a file of rejects, the searches given up are
+            // told apart from the puzzles without a solution.
+            let line = if rejects.is_none() { Ok(exceeded.status().to_string()) } else { Err(exceeded.reason().to_string()) };
+            return (Outcome::GivenUp, line);
+        }
+        return match solution {
+            Some(solution) if show_guessed => (Outcome::Solved, Ok(format!("{} {}", puzzles.format(&solution.to_str()), guesses(text)))),
+            Some(solution) => (Outcome::Solved, Ok(puzzles.format(&solution.to_str()))),
+            None => (Outcome::Unsolvable, Ok("No solution".to_string())),
+        };
+    };
+    // Return the solution of the puzzle `id` and the steps taken, in
+    // JSON, logging its span if need be.
+    let solve_traced = |id: usize, text: &str| -> (Outcome, String) {
+        let (digits, puzzle_rules, _) = puzzles.parse(text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        let board = SudokuBoard::from_str(&digits, rules);
+        let start = std::time::Instant::now();
+        let (solution, steps) = board.solve_traced();
+        if logging {
+            let outcome = if solution.is_some() { "solved" } else { "unsolvable" };
+            eprint!("{}", log::span(id, &board, outcome, &steps, start.elapsed()));
+        }
+        let outcome = if solution.is_some() { Outcome::Solved } else { Outcome::Unsolvable };
+        return (outcome, json::Json::object(vec![
+            ("solution", solution.map_or(json::Json::Null, |solution| json::Json::String(solution.to_str()))),
+            ("steps", trace::to_json(&steps, rules.shape)),
+        ]).to_string());
+    };
+    // Return the line written for a position given by its marks.
+    let solve_marks = |text: &str| -> (Outcome, String) {
+        let board = marks::board(text, puzzles.shared_rules.as_ref().unwrap()).unwrap_or_else(|msg| error(msg));
+        return match marks::solve(&board) {
+            marks::Outcome::Solved(solution) => (Outcome::Solved, puzzles.format(&solution)),
+            marks::Outcome::Excluded => (Outcome::Unsolvable, "No solution; the marks exclude the solution".to_string()),
+            marks::Outcome::Unsolvable => (Outcome::Unsolvable, "No solution".to_string()),
+        };
+    };
+    // Return what came of the puzzle `id` and the line written for it,
+    // or the record of its rejection: the reason, then its text.
+    let solve_line = |id: usize, text: &str| -> (Outcome, Result<String, String>) {
+        if with_marks {
+            let (outcome, line) = solve_marks(text);
+            return (outcome, Ok(line));
+        }
+        if tracing {
+            let (outcome, line) = solve_traced(id, text);
+            return (outcome, Ok(line));
+        }
+        let (outcome, line) = solve_limited(id, text);
+        return (outcome, line.map_err(|reason| format!("# {}: {}\n{}", id, reason, text.trim_end())));
+    };
+
+    // Solutions typed in one at a time are written at once.
+    output::start(flush_every.unwrap_or(if io::stdin().is_terminal() { 1 } else { 0 }));
+    // A job resumed skips the puzzles done, whose lines and rejects
+    // the job it resumes wrote.
+    let mut progress = match checkpointing {
+        Some(ref path) if resumed => checkpoint::read(path).unwrap_or_else(|msg| error(msg)),
+        _ => Checkpoint::default(),
+    };
+    let mut reader = puzzles.reader(with_marks);
+    let skipped = progress.offset as usize;
+    for _ in 0 .. skipped {
+        if reader.next().is_none() {
+            break;
+        }
+    }
+    // The rejects written after the checkpoint are written again.
+    let mut rejected_file = rejects.as_ref().map(|path| {
+        let file = fs::OpenOptions::new().create(true).append(true).open(path)
+            .and_then(|file| file.set_len(progress.rejects_bytes).map(|_| file));
+        BufWriter::new(file.unwrap_or_else(|e| error(format!("cannot write {}: {}", path, e))))
+    });
+    let output_bytes = progress.output_bytes;
+    // Write out the lines and rejects so far, then the checkpoint
+    // that counts them, if any.
+    let keep = |progress: &mut Checkpoint, rejected_file: &mut Option<BufWriter<fs::File>>| {
+        output::flush();
+        progress.output_bytes = output_bytes + output::written();
+        if let Some(ref mut file) = *rejected_file {
+            file.flush().unwrap_or_else(|e| error(format!("cannot write {}: {}", rejects.as_ref().unwrap(), e)));
+        }
+        if let Some(ref path) = checkpointing {
+            checkpoint::write(path, progress).unwrap_or_else(|msg| error(msg));
+        }
+    };
+    let mut kept = Instant::now();
+    let mut write = |(outcome, line): (Outcome, Result<String, String>)| {
+        match line {
+            Ok(line) => output::write_line(&line),
+            Err(record) => {
+                writeln!(rejected_file.as_mut().unwrap(), "{}", record)
+                    .unwrap_or_else(|e| error(format!("cannot write {}: {}", rejects.as_ref().unwrap(), e)));
+                progress.rejects_bytes += record.len() as u64 + 1;
+            }
+        }
+        progress.count(outcome);
+        if checkpointing.is_some() && kept.elapsed() >= checkpoint::INTERVAL {
+            keep(&mut progress, &mut rejected_file);
+            kept = Instant::now();
+        }
+    };
+    let solve_next = |id: usize, text: &str| solve_line(skipped + id, text);
+    if threads > 1 {
+        pipeline::run(threads, !unordered, || reader.next(), &solve_next, &mut write);
+    } else {
+        for (id, text) in reader.enumerate() {
+            write(solve_next(id + 1, &text));
+        }
+    }
+    keep(&mut progress, &mut rejected_file);
+}
+
+/// Write the puzzles asked for by the generator's options.
+#[cfg(feature = "generate")]
+fn run_generate(mut args: Args) {
+    let mut options = Options::new();
+    let mut settings = generate::Settings::new();
+    while let Some(arg) = args.next() {
+        if !options.parse_arg(&arg, &mut args) && !settings.parse_arg(&arg, &mut args) {
+            error(format!("unknown argument {:?}", arg));
+        }
+    }
+    let puzzles = options.puzzles();
+    if settings.symmetry != Symmetry::None && puzzles.variant.multi_grid() {
+        error("symmetric clues only apply to single grids".to_string());
+    }
+    let format = |digits: &str| puzzles.format(digits);
+    match puzzles.shared_rules {
+        Some(ref rules) => generate::run(&settings, &generate::Source::Shared(rules), puzzles.isomorphisms, &format),
+        None if puzzles.variant == Variant::Jigsaw => {
+            let make_jigsaw_rules = |regions: &str| puzzles.make_rules(&[regions]).unwrap_or_else(|msg| error(msg));
+            let source = generate::Source::Jigsaw(puzzles.shape, &make_jigsaw_rules);
+            generate::run(&settings, &source, puzzles.isomorphisms, &format);
+        }
+        #[cfg(feature = "variants")]
+        None if puzzles.variant == Variant::Killer => {
+            let make_killer_rules = |cages: &str| {
+                puzzles.make_rules(&cages.split_whitespace().collect::<Vec<_>>()).unwrap_or_else(|msg| error(msg))
+            };
+            generate::run(&settings, &generate::Source::Killer(&make_killer_rules), puzzles.isomorphisms, &format);
+        }
+        None => { error(format!("cannot generate {:?} puzzles", puzzles.variant)); }
+    }
+}
+
+/// Write mutants of the puzzles read, with the generator's options.
+#[cfg(feature = "generate")]
+fn run_mutate(mut args: Args) {
+    let mut options = Options::new();
+    let mut settings = generate::Settings::new();
+    while let Some(arg) = args.next() {
+        if !options.parse_arg(&arg, &mut args) && !settings.parse_arg(&arg, &mut args) {
+            error(format!("unknown argument {:?}", arg));
+        }
+    }
+    let puzzles = options.puzzles();
+    #[cfg(feature = "variants")]
+    if puzzles.variant == Variant::GreaterThan {
+        error("cannot mutate greater-than puzzles".to_string());
+    }
+    let mut rng = Rng::new(settings.seed.unwrap_or_else(Rng::clock_seed));
+    for text in puzzles.reader(false) {
+        if text.trim_start().starts_with('{') {
+            error("cannot mutate JSON puzzles".to_string());
+        }
+        let (digits, puzzle_rules, puzzle_fields) = puzzles.parse(&text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        mutate::run(&SudokuBoard::from_str(&digits, rules), &settings, &mut rng, puzzle_fields,
+                    &|digits: &str| puzzles.format(digits));
+    }
+}
+
+/// Time the solver on the puzzles read, or on a built-in corpus.
+fn run_bench(mut args: Args) {
+    let mut options = Options::new();
+    let mut corpus = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--corpus" => {
+                let name = args.next().unwrap_or_else(|| error("--corpus needs a value".to_string()));
+                if bench::corpus_names().is_empty() {
+                    error("no built-in corpora; build with --features corpora".to_string());
+                }
+                corpus = Some(bench::corpus(&name).unwrap_or_else(|| {
+                    error(format!("unknown corpus {:?}; built-in corpora: {}", name, bench::corpus_names().join(", ")))
+                }));
+            }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    // Return the solution of a puzzle, if any.
+    let solve = |text: &str| -> Option<String> {
+        let (digits, puzzle_rules, _) = puzzles.parse(text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        return SudokuBoard::from_str(&digits, rules).solve().map(|solution| puzzles.format(&solution.to_str()));
+    };
+    // The puzzles are read before the clock starts.
+    let texts: Vec<String> = match corpus {
+        Some(texts) => texts.lines().filter(|line| !line.trim().is_empty()).map(str::to_string).collect(),
+        None => puzzles.reader(false).collect(),
+    };
+    let (elapsed, unsolved) = bench::run(&texts, &solve);
+    println!("{}", bench::report(texts.len(), elapsed, unsolved));
+}
+
+/// Answer HTTP requests on the address given with `--http`.
+#[cfg(feature = "serve")]
+fn run_serve(mut args: Args) {
+    let mut options = Options::new();
+    let mut threads = 1;
+    let mut address = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--http" => {
+                address = Some(args.next().unwrap_or_else(|| error("--http needs a value".to_string())));
+            }
+            "--threads" => { threads = threads_value(&arg, &mut args); }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    let address = address.unwrap_or_else(|| error("serve needs --http".to_string()));
+    match puzzles.shared_rules {
+        Some(ref rules) => server::run(&address, rules, threads),
+        None => { error(format!("cannot serve {:?} puzzles", puzzles.variant)); }
+    }
+}
+
+/// Draw the puzzles read as QR codes, or the URLs they fill in.
+#[cfg(feature = "render")]
+fn run_render(mut args: Args) {
+    let mut options = Options::new();
+    let mut url = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--format" => {
+                let name = args.next().unwrap_or_else(|| error("--format needs a value".to_string()));
+                if name != "qr" {
+                    error(format!("unknown format {:?}", name));
+                }
+            }
+            "--url" => {
+                url = Some(args.next().unwrap_or_else(|| error("--url needs a value".to_string())));
+            }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    for text in puzzles.reader(false) {
+        let text = text.trim();
+        let text = url.as_ref().map_or(text.to_string(), |url| url.replace("{}", text));
+        println!("{}\n", qr::render(&text).unwrap_or_else(|| "Too long for a QR code".to_string()));
+    }
+}
+
+/// Write the puzzles read for HoDoKu, at the position the techniques
+/// asked for, or singles, get stuck at.
+#[cfg(feature = "render")]
+fn run_export(mut args: Args) {
+    let mut options = Options::new();
+    let mut techniques = vec![logic::Technique::Singles];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--format" => {
+                let name = args.next().unwrap_or_else(|| error("--format needs a value".to_string()));
+                if name != "hodoku" {
+                    error(format!("unknown format {:?}", name));
+                }
+            }
+            "--techniques" | "--solvable-with" => { techniques = techniques_value(&arg, &mut args); }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    // HoDoKu only knows the standard rules.
+    if puzzles.variant != Variant::Standard || puzzles.shape != Shape::STANDARD
+        || !puzzles.extras.is_empty() || puzzles.rule_set.is_some() {
+        error("only standard puzzles can be exported to HoDoKu".to_string());
+    }
+    for text in puzzles.reader(false) {
+        let (digits, _, _) = puzzles.parse(&text);
+        let board = SudokuBoard::from_str(&digits, puzzles.shared_rules.as_ref().unwrap());
+        let position = logic::solve(&board, &techniques).0;
+        println!("{}\n{}\n", hodoku::library_line(&board, &position), hodoku::pm_grid(&position));
+    }
+}
+
+/// Stop the run unless the puzzles can be packed: packs hold the
+/// digits of puzzles with the same rules, given by their variant and
+/// size.
+fn check_packable(puzzles: &Puzzles) {
+    if puzzles.shared_rules.is_none() || puzzles.variant.multi_grid()
+        || !puzzles.extras.is_empty() || puzzles.rule_set.is_some() {
+        error(format!("cannot pack {:?} puzzles", puzzles.variant));
+    }
+}
+
+/// Write the puzzles read as a pack, with their ratings if asked.
+fn run_pack(mut args: Args) {
+    let mut options = Options::new();
+    let mut with_ratings = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--ratings" => { with_ratings = true; }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    check_packable(&puzzles);
+    let rules = puzzles.shared_rules.as_ref().unwrap();
+    let digits: Vec<String> = puzzles.reader(false).map(|text| puzzles.parse(&text).0).collect();
+    if let Some(digits) = digits.iter().find(|digits| digits.chars().count() != rules.neighbors.len()) {
+        error(format!("invalid puzzle length {:?}", digits));
+    }
+    let ratings = if with_ratings {
+        Some(digits.iter().map(|digits| logic::rate(&SudokuBoard::from_str(digits, rules))).collect())
+    } else {
+        None
+    };
+    let pack = pack::Pack { variant: puzzles.variant_name, side: puzzles.shape.side(), puzzles: digits, ratings };
+    pack::write(&pack, &mut io::stdout().lock()).unwrap_or_else(|e| error(format!("I/O error, {:?}", e)));
+}
+
+/// Write the puzzles of the pack read, with their ratings if it has
+/// them.
+fn run_unpack(args: Args) {
+    let puzzles = only_options(args).puzzles();
+    check_packable(&puzzles);
+    let pack = pack::read(&mut BufReader::with_capacity(1 << 16, io::stdin())).unwrap_or_else(|msg| error(msg));
+    if Variant::from_name(&pack.variant) != Some(puzzles.variant) || pack.side != puzzles.shape.side() {
+        error(format!("the pack holds {} puzzles of size {}; unpack them with --variant {} --size {}",
+                      pack.variant, pack.side, pack.variant, pack.side));
+    }
+    output::start(0);
+    for (i, digits) in pack.puzzles.iter().enumerate() {
+        match pack.ratings {
+            Some(ref ratings) => output::write_line(&format!("{} {}", puzzles.format(digits), ratings[i].name())),
+            None => output::write_line(&puzzles.format(digits)),
+        }
+    }
+    output::flush();
+}
+
+/// Write the next step of a human solver on each puzzle read.
+fn run_hint(args: Args) {
+    let puzzles = only_options(args).puzzles();
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        let hint = SudokuBoard::from_str(&digits, rules).hint();
+        println!("{}", hint.map_or("No hint".to_string(), |hint| hint.text(puzzles.shape)));
+    }
+}
+
+/// Rate the puzzles read.  A histogram of the ratings leaves out
+/// those of each puzzle, unless they annotate the puzzles.
+fn run_rate(mut args: Args) {
+    let mut options = Options::new();
+    let mut se_scale = false;
+    let mut histogram = false;
+    let mut annotate = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--histogram" => { histogram = true; }
+            "--annotate" => { annotate = true; }
+            "--scale" => {
+                let name = args.next().unwrap_or_else(|| error("--scale needs a value".to_string()));
+                se_scale = match name.as_str() {
+                    "difficulty" => false,
+                    "se" => true,
+                    _ => { error(format!("unknown scale {:?}", name)); }
+                };
+            }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    // The scale of Sudoku Explainer is that of standard puzzles.
+    if se_scale && (options.variant != Variant::Standard || !options.extras.is_empty() || options.rule_set.is_some()) {
+        error("--scale se only applies to standard puzzles".to_string());
+    }
+    let puzzles = options.puzzles();
+    let mut grades = Vec::new();
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let board = SudokuBoard::from_str(&digits, puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap());
+        let (rank, name) = if se_scale {
+            // Beyond the techniques of the logic solver.
+            logic::se_rating(&board).map_or((f64::INFINITY, ">3.6".to_string()), |se| (se, format!("{:.1}", se)))
+        } else {
+            let difficulty = logic::rate(&board);
+            (difficulty as usize as f64, difficulty.name().to_string())
+        };
+        if annotate {
+            println!("{} {}", text.trim_end(), name);
+        } else if !histogram {
+            println!("{}", name);
+        }
+        grades.push((rank, name));
+    }
+    if histogram {
+        if annotate {
+            println!();
+        }
+        println!("{}", grade::report(&grades));
+    }
+}
+
+/// Describe the puzzles read.
+fn run_stats(args: Args) {
+    let options = only_options(args);
+    if options.variant.multi_grid() {
+        error("stats only apply to single grids".to_string());
+    }
+    let puzzles = options.puzzles();
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        println!("{}", stats::report(&SudokuBoard::from_str(&digits, rules)));
+    }
+}
+
+/// Find the mistakes of the solves in progress of the puzzles read,
+/// or check the solutions handed in if `verifying`.  The grids of the
+/// solves in progress, or of the solutions, are on the lines of a
+/// file, in the order of the puzzles, or the grids of marks of the
+/// solves in progress.
+fn run_check(mut args: Args, verifying: bool) {
+    let mut options = Options::new();
+    let mut against = None;
+    let mut with_marks = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--against" => {
+                let path = args.next().unwrap_or_else(|| error("--against needs a value".to_string()));
+                against = Some(fs::read_to_string(&path)
+                    .unwrap_or_else(|e| error(format!("cannot read {}: {}", path, e))));
+            }
+            "--marks" if verifying => { error("check takes solutions, not marks".to_string()); }
+            "--marks" => { with_marks = true; }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    if options.variant.multi_grid() {
+        error("conflicts and check only apply to single grids".to_string());
+    }
+    let puzzles = options.puzzles();
+    let against = against.unwrap_or_else(|| error("--against is needed".to_string()));
+    let mut grids = if with_marks {
+        marks::split(&against, puzzles.shape.cells())
+    } else {
+        against.lines().filter_map(|line| line.split_whitespace().next()).map(str::to_string).collect()
+    }.into_iter();
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        let grid = grids.next().unwrap_or_else(|| error("fewer grids than puzzles".to_string()));
+        let grid = if with_marks {
+            marks::board(&grid, rules).unwrap_or_else(|msg| error(msg))
+        } else {
+            let grid = match puzzles.alphabet {
+                Some(ref alphabet) => alphabet.to_digits(&grid).unwrap_or_else(|msg| error(msg)),
+                None => grid,
+            };
+            if grid.chars().count() != digits.chars().count() {
+                error(format!("invalid grid length {:?}", grid));
+            }
+            SudokuBoard::from_str(&grid, rules)
+        };
+        let puzzle = SudokuBoard::from_str(&digits, rules);
+        if verifying {
+            match check::verify(&puzzle, &grid) {
+                Ok(()) => println!("ok"),
+                Err(msg) => println!("fail: {}", msg),
+            }
+            continue;
+        }
+        let texts: Vec<String> = check::conflicts(&puzzle, &grid).iter()
+            .map(|conflict| conflict.text(puzzles.shape))
+            .collect();
+        println!("{}", if texts.is_empty() { "No conflicts".to_string() } else { texts.join(", ") });
+    }
+}
+
+/// Tell whether the puzzles of two files are isomorphic, comparing
+/// them line by line.
+fn run_compare(mut args: Args) {
+    let mut options = Options::new();
+    let mut compared = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            _ if !arg.starts_with("--") => { compared.push(arg); }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    if compared.len() != 2 || options.variant.multi_grid() || Rules::per_puzzle(options.variant) {
+        error("compare takes two files of puzzles on single grids with the same rules".to_string());
+    }
+    let puzzles = options.puzzles();
+    let digits: Vec<Vec<String>> = compared.iter().map(|path| {
+        let text = fs::read_to_string(path).unwrap_or_else(|e| error(format!("cannot read {}: {}", path, e)));
+        return text.lines().filter_map(|line| line.split_whitespace().next()).map(|digits| {
+            let digits = match puzzles.alphabet {
+                Some(ref alphabet) => alphabet.to_digits(digits).unwrap_or_else(|msg| error(msg)),
+                None => digits.to_string(),
+            };
+            if digits.chars().count() != puzzles.shape.cells() {
+                error(format!("invalid puzzle length {:?}", digits));
+            }
+            return digits;
+        }).collect();
+    }).collect();
+    if digits[0].len() != digits[1].len() {
+        error(format!("{} and {} hold different numbers of puzzles", compared[0], compared[1]));
+    }
+    for (a, b) in digits[0].iter().zip(digits[1].iter()) {
+        match canonical::isomorphism(a, b, puzzles.shape, puzzles.isomorphisms) {
+            Some(transform) => println!("isomorphic: {}", transform.text()),
+            None => println!("not isomorphic"),
+        }
+    }
+}
+
+/// Write the canonical forms of the puzzles read, or their hashes if
+/// `fingerprinting`.
+fn run_canonical(args: Args, fingerprinting: bool) {
+    let puzzles = only_options(args).puzzles();
+    if puzzles.isomorphisms != Isomorphisms::Sudoku || puzzles.variant.multi_grid() {
+        error("canonical forms only apply to plain sudoku".to_string());
+    }
+    for text in puzzles.reader(false) {
+        let (digits, _, _) = puzzles.parse(&text);
+        let board = SudokuBoard::from_str(&digits, puzzles.shared_rules.as_ref().unwrap());
+        if fingerprinting {
+            println!("{:016x}", board.fingerprint());
+        } else {
+            println!("{}", puzzles.format(&board.canonical_form()));
+        }
+    }
+}
+
+/// Analyze the puzzles read for setters.
+fn run_analyze(mut args: Args) {
+    let mut options = Options::new();
+    let mut analysis = None;
+    let mut limit = 100;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--redundant" => { analysis = Some(analyze::Analysis::Redundant); }
+            "--minimal-subpuzzles" => { analysis = Some(analyze::Analysis::Minimal { limit: 0 }); }
+            "--backdoors" => { analysis = Some(analyze::Analysis::Backdoors); }
+            "--unavoidable-sets" => { analysis = Some(analyze::Analysis::UnavoidableSets); }
+            "--limit" => {
+                let count = args.next().unwrap_or_else(|| error("--limit needs a value".to_string()));
+                limit = count.parse().unwrap_or_else(|_| error(format!("invalid limit {:?}", count)));
+            }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    let analysis = match analysis {
+        Some(analyze::Analysis::Minimal { .. }) => analyze::Analysis::Minimal { limit },
+        Some(analysis) => analysis,
+        None => {
+            error("analyze needs --redundant, --minimal-subpuzzles, --backdoors or --unavoidable-sets".to_string());
+        }
+    };
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        println!("{}", analyze::run(analysis, &SudokuBoard::from_str(&digits, rules)));
+    }
+}
+
+/// Draw `--count` solutions of each puzzle read at random.  They are
+/// drawn with replacement, so the same may come again.
+fn run_sample(mut args: Args) {
+    let mut options = Options::new();
+    let mut count = 1;
+    let mut seed = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--count" => { count = value(&arg, &mut args); }
+            "--seed" => { seed = Some(value(&arg, &mut args)); }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    let mut rng = Rng::new(seed.unwrap_or_else(Rng::clock_seed));
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let board = SudokuBoard::from_str(&digits, puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap());
+        for _ in 0 .. count {
+            match sample::sample(&board, &mut rng) {
+                Some(solution) => println!("{}", puzzles.format(&solution.to_str())),
+                None => {
+                    println!("No solution");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Transpose, reorder and relabel the puzzles read.
+fn run_transform(mut args: Args) {
+    let mut options = Options::new();
+    let mut steps = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            // The transformations are read once the grid size is known.
+            "--transpose" | "--invert" => { steps.push(arg); }
+            "--swap-bands" | "--swap-stacks" | "--rows" | "--cols" | "--relabel" => {
+                let value = args.next().unwrap_or_else(|| error(format!("{} needs a value", arg)));
+                steps.push(arg);
+                steps.push(value);
+            }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    if puzzles.isomorphisms != Isomorphisms::Sudoku || puzzles.variant.multi_grid() {
+        error("only plain sudoku can be transformed".to_string());
+    }
+    let transform = transform::parse(puzzles.shape, &mut steps.into_iter()).unwrap_or_else(|msg| error(msg));
+    for text in puzzles.reader(false) {
+        let (digits, _, _) = puzzles.parse(&text);
+        let board = SudokuBoard::from_str(&digits, puzzles.shared_rules.as_ref().unwrap());
+        println!("{}", puzzles.format(&transform.apply_board(&board).to_str()));
+    }
+}
+
+/// Write the digits that can go in the cell given with `--cell` of
+/// each puzzle read.
+fn run_possible(mut args: Args) {
+    let mut options = Options::new();
+    let mut queried = None;
+    let mut reach = possible::Reach::Propagation;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--cell" => {
+                queried = Some(args.next().unwrap_or_else(|| error("--cell needs a value".to_string())));
+            }
+            "--reach" => {
+                let name = args.next().unwrap_or_else(|| error("--reach needs a value".to_string()));
+                reach = possible::Reach::from_name(&name).unwrap_or_else(|| error(format!("unknown reach {:?}", name)));
+            }
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    let name = queried.unwrap_or_else(|| error("--cell is needed".to_string()));
+    if puzzles.variant.multi_grid() {
+        error("possible only applies to single grids".to_string());
+    }
+    let (row, col) = cell_position(&name, puzzles.shape.side())
+        .unwrap_or_else(|| error(format!("invalid cell {:?}", name)));
+    for text in puzzles.reader(false) {
+        let (digits, puzzle_rules, _) = puzzles.parse(&text);
+        let rules = puzzle_rules.as_ref().or(puzzles.shared_rules.as_ref()).unwrap();
+        let possible = SudokuBoard::from_str(&digits, rules).possible_digits(row, col, reach);
+        let possible: Vec<String> = possible.iter().map(|&d| num_to_char(d as u32).to_string()).collect();
+        println!("{}", if possible.is_empty() { "None".to_string() } else { possible.join(" ") });
+    }
+}
+
+/// Play a puzzle in the terminal.  The puzzle played is given,
+/// generated or resumed; the keys are read from the terminal.  A game
+/// left unsolved is saved, and a game solved is no longer.
+#[cfg(feature = "play")]
+fn run_play(mut args: Args) {
+    let mut options = Options::new();
+    let mut settings = generate::Settings::new();
+    let mut played = None;
+    let mut resuming = false;
+    let mut live_checks = true;
+    let mut daily = false;
+    let mut campaign = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if options.parse_arg(&arg, &mut args) => {}
+            "--puzzle" => {
+                played = Some(args.next().unwrap_or_else(|| error("--puzzle needs a value".to_string())));
+            }
+            // A game is resumed from its saved file.
+            "--resume" => { resuming = true; }
+            "--no-check" => { live_checks = false; }
+            "--daily" => { daily = true; }
+            "--campaign" => { campaign = true; }
+            // The game generates its puzzle with the generator's options.
+            _ if settings.parse_arg(&arg, &mut args) => {}
+            _ => { error(format!("unknown argument {:?}", arg)); }
+        }
+    }
+    let puzzles = options.puzzles();
+    let (variant, shape) = (puzzles.variant, puzzles.shape);
+    if !io::stdin().is_terminal() {
+        error("play needs a terminal".to_string());
+    }
+    let rules = puzzles.shared_rules.as_ref().filter(|_| !variant.multi_grid())
+        .unwrap_or_else(|| error(format!("cannot play {:?} puzzles", variant)));
+    if daily && (resuming || played.is_some() || settings.seed.is_some()) {
+        error("--daily takes neither --resume, --puzzle nor --seed".to_string());
+    }
+    if campaign && (daily || resuming || played.is_some() || settings.seed.is_some() || settings.difficulty.is_some()) {
+        error("--campaign takes neither --daily, --resume, --puzzle, --seed nor --difficulty".to_string());
+    }
+    let mut rng = Rng::new(settings.seed.unwrap_or_else(Rng::clock_seed));
+    let days = daily::today();
+    if daily {
+        rng = Rng::new(daily::seed(days, settings.difficulty));
+    }
+    let home = || error("no home directory to save games in".to_string());
+    // The next puzzle of the campaign is that of its tier and its
+    // position in the tier, and a campaign game left unsolved is
+    // saved apart from the others, and resumed as the campaign is.
+    let mut progress = None;
+    if campaign {
+        let path = save::path("campaign.txt").unwrap_or_else(home);
+        let tiers = campaign::read(&path).unwrap_or_else(|msg| error(msg));
+        settings.difficulty = Some(tiers.current());
+        rng = Rng::new(tiers.seed());
+        progress = Some((path, tiers));
+    }
+    let path = save::path(if campaign { "campaign-game.txt" } else { "game.txt" }).unwrap_or_else(home);
+    let (mut game, elapsed) = if resuming || campaign && path.exists() {
+        let saved = save::read(&path).unwrap_or_else(|msg| error(msg));
+        if Variant::from_name(&saved.variant) != Some(variant) || saved.side != shape.side() {
+            error(format!("the saved game is a {} puzzle of size {}; resume it with --variant {} --size {}",
+                          saved.variant, saved.side, saved.variant, saved.side));
+        }
+        let (puzzle, grid) = (SudokuBoard::from_str(&saved.puzzle, rules), SudokuBoard::from_str(&saved.grid, rules));
+        let game = game::Game::restore(puzzle, grid, saved.marks, saved.mistakes).unwrap_or_else(|msg| error(msg));
+        (game, std::time::Duration::from_secs(saved.elapsed))
+    } else {
+        let digits = match played {
+            Some(digits) => puzzles.alphabet.as_ref().map_or(digits.clone(), |alphabet| {
+                alphabet.to_digits(&digits).unwrap_or_else(|msg| error(msg))
+            }),
+            None => generate::one(&generate::Source::Shared(rules), &settings, &mut rng)
+                .unwrap_or_else(|| error(generate::not_found())).0,
+        };
+        (game::Game::new(SudokuBoard::from_str(&digits, rules)), std::time::Duration::ZERO)
+    };
+    let config = config::read(config::path()).unwrap_or_else(|msg| error(msg));
+    config.keys.check(shape.side()).unwrap_or_else(|msg| error(msg));
+    let elapsed = play::run(&mut game, elapsed, live_checks, &config).unwrap_or_else(|e| error(format!("cannot play: {}", e)));
+    if game.complete() {
+        if path.exists() {
+            fs::remove_file(&path).unwrap_or_else(|e| error(format!("cannot remove {}: {}", path.display(), e)));
+        }
+        if daily {
+            println!("{}", daily::share(days, settings.difficulty, elapsed, game.mistakes(), game.score(elapsed)));
+        }
+        if let Some((path, mut tiers)) = progress {
+            let difficulty = tiers.current();
+            let cleared = tiers.record(difficulty, elapsed, game.mistakes(), game.score(elapsed));
+            campaign::write(&path, &tiers).unwrap_or_else(|msg| error(msg));
+            if cleared && difficulty == logic::Difficulty::Diabolical {
+                println!("Tier {} cleared: the campaign is over", difficulty.name());
+            } else if cleared {
+                println!("Tier {} cleared: {} unlocked", difficulty.name(), tiers.current().name());
+            }
+            println!("{}", tiers.summary());
+        }
+    } else {
+        let saved = save::Saved {
+            variant: puzzles.variant_name.clone(),
+            side: shape.side(),
+            puzzle: game.givens(),
+            grid: game.entries(),
+            marks: game.all_marks().to_vec(),
+            elapsed: elapsed.as_secs(),
+            mistakes: game.mistakes(),
+        };
+        save::write(&path, &saved).unwrap_or_else(|msg| error(msg));
+        println!("Saved; resume with play {}", if campaign { "--campaign" } else { "--resume" });
+    }
+}
+
+/// Whether the digits of a solved board satisfy every constraint.
+#[cfg(all(test, any(feature = "variants", feature = "generate")))]
+fn satisfies(rules: &Rules, cells: &[CandidateSet]) -> bool {
+    let mut check = cells.to_vec();
+    return rules.constraints.iter().all(|constraint| !constraint.prune(&mut check));
+}
+
+/// The rules of standard sudoku, which most tests solve under.
+#[cfg(test)]
+fn standard_rules() -> Rules {
+    return Rules::for_puzzle(Shape::STANDARD, Variant::Standard, &[], &[]).unwrap();
+}
+
+/// The first puzzle of Project Euler's problem 96, solved by singles
+/// alone, and its solution.
+#[cfg(test)]
+const PUZZLE: &str = "..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..";
+#[cfg(test)]
+const SOLUTION: &str = "483921657967345821251876493548132976729564138136798245372689514814253769695417382";
+/// Arto Inkala's "world's hardest sudoku", which takes guesses.
+#[cfg(test)]
+const INKALA: &str = "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+#[test]
+fn test_canonical_form() {
+    let rules = standard_rules();
+    // The smallest canonical form of a solution grid, itself in disguise.
+    let smallest = "123456789456789123789123456214365897365897214897214365531642978642978531978531642";
+    let disguised: String = (0 .. NSQ)
+        .map(|i| smallest.as_bytes()[9 * (8 - i % 9) + (i / 9 + 3) % 9] as char)
+        .map(|c| (b'1' + (c as u8 - b'1' + 4) % 9) as char)
+        .collect();
+    assert!(disguised != smallest);
+    assert_eq!(SudokuBoard::from_str(&disguised, &rules).canonical_form(), smallest);
+    let one = format!("{}7{}", ".".repeat(40), ".".repeat(40));
+    assert_eq!(SudokuBoard::from_str(&one, &rules).canonical_form(), format!("{}1", ".".repeat(80)));
+    assert_eq!(SudokuBoard::from_str(&disguised, &rules).fingerprint(),
+               SudokuBoard::from_str(smallest, &rules).fingerprint());
+}
+
+#[test]
+fn test_row_col() {
+    assert_eq!(Shape::STANDARD.row(11), 1);
+    assert_eq!(Shape::STANDARD.col(11), 2);
+}
+
+#[test]
+fn test_group() {
+    assert_eq!(Shape::STANDARD.group(0), 0);
+    assert_eq!(Shape::STANDARD.group(1), 0);
+    assert_eq!(Shape::STANDARD.group(2), 0);
+    assert_eq!(Shape::STANDARD.group(9), 0);
+    assert_eq!(Shape::STANDARD.group(10), 0);
+    assert_eq!(Shape::STANDARD.group(11), 0);
+    assert_eq!(Shape::STANDARD.group(18), 0);
+    assert_eq!(Shape::STANDARD.group(19), 0);
+    assert_eq!(Shape::STANDARD.group(20), 0);
+    assert_eq!(Shape::STANDARD.group(60), 60);
+    assert_eq!(Shape::STANDARD.group(61), 60);
+    assert_eq!(Shape::STANDARD.group(62), 60);
+    assert_eq!(Shape::STANDARD.group(69), 60);
+    assert_eq!(Shape::STANDARD.group(70), 60);
+    assert_eq!(Shape::STANDARD.group(71), 60);
+    assert_eq!(Shape::STANDARD.group(78), 60);
+    assert_eq!(Shape::STANDARD.group(79), 60);
+    assert_eq!(Shape::STANDARD.group(80), 60);
+}
+
+#[test]
+fn test_neighbors() {
+    let standard = units(Shape::STANDARD, Variant::Standard, &standard_regions(Shape::STANDARD));
+    let hyper = units(Shape::STANDARD, Variant::Hyper, &standard_regions(Shape::STANDARD));
+    assert_eq!(standard.len(), 27);
+    assert_eq!(hyper.len(), 31);
+    assert_eq!(neighbors_of(0, &standard).len(), 20);
+    assert_eq!(neighbors_of(0, &hyper).len(), 20);
+    assert_eq!(neighbors_of(10, &hyper).len(), 23);
+}
+
+#[test]
+fn test_solve_hyper() {
+    let all_units = units(Shape::STANDARD, Variant::Hyper, &standard_regions(Shape::STANDARD));
+    let rules = Rules::new(Shape::STANDARD, all_units.clone());
+    let empty: String = ".".repeat(NSQ);
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    for unit in all_units.iter() {
+        let seen = unit.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+        assert_eq!(seen, FULL_SET);
+    }
+}
+
+#[test]
+fn test_solve_jigsaw() {
+    let regions = parse_regions(Shape::STANDARD, "AAABBBCCCAAABBECCCAAABEECCCDDDBBEFFFDDDEBEFFF\
+                                 DGDEEEFFIDGGHHHIFIGGGHHHIIIGGGHHHIII").unwrap();
+    let all_units = units(Shape::STANDARD, Variant::Jigsaw, &regions);
+    let rules = Rules::new(Shape::STANDARD, all_units.clone());
+    let puzzle = "1.3..67.94.....1.....1..45......7....618...9.89........\
+                  1.....4..48..5..26..9.2.1.";
+    let solution = SudokuBoard::from_str(puzzle, &rules).solve().unwrap();
+    for unit in all_units.iter() {
+        let seen = unit.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+        assert_eq!(seen, FULL_SET);
+    }
+
+    // A region map that cannot be read rejects its puzzle alone.
+    assert!(parse_regions(Shape::STANDARD, "AAAB").is_err());
+    let regions: String = (0 .. NSQ).map(|cell| if cell % N == 0 { 'A' } else { 'B' }).collect();
+    assert!(Rules::for_puzzle(Shape::STANDARD, Variant::Jigsaw, &[], &[&regions]).is_err());
+    assert!(Rules::for_puzzle(Shape::STANDARD, Variant::Jigsaw, &[], &[]).is_err());
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_killer() {
+    let labels = "BBeRiFFFFcQQRRUDKKcLGSUUDKbLLGSAADZCXNNHHHJJCXPPEEEYJ\
+                  hVPdEafYOMVddaaWIOMTTTgaWIMM";
+    let sums = "A=16,B=3,C=8,D=13,E=15,F=30,G=13,H=15,I=12,J=17,K=10,L=16,\
+                M=16,N=7,O=11,P=17,Q=11,R=19,S=3,T=18,U=14,V=12,W=7,X=10,\
+                Y=8,Z=6,a=18,b=6,c=11,d=14,e=3,f=8,g=9,h=4,i=5";
+    let rules = Rules::for_puzzle(Shape::STANDARD, Variant::Killer, &[], &[labels, sums]).unwrap();
+    let empty: String = ".".repeat(NSQ);
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    for pair in sums.split(',') {
+        let label = pair.chars().next().unwrap();
+        let sum: u32 = labels.chars().zip(solution.cells.iter())
+            .filter(|&(l, _)| l == label)
+            .map(|(_, &set)| set_to_num(set))
+            .sum();
+        assert_eq!(format!("{}={}", label, sum), pair);
+    }
+
+    // Cages that cannot be read reject their puzzle alone.
+    let impossible = sums.replace("B=3", "B=99");
+    let rejected = Rules::for_puzzle(Shape::STANDARD, Variant::Killer, &[], &[labels, &impossible]);
+    assert_eq!(rejected.err(), Some("no 2 distinct digits add up to 99".to_string()));
+    let missing = sums.replace("B=3,", "");
+    assert!(Rules::for_puzzle(Shape::STANDARD, Variant::Killer, &[], &[labels, &missing]).is_err());
+    assert!(Rules::for_puzzle(Shape::STANDARD, Variant::Killer, &[], &[labels]).is_err());
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_samurai() {
+    let all_units = layout::SAMURAI.units();
+    let rules = Rules::new(Shape::STANDARD, all_units.clone());
+    let empty: String = ".".repeat(rules.neighbors.len());
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    for unit in all_units.iter() {
+        let seen = unit.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+        assert_eq!(seen, FULL_SET);
+    }
+    let text = layout::SAMURAI.format(&solution.to_str());
+    assert_eq!(layout::SAMURAI.parse(&text.lines().collect::<Vec<_>>()), Ok(solution.to_str()));
+}
+
+#[test]
+fn test_anti_knight() {
+    let rules = Rules::for_puzzle(Shape::STANDARD, Variant::Standard, &[Extra::AntiKnight], &[]).unwrap();
+    // The knight moves from a corner stay in its box.
+    assert_eq!(rules.neighbors[0].len(), 20);
+    assert_eq!(rules.neighbors[40].len(), 20 + 8);
+    let empty: String = ".".repeat(NSQ);
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    for pair in Extra::AntiKnight.units(Shape::STANDARD) {
+        assert!(solution.cells[pair[0]] != solution.cells[pair[1]]);
+    }
+}
+
+#[test]
+fn test_anti_king() {
+    let rules = Rules::for_puzzle(Shape::STANDARD, Variant::Standard,
+                                  &[Extra::AntiKnight, Extra::AntiKing], &[]).unwrap();
+    // From the edge of a box, two knight moves and one diagonal
+    // move lead to the next box.
+    assert_eq!(rules.neighbors[2].len(), 20 + 2 + 1);
+    let empty: String = ".".repeat(NSQ);
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    for pair in Extra::AntiKing.units(Shape::STANDARD) {
+        assert!(solution.cells[pair[0]] != solution.cells[pair[1]]);
+    }
+}
+
+#[test]
+fn test_non_consecutive() {
+    let rules = Rules::for_puzzle(Shape::STANDARD, Variant::Standard, &[Extra::NonConsecutive], &[]).unwrap();
+    let puzzle = format!("135279468{}", ".".repeat(NSQ - N));
+    let solution = SudokuBoard::from_str(&puzzle, &rules).solve().unwrap();
+    for link in Extra::NonConsecutive.links(Shape::STANDARD) {
+        let first = set_to_num(solution.cells[link.first]);
+        let second = set_to_num(solution.cells[link.second]);
+        assert!(first + 1 != second && second + 1 != first);
+    }
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_parse_cell() {
+    assert_eq!(parse_cell("r1c1"), Ok(0));
+    assert_eq!(parse_cell("r2c3"), Ok(11));
+    assert_eq!(parse_cell("r9c9"), Ok(80));
+    assert!(parse_cell("r0c1").is_err());
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_thermometers() {
+    let puzzle = json_puzzle::parse(&format!(r#"{{"grid": "{}", "thermometers": [
+        ["r1c1", "r1c2", "r1c3", "r2c3", "r3c3", "r3c2", "r3c1", "r2c1"],
+        ["r9c9", "r8c8", "r7c7", "r6c6", "r5c5"]
+    ]}}"#, ".".repeat(NSQ))).unwrap();
+    let mut rules = standard_rules();
+    rules.add_clues(puzzle.clues);
+    let solution = SudokuBoard::from_str(&puzzle.grid, &rules).solve().unwrap();
+    assert_eq!(&solution.to_str()[0 .. 3], "123");
+    assert!(satisfies(&rules, &solution.cells));
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_sandwiches() {
+    // The clues of 123456789456789123789123456817234965634597218...
+    let puzzle = json_puzzle::parse(&format!(r#"{{"grid": "123456789{}", "sandwiches": {{
+        "rows": [35, 0, 0, 16, 9, 8, 18, 21, 21],
+        "columns": [30, 3, 18, 24, 0, 22, null, 11, null]
+    }}}}"#, ".".repeat(NSQ - N))).unwrap();
+    let mut rules = standard_rules();
+    rules.add_clues(puzzle.clues);
+    let solution = SudokuBoard::from_str(&puzzle.grid, &rules).solve().unwrap();
+    assert!(satisfies(&rules, &solution.cells));
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_greater_than() {
+    let lines = [
+        ".<.<. .<.<. .<.<.",
+        "^ ^ ^ ^ ^ ^ v v v",
+        ".<.<. .<.<. .<.<.",
+        "^ ^ ^ v v v ^ ^ ^",
+        ".<.<. .<.<. .<.<.",
+        "",
+        ".>.<. .<.<. .>.>.",
+        "v ^ v ^ ^ ^ v v ^",
+        ".>.<. .<.>. .>.<.",
+        "v ^ v ^ v ^ ^ ^ v",
+        ".<.>. .>.<. .<.<.",
+        "",
+        ".>.>. .<.>. .>.>.",
+        "v ^ v ^ ^ ^ v ^ ^",
+        ".<.>. .>.>. .<.>.",
+        "v v ^ ^ ^ ^ ^ v v",
+        ".<.<. .>.>. .>.>.",
+    ];
+    let (digits, links) = inequality::parse(&lines).unwrap();
+    let mut rules = Rules::for_puzzle(Shape::STANDARD, Variant::GreaterThan, &[], &[]).unwrap();
+    rules.extend(links);
+    let solution = SudokuBoard::from_str(&digits, &rules).solve().unwrap();
+    assert_eq!(solution.to_str(), "123456789456789123789123456817234965634597218\
+                                   592618347965341872371862594248975631");
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_xv() {
+    let puzzle = json_puzzle::parse(&format!(r#"{{"grid": "{}",
+        "x": [["r1c8", "r2c8"], ["r2c5", "r3c5"], ["r2c6", "r2c7"], ["r3c3", "r3c4"],
+              ["r5c5", "r6c5"], ["r7c5", "r8c5"], ["r8c1", "r8c2"]],
+        "v": [["r1c1", "r2c1"], ["r1c2", "r1c3"], ["r2c7", "r3c7"], ["r2c8", "r2c9"],
+              ["r3c5", "r3c6"], ["r3c5", "r4c5"], ["r4c4", "r4c5"], ["r5c7", "r6c7"],
+              ["r5c8", "r6c8"], ["r6c5", "r7c5"], ["r7c5", "r7c6"], ["r8c1", "r9c1"],
+              ["r8c9", "r9c9"]],
+        "xv_negative": true}}"#, ".".repeat(NSQ))).unwrap();
+    let mut rules = standard_rules();
+    rules.add_clues(puzzle.clues);
+    let solution = SudokuBoard::from_str(&puzzle.grid, &rules).solve().unwrap();
+    assert!(satisfies(&rules, &solution.cells));
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_arrows() {
+    let puzzle = json_puzzle::parse(r#"{
+        "grid": "1.........5..............5..........6..........2..............2..........4.......",
+        "arrows": [
+            {"circle": "r8c4", "cells": ["r8c5", "r8c6"]},
+            {"circle": "r4c1", "cells": ["r3c1", "r4c2"]},
+            {"circle": "r2c3", "cells": ["r2c2", "r1c1"]},
+            {"circle": "r9c3", "cells": ["r8c3", "r7c3", "r6c3"]},
+            {"circle": "r1c7", "cells": ["r2c7", "r1c6"]},
+            {"circle": "r2c4", "cells": ["r1c4", "r1c3"]},
+            {"circle": "r8c7", "cells": ["r7c6", "r6c5", "r7c4"]},
+            {"circle": "r4c8", "cells": ["r5c7", "r4c6"]},
+            {"circle": "r8c8", "cells": ["r9c8", "r8c9", "r7c9"]},
+            {"circle": "r1c9", "cells": ["r2c8", "r3c7", "r3c6"]},
+            {"circle": "r5c3", "cells": ["r4c4", "r3c5"]},
+            {"circle": "r5c9", "cells": ["r6c9", "r5c8"]}
+        ]}"#).unwrap();
+    let mut rules = standard_rules();
+    rules.add_clues(puzzle.clues);
+    let solution = SudokuBoard::from_str(&puzzle.grid, &rules).solve().unwrap();
+    assert!(satisfies(&rules, &solution.cells));
+
+    assert!(json_puzzle::parse(r#"{"nogrid": 1}"#).is_err());
+    assert!(json_puzzle::parse(r#"{"grid": "", "arrows": [{"circle": "r0c1", "cells": []}]}"#).is_err());
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_solve_little_killers() {
+    let puzzle = json_puzzle::parse(&format!(r#"{{"grid": "123456789456789123789123456817234965{}", "little_killers": [
+        {{"start": "r1c2", "direction": "down-right", "sum": 33}},
+        {{"start": "r1c3", "direction": "down-right", "sum": 24}},
+        {{"start": "r2c1", "direction": "down-right", "sum": 34}},
+        {{"start": "r3c1", "direction": "down-right", "sum": 30}},
+        {{"start": "r1c8", "direction": "down-left", "sum": 31}},
+        {{"start": "r9c2", "direction": "up-right", "sum": 33}},
+        {{"start": "r9c8", "direction": "up-left", "sum": 34}},
+        {{"start": "r4c9", "direction": "down-left", "sum": 25}}
+    ]}}"#, ".".repeat(NSQ - 4 * N))).unwrap();
+    assert_eq!(puzzle.clues.little_killers[0].cells, vec![1, 11, 21, 31, 41, 51, 61, 71]);
+    let mut rules = standard_rules();
+    rules.add_clues(puzzle.clues);
+    let solution = SudokuBoard::from_str(&puzzle.grid, &rules).solve().unwrap();
+    assert!(satisfies(&rules, &solution.cells));
+}
+
+#[test]
+fn test_asterisk() {
+    let names = ["r2c5", "r3c3", "r3c7", "r5c2", "r5c5", "r5c8", "r7c3", "r7c7", "r8c5"];
+    let cells: Vec<usize> = names.iter().map(|name| parse_cell(name).unwrap()).collect();
+    assert_eq!(cells, ASTERISK.to_vec());
+    let rules = Rules::for_puzzle(Shape::STANDARD, Variant::Hyper, &[Extra::Asterisk], &[]).unwrap();
+    let empty: String = ".".repeat(NSQ);
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    let seen = ASTERISK.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+    assert_eq!(seen, FULL_SET);
+}
+
+#[test]
+fn test_diagonals() {
+    let shape = Shape::from_side(6).unwrap();
+    let rules = Rules::for_puzzle(shape, Variant::Standard, &[Extra::Diagonals], &[]).unwrap();
+    let solution = SudokuBoard::from_str(&".".repeat(36), &rules).solve().unwrap();
+    for diagonal in Extra::Diagonals.units(shape) {
+        let seen = diagonal.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+        assert_eq!(seen, shape.full_set());
+    }
+}
+
+#[cfg(feature = "generate")]
+#[test]
+fn test_random_solution() {
+    let rules = Rules::for_puzzle(Shape::STANDARD, Variant::Standard, &[Extra::AntiKnight], &[]).unwrap();
+    let grid = SudokuBoard::random_solution(&rules, &mut Rng::new(1)).unwrap();
+    assert!(grid.solved() && satisfies(&rules, &grid.cells));
+    let other = SudokuBoard::random_solution(&rules, &mut Rng::new(2)).unwrap();
+    assert!(grid.cells != other.cells);
+}
+
+#[test]
+fn test_shape() {
+    assert_eq!(Shape::from_side(6), Some(Shape { box_rows: 2, box_cols: 3 }));
+    assert_eq!(Shape::from_side(9), Some(Shape::STANDARD));
+    assert_eq!(Shape::from_side(12), Some(Shape { box_rows: 3, box_cols: 4 }));
+    assert_eq!(Shape::from_side(7), None);
+    assert_eq!(Shape::from_side(12).unwrap().group(100), 76);
+}
+
+#[test]
+fn test_solve_sizes() {
+    for &side in [6, 12].iter() {
+        let shape = Shape::from_side(side).unwrap();
+        let rules = Rules::for_puzzle(shape, Variant::Standard, &[Extra::AntiKing], &[]).unwrap();
+        let empty: String = ".".repeat(shape.cells());
+        let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+        for unit in units(shape, Variant::Standard, &standard_regions(shape)) {
+            let seen = unit.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+            assert_eq!(seen, shape.full_set());
+        }
+        let text = solution.to_str();
+        assert_eq!(SudokuBoard::from_str(&text, &rules).to_str(), text);
+    }
+}
+
+#[test]
+fn test_solve_latin() {
+    let shape = Shape::latin(7).unwrap();
+    let rules = Rules::for_puzzle(shape, Variant::Latin, &[], &[]).unwrap();
+    assert_eq!(rules.neighbors[0].len(), 2 * 6);
+    let puzzle = format!("1234567{}", ".".repeat(shape.cells() - 7));
+    let solution = SudokuBoard::from_str(&puzzle, &rules).solve().unwrap();
+    for unit in units(shape, Variant::Latin, &[]) {
+        let seen = unit.iter().fold(EMPTY_SET, |acc, &i| acc | solution.cells[i]);
+        assert_eq!(seen, shape.full_set());
+    }
+}
+
+#[test]
+fn test_connected() {
+    let mut regions = standard_regions(Shape::STANDARD);
+    assert!(connected(Shape::STANDARD, &regions));
+    // Swapping r1c1 and r1c4 strands r1c1 away from its new box.
+    regions.swap(0, 3);
+    assert!(!connected(Shape::STANDARD, &regions));
+}
+
+#[test]
+fn test_limits() {
+    let rules = standard_rules();
+    assert!(SudokuBoard::parse("12x", &rules).is_err());
+    let board = SudokuBoard::parse(&".".repeat(NSQ), &rules).unwrap();
+    let limits = Limits::new(Some(Duration::from_secs(0)), None);
+    assert!(board.search(None, &mut 0, &limits).is_none());
+    assert_eq!(limits.exceeded.get(), Some(Exceeded::Time));
+    // The search holds a board for each guess deep it goes.
+    let limits = Limits::new(None, Some(10 * board.footprint()));
+    assert!(board.search(None, &mut 0, &limits).is_none());
+    assert_eq!(limits.exceeded.get(), Some(Exceeded::Memory));
+    let limits = Limits::new(Some(Duration::from_secs(60)), Some(100 * board.footprint()));
+    assert!(board.search(None, &mut 0, &limits).is_some());
+    assert_eq!((limits.exceeded.get(), limits.used.get()), (None, 0));
+
+    assert_eq!(parse_size("64M"), Some(64 << 20));
+    assert_eq!(parse_size("1000"), Some(1000));
+    assert_eq!(parse_size("M"), None);
 }
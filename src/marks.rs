@@ -0,0 +1,132 @@
+// Positions given by their pencil marks: the candidates left in every
+// cell, as a solver has marked them, instead of the digits placed.
+// The candidates removed by hand stay removed, so a solve can be
+// finished from exactly where it stands, and marks that went wrong
+// along the way are told apart from a puzzle without a solution.
+
+use super::{Cells, Rules, SudokuBoard};
+
+/// What comes of solving a position given by its pencil marks.
+pub enum Outcome {
+    /// The digits of the solution the marks lead to.
+    Solved(String),
+    /// The marks have no solution, but the digits placed do: a
+    /// candidate of the solution was removed.
+    Excluded,
+    /// The digits placed have no solution.
+    Unsolvable,
+}
+
+/// Whether `token` is only the border of a grid of marks.
+fn border(token: &str) -> bool {
+    return token.chars().all(|c| ".-:+|'".contains(c));
+}
+
+/// Whether `line` is a line of the library format of HoDoKu, which
+/// `export` writes before the grid of marks of a position.
+fn library(line: &str) -> bool {
+    let line = line.trim();
+    return line.starts_with(':') && !border(line);
+}
+
+/// Return the marks of the cells of `text`, skipping the borders of
+/// the grids and the library lines.
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    return text.lines()
+        .filter(|line| !library(line))
+        .flat_map(str::split_whitespace)
+        .filter(|token| !border(token));
+}
+
+/// Return the number of cells given by the marks of `text` so far.
+pub fn count(text: &str) -> usize {
+    return tokens(text).count();
+}
+
+/// Return the texts of the grids of marks on the lines of `text`,
+/// each giving `cells` cells.
+pub fn split(text: &str, cells: usize) -> Vec<String> {
+    let mut grids = Vec::new();
+    let mut grid = String::new();
+    for line in text.lines().filter(|line| count(line) > 0) {
+        grid.push_str(line);
+        grid.push('\n');
+        if count(&grid) >= cells {
+            grids.push(grid.clone());
+            grid.clear();
+        }
+    }
+    if !grid.is_empty() {
+        grids.push(grid);
+    }
+    return grids;
+}
+
+/// Return the board of the marks of `text` under `rules`, the
+/// candidates of every cell written together, e.g., "158", and the
+/// cells apart; the borders of the grids of HoDoKu and its library
+/// lines are skipped, so the positions written by `export` are read
+/// back.
+pub fn board<'a>(text: &str, rules: &'a Rules) -> Result<SudokuBoard<'a>, String> {
+    let side = rules.shape.side() as u32;
+    let mut cells = Vec::new();
+    for token in tokens(text) {
+        let mut set = 0;
+        for c in token.chars() {
+            match c.to_digit(36) {
+                Some(n) if (1 ..= side).contains(&n) => { set |= 1 << (n - 1); }
+                _ => { return Err(format!("invalid candidate ({:?}) in marks {:?}", c, token)); }
+            }
+        }
+        cells.push(set);
+    }
+    if cells.len() != rules.neighbors.len() {
+        return Err(format!("invalid number of cells; expected {}, got {}", rules.neighbors.len(), cells.len()));
+    }
+    return Ok(SudokuBoard { cells: Cells::from(&cells[..]), rules });
+}
+
+/// Solve the position `marks`, keeping its candidates.  The digits
+/// placed are its cells with a single candidate.
+pub fn solve(marks: &SudokuBoard) -> Outcome {
+    if let Some(solution) = marks.solve() {
+        return Outcome::Solved(solution.to_str());
+    }
+    let mut placed = marks.clone();
+    for cell in 0 .. placed.cells.len() {
+        if !marks.cell_solved(cell) {
+            placed.cells[cell] = placed.rules.shape.full_set();
+        }
+    }
+    return if placed.solve().is_some() { Outcome::Excluded } else { Outcome::Unsolvable };
+}
+
+#[test]
+fn test_marks() {
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    // Two solutions, the 9 and 6 of r2c1 and r2c2 swapping with the
+    // 6 and 9 of r9c1 and r9c2; the marks keep the second.
+    let digits = "483921657..7345821251876493548132976729564138136798245372689514814253769..5417382";
+    let mut text: Vec<String> = digits.chars()
+        .map(|c| if c == '.' { "69".to_string() } else { c.to_string() })
+        .collect();
+    text[9] = "6".to_string();
+    let marks = board(&text.join(" "), &rules).unwrap();
+    assert_eq!(count(&text.join(" ")), 81);
+    match solve(&marks) {
+        Outcome::Solved(solution) => assert_eq!(&solution[9 .. 11], "69"),
+        _ => panic!("the marks have a solution"),
+    }
+    // No 6 nor 9 is left in r2c1.
+    text[9] = "18".to_string();
+    assert!(matches!(solve(&board(&text.join(" "), &rules).unwrap()), Outcome::Excluded));
+    text[0] = "3".to_string();
+    assert!(matches!(solve(&board(&text.join(" "), &rules).unwrap()), Outcome::Unsolvable));
+    assert!(board("1 2 3", &rules).is_err());
+    assert_eq!(count(":0000:x:4+83921657...:218 319::\n"), 0);
+    let grids = split(&format!(".---.\n{}\n{}\n'---'\n\n{}\n", &text[.. 40].join(" "), &text[40 ..].join(" "), text.join(" ")), 81);
+    assert_eq!(grids.len(), 2);
+    assert!(grids.iter().all(|grid| count(grid) == 81));
+}
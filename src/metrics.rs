@@ -0,0 +1,116 @@
+// Metrics of the server, written at `/metrics` in the text format of
+// Prometheus: the requests answered, by path and status, the requests
+// that timed out, and histograms of the time taken to solve puzzles
+// and of the guesses each one needed.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of buckets of a histogram, not counting the last one,
+/// which has no upper bound.
+const BUCKETS: usize = 8;
+
+/// Upper bounds of the buckets of solving times, in seconds.
+const SECONDS: [f64; BUCKETS] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 1.0];
+
+/// Upper bounds of the buckets of guesses.
+const GUESSES: [f64; BUCKETS] = [0.0, 1.0, 2.0, 5.0, 10.0, 100.0, 1000.0, 10000.0];
+
+/// The paths counted by name; the others are counted together.
+const PATHS: [&str; 5] = ["/solve", "/rate", "/generate", "/ws", "/metrics"];
+
+struct Histogram {
+    bounds: [f64; BUCKETS],
+    /// The observations in each bucket, the last one unbounded.
+    counts: [u64; BUCKETS + 1],
+    sum: f64,
+}
+
+impl Histogram {
+    const fn new(bounds: [f64; BUCKETS]) -> Self {
+        Histogram { bounds, counts: [0; BUCKETS + 1], sum: 0.0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.iter().position(|&bound| value <= bound).unwrap_or(BUCKETS);
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    /// Write the histogram `name` to `text`; its buckets are
+    /// cumulative, as Prometheus expects.
+    fn write(&self, text: &mut String, name: &str, help: &str) {
+        let _ = writeln!(text, "# HELP {} {}\n# TYPE {} histogram", name, help, name);
+        let mut count = 0;
+        for (i, &n) in self.counts.iter().enumerate() {
+            count += n;
+            let bound = self.bounds.get(i).map_or("+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(text, "{}_sum {}\n{}_count {}", name, self.sum, name, count);
+    }
+}
+
+struct Metrics {
+    /// Requests answered, by path and status.
+    requests: BTreeMap<(&'static str, u16), u64>,
+    timeouts: u64,
+    seconds: Histogram,
+    guesses: Histogram,
+}
+
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics {
+    requests: BTreeMap::new(),
+    timeouts: 0,
+    seconds: Histogram::new(SECONDS),
+    guesses: Histogram::new(GUESSES),
+});
+
+/// Count a request for `path` answered with `status`.
+pub fn request(path: &str, status: u16) {
+    let path = PATHS.iter().find(|&&p| p == path).map_or("other", |p| p);
+    *METRICS.lock().unwrap().requests.entry((path, status)).or_insert(0) += 1;
+}
+
+/// Count a request that timed out.
+pub fn timeout() {
+    METRICS.lock().unwrap().timeouts += 1;
+}
+
+/// Record a puzzle solved in `elapsed` with `guesses` guesses.
+pub fn solved(elapsed: Duration, guesses: usize) {
+    let mut metrics = METRICS.lock().unwrap();
+    metrics.seconds.observe(elapsed.as_secs_f64());
+    metrics.guesses.observe(guesses as f64);
+}
+
+/// Return the metrics in the text format of Prometheus.
+pub fn text() -> String {
+    let metrics = METRICS.lock().unwrap();
+    let mut text = String::new();
+    text.push_str("# HELP sudoku_requests_total Requests answered, by path and status.\n\
+                   # TYPE sudoku_requests_total counter\n");
+    for (&(path, status), n) in metrics.requests.iter() {
+        let _ = writeln!(text, "sudoku_requests_total{{path=\"{}\",status=\"{}\"}} {}", path, status, n);
+    }
+    let _ = writeln!(text, "# HELP sudoku_timeouts_total Requests that timed out.\n\
+                            # TYPE sudoku_timeouts_total counter\nsudoku_timeouts_total {}", metrics.timeouts);
+    metrics.seconds.write(&mut text, "sudoku_solve_seconds", "Time taken to solve a puzzle.");
+    metrics.guesses.write(&mut text, "sudoku_guesses", "Guesses made to solve a puzzle.");
+    return text;
+}
+
+#[test]
+fn test_histogram() {
+    let mut histogram = Histogram::new(GUESSES);
+    for &value in [0.0, 3.0, 4.0, 1e6].iter() {
+        histogram.observe(value);
+    }
+    let mut text = String::new();
+    histogram.write(&mut text, "g", "Guesses.");
+    assert!(text.contains("g_bucket{le=\"0\"} 1\n"));
+    assert!(text.contains("g_bucket{le=\"2\"} 1\ng_bucket{le=\"5\"} 3\n"));
+    assert!(text.contains("g_bucket{le=\"+Inf\"} 4\ng_sum 1000007\ng_count 4\n"));
+}
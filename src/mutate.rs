@@ -0,0 +1,114 @@
+// Puzzle mutation: derive new puzzles from one, keeping its
+// solution.  A mutation either moves a clue to a blank cell, which
+// gets the digit of the solution there, or fills a house from the
+// solution and digs it again in a random order.  Mutants are kept
+// only if their solution is still unique.
+
+use super::{CandidateSet, SudokuBoard};
+use generate::{self, Settings};
+use logic;
+use rng::Rng;
+
+/// Number of mutations tried for each mutant written.
+const ATTEMPTS: usize = 1000;
+
+/// Move a random clue of `board` to a random blank cell, with the
+/// digit of `solution` there.  Return whether the solution stays
+/// unique; the board is left as it was if not.
+fn move_clue(board: &mut SudokuBoard, solution: &[CandidateSet], rng: &mut Rng) -> bool {
+    let (clues, blanks): (Vec<usize>, Vec<usize>) = (0 .. board.cells.len())
+        .partition(|&c| board.cell_solved(c));
+    if clues.is_empty() || blanks.is_empty() {
+        return false;
+    }
+    let (clue, blank) = (clues[rng.below(clues.len())], blanks[rng.below(blanks.len())]);
+    board.cells[clue] = board.rules.shape.full_set();
+    board.cells[blank] = solution[blank];
+    if board.count_solutions(2) == 1 {
+        return true;
+    }
+    board.cells[clue] = solution[clue];
+    board.cells[blank] = board.rules.shape.full_set();
+    return false;
+}
+
+/// Fill a random house of `board` from `solution`, then blank its
+/// cells in a random order as long as the solution stays unique.
+fn redig_house(board: &mut SudokuBoard, solution: &[CandidateSet], rng: &mut Rng) {
+    let houses = &board.rules.houses;
+    if houses.is_empty() {
+        return;
+    }
+    let mut house = houses[rng.below(houses.len())].clone();
+    for &cell in house.iter() {
+        board.cells[cell] = solution[cell];
+    }
+    rng.shuffle(&mut house);
+    for cell in house {
+        board.cells[cell] = board.rules.shape.full_set();
+        if board.count_solutions(2) != 1 {
+            board.cells[cell] = solution[cell];
+        }
+    }
+}
+
+/// Return a mutant of `board`, a puzzle with the unique solution
+/// `solution`: a puzzle with the same solution, other clues, and
+/// no other solution.  Return None if no mutation changes it.
+pub fn mutant(board: &SudokuBoard, solution: &[CandidateSet], rng: &mut Rng) -> Option<String> {
+    let digits = board.to_str();
+    for _ in 0 .. ATTEMPTS {
+        let mut mutant = board.clone();
+        if rng.below(2) == 0 {
+            move_clue(&mut mutant, solution, rng);
+        } else {
+            redig_house(&mut mutant, solution, rng);
+        }
+        let mutant = mutant.to_str();
+        if mutant != digits {
+            return Some(mutant);
+        }
+    }
+    return None;
+}
+
+/// Print as many mutants of `board` as `settings` asks for, written
+/// out by `format` and followed by the `fields` of its rules, each
+/// with its rating.
+pub fn run(board: &SudokuBoard, settings: &Settings, rng: &mut Rng, fields: Option<String>,
+           format: &dyn Fn(&str) -> String) {
+    if board.count_solutions(2) != 1 {
+        println!("No unique solution");
+        return;
+    }
+    let solution = board.solve().unwrap().cells;
+    for _ in 0 .. settings.count {
+        let digits = match mutant(board, &solution, rng) {
+            Some(digits) => digits,
+            None => {
+                println!("No mutant");
+                return;
+            }
+        };
+        let rating = logic::rate(&SudokuBoard::from_str(&digits, board.rules));
+        generate::print(&digits, fields.clone(), generate::report(settings, &digits, rating), format);
+    }
+}
+
+#[test]
+fn test_mutant() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let digits = PUZZLE;
+    let board = SudokuBoard::from_str(digits, &rules);
+    let solution = board.solve().unwrap().cells;
+    let mut rng = Rng::new(1);
+    for _ in 0 .. 10 {
+        let mutant = mutant(&board, &solution, &mut rng).unwrap();
+        assert!(mutant != digits);
+        let mutant = SudokuBoard::from_str(&mutant, &rules);
+        assert_eq!(mutant.count_solutions(2), 1);
+        assert_eq!(mutant.solve().unwrap().cells, solution);
+    }
+}
@@ -0,0 +1,63 @@
+// Buffered standard output for batch runs: the lines written go to
+// a buffer that is flushed every so many lines, so that large inputs
+// take few writes.  Error messages flush it first, so that they come
+// after the lines written before them.
+
+use std::io::{self, BufWriter, Stdout, Write};
+use std::sync::Mutex;
+
+struct Output {
+    writer: BufWriter<Stdout>,
+    /// Lines written since the last flush.
+    lines: usize,
+    /// Lines between flushes; 0 to flush only a full buffer.
+    flush_every: usize,
+    /// Bytes written since the start.
+    bytes: u64,
+}
+
+static OUTPUT: Mutex<Option<Output>> = Mutex::new(None);
+
+/// Buffer the lines written from now on, flushing them every
+/// `flush_every` lines, or only when the buffer is full if 0.
+pub fn start(flush_every: usize) {
+    let writer = BufWriter::with_capacity(1 << 16, io::stdout());
+    *OUTPUT.lock().unwrap() = Some(Output { writer, lines: 0, flush_every, bytes: 0 });
+}
+
+/// Write `line` and a newline.
+pub fn write_line(line: &str) {
+    let mut output = OUTPUT.lock().unwrap();
+    let output = match *output {
+        Some(ref mut output) => output,
+        None => {
+            println!("{}", line);
+            return;
+        }
+    };
+    let written = writeln!(output.writer, "{}", line);
+    if written.is_ok() {
+        output.bytes += line.len() as u64 + 1;
+    }
+    output.lines += 1;
+    if written.is_ok() && output.lines == output.flush_every {
+        output.lines = 0;
+        let _ = output.writer.flush();
+    }
+}
+
+/// Return the bytes of the lines written since the start, out or
+/// still buffered.
+pub fn written() -> u64 {
+    return OUTPUT.lock().unwrap().as_ref().map_or(0, |output| output.bytes);
+}
+
+/// Write out the buffered lines.
+pub fn flush() {
+    if let Ok(mut output) = OUTPUT.lock() {
+        if let Some(ref mut output) = *output {
+            output.lines = 0;
+            let _ = output.writer.flush();
+        }
+    }
+}
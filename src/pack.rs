@@ -0,0 +1,164 @@
+// Puzzle packs: a compact binary form of a list of puzzles sharing
+// their rules, for shipping large collections.  A pack starts with a
+// header:
+//
+//  - the magic bytes `SDKP` and the version of the format, 1;
+//  - a byte of flags: 1 if the pack holds ratings;
+//  - the side of the grid, a byte, and the name of the variant, a
+//    byte of length followed by its characters;
+//  - the number of cells of a grid, 2 bytes, and the number of
+//    puzzles, 4 bytes, both little-endian.
+//
+// Then come the grids, the value of each cell (0 for a blank) in as
+// few bits as its largest value takes, each grid starting on a byte,
+// then the ratings, a byte each.
+
+use std::io::{self, Read, Write};
+
+use super::num_to_char;
+use logic::Difficulty;
+
+const MAGIC: &[u8] = b"SDKP";
+const VERSION: u8 = 1;
+
+/// The flag of packs with ratings.
+const RATED: u8 = 1;
+
+/// The ratings, in the order of their bytes.
+const RATINGS: [Difficulty; 5] = [
+    Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Fiendish, Difficulty::Diabolical,
+];
+
+/// A list of puzzles with the same rules.
+pub struct Pack {
+    pub variant: String,
+    pub side: usize,
+    /// The digits of the puzzles, with `.` for blanks.
+    pub puzzles: Vec<String>,
+    pub ratings: Option<Vec<Difficulty>>,
+}
+
+/// Return the number of bits of a cell of a grid of side `side`.
+fn cell_bits(side: usize) -> usize {
+    (usize::BITS - side.leading_zeros()) as usize
+}
+
+/// Write `pack` to `output`.
+pub fn write<W: Write>(pack: &Pack, output: &mut W) -> io::Result<()> {
+    let cells = pack.puzzles.first().map_or(0, |digits| digits.chars().count());
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    header.push(if pack.ratings.is_some() { RATED } else { 0 });
+    header.push(pack.side as u8);
+    header.push(pack.variant.len() as u8);
+    header.extend_from_slice(pack.variant.as_bytes());
+    header.extend_from_slice(&(cells as u16).to_le_bytes());
+    header.extend_from_slice(&(pack.puzzles.len() as u32).to_le_bytes());
+    output.write_all(&header)?;
+
+    let bits = cell_bits(pack.side);
+    for digits in pack.puzzles.iter() {
+        let mut grid = vec![0u8; (cells * bits).div_ceil(8)];
+        for (i, c) in digits.chars().enumerate() {
+            let value = c.to_digit(36).unwrap_or(0) as usize;
+            for b in 0 .. bits {
+                if value & (1 << b) != 0 {
+                    grid[(i * bits + b) / 8] |= 1 << ((i * bits + b) % 8);
+                }
+            }
+        }
+        output.write_all(&grid)?;
+    }
+    if let Some(ref ratings) = pack.ratings {
+        let bytes: Vec<u8> = ratings.iter().map(|r| RATINGS.iter().position(|x| x == r).unwrap() as u8).collect();
+        output.write_all(&bytes)?;
+    }
+    return Ok(());
+}
+
+/// Read a pack from `input`.
+pub fn read<R: Read>(input: &mut R) -> Result<Pack, String> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    let truncated = || "truncated pack".to_string();
+    let mut rest = &bytes[..];
+    let mut take = |n: usize| -> Result<&[u8], String> {
+        if rest.len() < n {
+            return Err(truncated());
+        }
+        let (taken, left) = rest.split_at(n);
+        rest = left;
+        return Ok(taken);
+    };
+
+    if take(MAGIC.len()).ok() != Some(MAGIC) {
+        return Err("not a puzzle pack".to_string());
+    }
+    let version = take(1)?[0];
+    if version != VERSION {
+        return Err(format!("unsupported pack version {}", version));
+    }
+    let flags = take(1)?[0];
+    let side = take(1)?[0] as usize;
+    let length = take(1)?[0] as usize;
+    let variant = String::from_utf8(take(length)?.to_vec()).map_err(|_| "invalid variant name".to_string())?;
+    let cells = u16::from_le_bytes([take(1)?[0], take(1)?[0]]) as usize;
+    let mut count = [0; 4];
+    count.copy_from_slice(take(4)?);
+    let count = u32::from_le_bytes(count) as usize;
+
+    let bits = cell_bits(side);
+    let size = (cells * bits).div_ceil(8);
+    if size == 0 && count > 0 {
+        return Err("invalid pack; its grids have no cells".to_string());
+    }
+    // The count comes from the file: the bytes it takes must be there
+    // before any is allocated for it.
+    let grids = take(count.checked_mul(size).ok_or_else(truncated)?)?;
+    let rated = if flags & RATED != 0 { Some(take(count)?) } else { None };
+    let mut puzzles = Vec::with_capacity(count);
+    for grid in grids.chunks(size) {
+        let digits = (0 .. cells).map(|i| {
+            let value = (0 .. bits).filter(|b| grid[(i * bits + b) / 8] & (1 << ((i * bits + b) % 8)) != 0)
+                .fold(0, |value, b| value | 1 << b);
+            return if value == 0 { '.' } else { num_to_char(value) };
+        }).collect();
+        puzzles.push(digits);
+    }
+    let ratings = match rated {
+        Some(bytes) => Some(bytes.iter()
+            .map(|&byte| RATINGS.get(byte as usize).cloned().ok_or_else(|| format!("invalid rating {}", byte)))
+            .collect::<Result<Vec<_>, _>>()?),
+        None => None,
+    };
+    return Ok(Pack { variant, side, puzzles, ratings });
+}
+
+#[test]
+fn test_pack() {
+    use super::PUZZLE;
+
+    let pack = Pack {
+        variant: "standard".to_string(),
+        side: 9,
+        puzzles: vec![
+            PUZZLE.to_string(),
+            "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......".to_string(),
+        ],
+        ratings: Some(vec![Difficulty::Easy, Difficulty::Diabolical]),
+    };
+    let mut bytes = Vec::new();
+    write(&pack, &mut bytes).unwrap();
+    // A header of 22 bytes, 41 bytes a grid and a byte a rating.
+    assert_eq!(bytes.len(), 22 + 2 * 41 + 2);
+    assert!(read(&mut &bytes[.. 50]).is_err());
+    // A forged count of puzzles is not allocated for.
+    let mut forged = bytes[.. 22].to_vec();
+    forged[18 .. 22].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(read(&mut &forged[..]).err(), Some("truncated pack".to_string()));
+    assert!(read(&mut &b"SDKP\x01\x00\x09\x01s\x51\x00\xff\xff\xff\xff"[..]).is_err());
+    let unpacked = read(&mut &bytes[..]).unwrap();
+    assert_eq!((unpacked.variant, unpacked.side), (pack.variant, pack.side));
+    assert_eq!(unpacked.puzzles, pack.puzzles);
+    assert_eq!(unpacked.ratings, pack.ratings);
+}
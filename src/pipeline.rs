@@ -0,0 +1,133 @@
+// Solving on several threads: one thread reads the puzzles, the
+// others solve them, and the calling thread writes their solutions,
+// in the order of the puzzles unless asked otherwise.  The queue of
+// puzzles read but not taken yet is bounded, so huge inputs are never
+// read ahead, and so is the buffer of solutions waiting for those of
+// the puzzles before them: a puzzle slow to solve stops the reading
+// once the puzzles after it fill the buffer.
+
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+/// Number of puzzles queued for each solving thread.
+const QUEUED: usize = 64;
+/// Number of puzzles read past the first not written yet, for each
+/// solving thread, when the solutions are written in order.
+const REORDERED: usize = 256;
+
+/// Read puzzles with `read` until it returns None, solve them with
+/// `solve` on `threads` threads, given their position from 1 and
+/// their text, and pass what it returns for each puzzle to `write`,
+/// in order if `ordered`, or else as soon as it is solved.
+pub fn run<R, S, W, T>(threads: usize, ordered: bool, mut read: R, solve: &S, mut write: W)
+    where R: FnMut() -> Option<String> + Send, S: Fn(usize, &str) -> T + Sync, W: FnMut(T), T: Send
+{
+    let (puzzle_sender, puzzle_receiver) = mpsc::sync_channel(QUEUED * threads);
+    let puzzle_receiver = Mutex::new(puzzle_receiver);
+    let (solution_sender, solution_receiver) = mpsc::channel();
+    // The puzzles written so far, which the reader waits on.
+    let written = (Mutex::new(0), Condvar::new());
+    thread::scope(|scope| {
+        let written = &written;
+        scope.spawn(move || {
+            let mut index = 0;
+            while let Some(text) = read() {
+                if ordered {
+                    let mut count = written.0.lock().unwrap();
+                    while index >= *count + REORDERED * threads {
+                        count = written.1.wait(count).unwrap();
+                    }
+                }
+                if puzzle_sender.send((index, text)).is_err() {
+                    return;
+                }
+                index += 1;
+            }
+        });
+        for _ in 0 .. threads {
+            let (puzzle_receiver, solution_sender) = (&puzzle_receiver, solution_sender.clone());
+            scope.spawn(move || {
+                loop {
+                    // The lock is released as soon as a puzzle is taken.
+                    let taken = puzzle_receiver.lock().unwrap().recv();
+                    let (index, text) = match taken {
+                        Ok(puzzle) => puzzle,
+                        Err(_) => { return; }
+                    };
+                    let solution = solve(index + 1, &text);
+                    if solution_sender.send((index, solution)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(solution_sender);
+
+        if !ordered {
+            for (_, solution) in solution_receiver.iter() {
+                write(solution);
+            }
+            return;
+        }
+        // The threads finish their puzzles out of order.
+        let mut finished = BTreeMap::new();
+        let mut next = 0;
+        for (index, solution) in solution_receiver.iter() {
+            finished.insert(index, solution);
+            if !finished.contains_key(&next) {
+                continue;
+            }
+            while let Some(solution) = finished.remove(&next) {
+                write(solution);
+                next += 1;
+            }
+            *written.0.lock().unwrap() = next;
+            written.1.notify_one();
+        }
+    });
+}
+
+#[test]
+fn test_run() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    let mut puzzles = (0 .. 100).map(|i| i.to_string());
+    let mut written = Vec::new();
+    // Earlier puzzles take longer, so they finish last.
+    run(4, true, || puzzles.next(), &|id: usize, text: &str| {
+        let i: u64 = text.parse().unwrap();
+        assert_eq!(id as u64, i + 1);
+        thread::sleep(Duration::from_micros(100 - i));
+        format!("{}!", i)
+    }, |line| written.push(line));
+    assert_eq!(written, (0 .. 100).map(|i| format!("{}!", i)).collect::<Vec<_>>());
+
+    // A slow first puzzle holds back the reading, in order only.
+    let count = 4 * REORDERED;
+    for &ordered in [true, false].iter() {
+        let read = AtomicUsize::new(0);
+        let mut puzzles = (0 .. count).map(|i| i.to_string());
+        let mut written = Vec::new();
+        run(2, ordered, || {
+            read.fetch_add(1, Ordering::SeqCst);
+            puzzles.next()
+        }, &|id: usize, _: &str| {
+            if id == 1 {
+                thread::sleep(Duration::from_millis(50));
+            }
+            id
+        }, |id| {
+            if ordered {
+                assert!(read.load(Ordering::SeqCst) <= written.len() + 2 * REORDERED + 1);
+            }
+            written.push(id);
+        });
+        assert_eq!(written.len(), count);
+        assert_eq!(written[0] == 1, ordered);
+        written.sort();
+        assert_eq!(written, (1 ..= count).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,363 @@
+// The `play` command: a puzzle played in the terminal.  The arrows
+// move the selected cell, the digits fill it in, or toggle its pencil
+// marks in mark mode, and backspace, delete or 0 erase it; the givens
+// are drawn in bold and cannot be changed, the cells of the mistakes
+// are drawn in red, unless the player checks their own work, and the
+// game is over once the puzzle is solved, with a summary of the time
+// taken, the mistakes made and the score.  The digits beyond 9 are
+// the capital letters, so that the commands can be lowercase letters
+// on any grid, and the keys and colors can be changed in the config
+// file, see `config`.  The hint key shows the next step of a human solver a
+// little more at each press: the cells it looks at, then the technique
+// it takes, then the digit it finds, filled in.  A click on a cell
+// selects it, and a click on the selected cell toggles the mark drawn
+// where it is clicked.
+
+use std::io::{self, Write};
+use std::mem;
+use std::time::{Duration, Instant};
+
+use super::{num_to_char, Shape};
+use check::Conflict;
+use config::{Config, Edge};
+use game::Game;
+use logic::Hint;
+use terminal::{self, Key, RawMode, CLEAR, RESET};
+
+/// What a key or a click does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Fill in the digit, counting from 1, or toggle its mark.
+    Digit(usize),
+    Erase,
+    /// Switch between filling in digits and marking them.
+    Marks,
+    /// Mark every empty cell.
+    AllMarks,
+    Undo,
+    Redo,
+    Hint,
+    Quit,
+    /// Select `cell`, or toggle the mark of `digit` if it is selected.
+    Click { cell: usize, digit: Option<usize> },
+}
+
+/// What the player sees of the game besides the grid.
+struct View {
+    /// The selected cell.
+    cursor: usize,
+    /// Whether the digits toggle pencil marks instead of filling in.
+    marking: bool,
+    /// Whether the mistakes are shown as they are made.
+    checking: bool,
+    /// The time played.
+    elapsed: Duration,
+    /// The hint asked for.
+    hinting: Hinting,
+}
+
+/// How much of a hint is shown: each press of the hint key shows
+/// more, until the digit is filled in.
+enum Hinting {
+    Off,
+    /// The cells of the step are highlighted.
+    Cells(Hint),
+    /// The technique is named too.
+    Technique(Hint),
+    /// No hint could be found.
+    Stuck,
+}
+
+/// Return `elapsed` as minutes and seconds, e.g., "12:05".
+pub fn clock(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    return format!("{}:{:02}", seconds / 60, seconds % 60);
+}
+
+/// Apply `action` to `game` and `view`.
+fn handle(game: &mut Game, view: &mut View, action: Action) {
+    let side = game.shape().side();
+    let (row, col) = (view.cursor / side, view.cursor % side);
+    match action {
+        // The hint shown no longer holds once the grid changes.
+        Action::Up | Action::Down | Action::Left | Action::Right | Action::Hint => {}
+        Action::Click { cell, .. } if cell != view.cursor => {}
+        _ => { view.hinting = Hinting::Off; }
+    }
+    match action {
+        Action::Up if row > 0 => { view.cursor -= side; }
+        Action::Down if row + 1 < side => { view.cursor += side; }
+        Action::Left if col > 0 => { view.cursor -= 1; }
+        Action::Right if col + 1 < side => { view.cursor += 1; }
+        Action::Erase => { game.erase(view.cursor); }
+        Action::Marks => { view.marking = !view.marking; }
+        Action::AllMarks => { game.fill_marks(); }
+        Action::Undo => { game.undo(); }
+        Action::Redo => { game.redo(); }
+        Action::Hint => {
+            view.hinting = match mem::replace(&mut view.hinting, Hinting::Off) {
+                Hinting::Cells(hint) => Hinting::Technique(hint),
+                Hinting::Technique(hint) => {
+                    let (cell, digit) = hint.placements[0];
+                    game.place(cell, digit);
+                    view.cursor = cell;
+                    Hinting::Off
+                }
+                Hinting::Off | Hinting::Stuck => match game.hint() {
+                    Some(hint) => Hinting::Cells(hint),
+                    None => Hinting::Stuck,
+                },
+            };
+        }
+        Action::Click { cell, .. } if cell != view.cursor => { view.cursor = cell; }
+        Action::Click { cell, digit: Some(digit) } => { game.toggle_mark(cell, digit); }
+        Action::Digit(digit) if view.marking => { game.toggle_mark(view.cursor, digit); }
+        Action::Digit(digit) => { game.place(view.cursor, digit); }
+        _ => {}
+    }
+}
+
+/// Return the cell drawn on the line `row` and column `col` of the
+/// screen, counting from 0, and the digit of the mark drawn there, if
+/// any; None off the cells.
+fn locate(shape: Shape, row: usize, col: usize) -> Option<(usize, Option<usize>)> {
+    // A band of boxes is a border and the lines of its cells, and a
+    // stack of boxes a line and its cells, each a space, then a mark
+    // and a space for each column of a box.
+    let (band, line) = (row / (1 + shape.box_rows * shape.box_rows), row % (1 + shape.box_rows * shape.box_rows));
+    let width = 2 * shape.box_cols + 1;
+    let (stack, place) = (col / (1 + shape.box_cols * width), col % (1 + shape.box_cols * width));
+    if line == 0 || place == 0 {
+        return None;
+    }
+    let cell_row = band * shape.box_rows + (line - 1) / shape.box_rows;
+    let cell_col = stack * shape.box_cols + (place - 1) / width;
+    if cell_row >= shape.side() || cell_col >= shape.side() {
+        return None;
+    }
+    let (mark_row, mark_col) = ((line - 1) % shape.box_rows, (place - 1) % width);
+    let digit = if mark_col == 0 { None } else { Some(shape.box_cols * mark_row + (mark_col - 1) / 2 + 1) };
+    return Some((shape.side() * cell_row + cell_col, digit));
+}
+
+/// Return the screen of `game` seen through `view`, for a terminal in
+/// raw mode, in the colors and with the keys of `config`.  Every cell
+/// is a small grid of the shape of the boxes, with each mark at the
+/// place of its digit in the box, and a digit in the middle.
+fn draw(game: &Game, view: &View, config: &Config) -> String {
+    let shape = game.shape();
+    let side = shape.side();
+    let mut mistakes = vec![false; side * side];
+    for conflict in if view.checking { game.conflicts() } else { Vec::new() } {
+        match conflict {
+            Conflict::Duplicate { first, second, .. } => {
+                mistakes[first] = true;
+                mistakes[second] = true;
+            }
+            Conflict::Wrong { cell, .. } | Conflict::Excluded { cell, .. } => { mistakes[cell] = true; }
+        }
+    }
+    let hinted = match view.hinting {
+        Hinting::Cells(ref hint) | Hinting::Technique(ref hint) => hint.cells.clone(),
+        Hinting::Off | Hinting::Stuck => Vec::new(),
+    };
+    let theme = &config.theme;
+    let border = |edge| theme.lines.border(edge, (2 * shape.box_cols + 1) * shape.box_cols, side / shape.box_cols);
+
+    let mut screen = String::from(CLEAR);
+    for row in 0 .. side {
+        if row % shape.box_rows == 0 {
+            screen.push_str(&border(if row == 0 { Edge::Top } else { Edge::Middle }));
+            screen.push_str("\r\n");
+        }
+        for line in 0 .. shape.box_rows {
+            for col in 0 .. side {
+                let cell = side * row + col;
+                if col % shape.box_cols == 0 {
+                    screen.push(theme.lines.vertical);
+                }
+                let text: String = (0 .. shape.box_cols).map(|place| {
+                    let mark = shape.box_cols * line + place;
+                    let c = match game.digit(cell) {
+                        Some(d) if line == shape.box_rows / 2 && place == shape.box_cols / 2 => num_to_char(d as u32),
+                        None if game.marks(cell) & (1 << mark) != 0 => num_to_char(mark as u32 + 1),
+                        _ => ' ',
+                    };
+                    return format!("{} ", c);
+                }).collect();
+                if game.given(cell) {
+                    screen.push_str(&theme.given);
+                }
+                if mistakes[cell] {
+                    screen.push_str(&theme.mistake);
+                }
+                if hinted.contains(&cell) {
+                    screen.push_str(&theme.hint);
+                }
+                if cell == view.cursor {
+                    screen.push_str(&theme.cursor);
+                }
+                screen.push_str(&format!(" {}{}", text, RESET));
+            }
+            screen.push(theme.lines.vertical);
+            screen.push_str("\r\n");
+        }
+    }
+    screen.push_str(&border(Edge::Bottom));
+    screen.push_str("\r\n\r\n");
+    if game.complete() {
+        screen.push_str(&format!("Solved in {} with {} mistake{}: {} points.  Press any key.",
+                                 clock(view.elapsed), game.mistakes(), if game.mistakes() == 1 { "" } else { "s" },
+                                 game.score(view.elapsed)));
+    } else {
+        screen.push_str(&format!("Time: {}  ", clock(view.elapsed)));
+        if view.checking {
+            screen.push_str(&format!("Mistakes: {}  ", game.mistakes()));
+        }
+        screen.push_str(if view.marking { "Mode: marks" } else { "Mode: digits" });
+        screen.push_str("\r\n");
+        let again = config.keys.first(Action::Hint);
+        match view.hinting {
+            Hinting::Off => {}
+            Hinting::Cells(_) => {
+                screen.push_str(&format!("Hint: look at the cells highlighted; {} again for the technique\r\n", again));
+            }
+            Hinting::Technique(ref hint) => {
+                screen.push_str(&format!("Hint: {} in the cells highlighted; {} again to fill in the digit\r\n",
+                                         hint.technique.name(), again));
+            }
+            Hinting::Stuck => { screen.push_str("No hint: a digit is wrong, or no technique applies\r\n"); }
+        }
+        screen.push_str(&config.keys.help());
+    }
+    screen.push_str("\r\n");
+    return screen;
+}
+
+/// Play `game`, already played for `elapsed`, in the terminal until it
+/// is solved or the player quits, showing the mistakes as they are
+/// made if `checking`, with the keys and colors of `config`.  Return
+/// the time it has been played.
+pub fn run(game: &mut Game, elapsed: Duration, checking: bool, config: &Config) -> io::Result<Duration> {
+    let start = Instant::now();
+    let _raw = RawMode::enter()?;
+    let mut input = terminal::input();
+    let mut out = io::stdout();
+    let mut view = View { cursor: 0, marking: false, checking, elapsed, hinting: Hinting::Off };
+    loop {
+        // The clock stops once the puzzle is solved.
+        if !game.complete() {
+            view.elapsed = elapsed + start.elapsed();
+        }
+        write!(out, "{}", draw(game, &view, config))?;
+        out.flush()?;
+        match terminal::read_key(&mut input) {
+            // The clock ticks.
+            None => {}
+            Some(_) if game.complete() => { break; }
+            Some(Key::Interrupt) => { break; }
+            Some(Key::Click { row, col }) => {
+                if let Some((cell, digit)) = locate(game.shape(), row, col) {
+                    handle(game, &mut view, Action::Click { cell, digit });
+                }
+            }
+            Some(key) => match config.keys.action(key) {
+                Some(Action::Quit) => { break; }
+                Some(action) => { handle(game, &mut view, action); }
+                None => {}
+            },
+        }
+    }
+    return Ok(view.elapsed);
+}
+
+#[test]
+fn test_handle() {
+    use super::{standard_rules, SudokuBoard, SOLUTION};
+    use config;
+
+    let rules = standard_rules();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules));
+    let config = config::from_text("").unwrap();
+    let (cursor, hint) = (&config.theme.cursor, &config.theme.hint);
+    let mut view = View { cursor: 0, marking: false, checking: true, elapsed: Duration::from_secs(65),
+                          hinting: Hinting::Off };
+    for &action in [Action::Up, Action::Left, Action::Marks, Action::Digit(4), Action::Marks, Action::Digit(4)].iter() {
+        handle(&mut game, &mut view, action);
+    }
+    assert_eq!((view.cursor, game.digit(0), game.marks(0)), (0, Some(4), 0));
+    assert!(draw(&game, &view, &config).contains("Time: 1:05  Mistakes: 0  Mode: digits"));
+    for &action in [Action::Right, Action::Marks, Action::Digit(8), Action::Digit(2)].iter() {
+        handle(&mut game, &mut view, action);
+    }
+    assert_eq!((view.cursor, game.marks(1)), (1, 0b1000_0010));
+    // The marks of r1c2: 2 on the first line of its cell, 8 on the last.
+    let screen = draw(&game, &view, &config);
+    let lines: Vec<&str> = screen.lines().collect();
+    assert!(lines[0].ends_with(&format!("+{}+{}+{}+", "-".repeat(21), "-".repeat(21), "-".repeat(21))));
+    assert!(lines[1].contains(&format!("{}   2   {}", cursor, RESET)));
+    assert!(lines[3].contains(&format!("{}   8   {}", cursor, RESET)));
+    for &action in [Action::Marks, Action::Digit(8)].iter() {
+        handle(&mut game, &mut view, action);
+    }
+    assert!(game.complete() && draw(&game, &view, &config).contains("Solved in 1:05 with 0 mistakes: 935 points."));
+    for &action in [Action::Undo, Action::Undo, Action::Redo].iter() {
+        handle(&mut game, &mut view, action);
+    }
+    assert_eq!((game.digit(1), game.marks(1)), (None, 0b1000_0010));
+    handle(&mut game, &mut view, Action::Hint);
+    assert!(draw(&game, &view, &config).contains(&format!("{}{}   2   {}", hint, cursor, RESET)));
+    handle(&mut game, &mut view, Action::Hint);
+    assert!(draw(&game, &view, &config).contains("Hint: singles in the cells highlighted; h again"));
+    handle(&mut game, &mut view, Action::Hint);
+    assert!(game.complete() && game.digit(1) == Some(8));
+
+    let config = config::from_text("[grid]\nlines = \"heavy\"\n").unwrap();
+    let screen = draw(&game, &view, &config);
+    let lines: Vec<&str> = screen.lines().collect();
+    assert!(lines[0].ends_with("━┓") && lines[1].starts_with('┃') && lines[10].starts_with('┣'));
+    assert!(lines[30].starts_with('┗'));
+}
+
+#[test]
+fn test_locate() {
+    use super::{Rules, SudokuBoard, Variant, SOLUTION};
+    use config;
+
+    // On a 9x9 grid, every tenth line is a border, and each cell is 7
+    // columns wide, after the line of its box.
+    let shape = Shape::STANDARD;
+    assert_eq!((locate(shape, 0, 3), locate(shape, 10, 3), locate(shape, 1, 0)), (None, None, None));
+    assert_eq!((locate(shape, 1, 1), locate(shape, 1, 2), locate(shape, 1, 3)),
+               (Some((0, None)), Some((0, Some(1))), Some((0, Some(1)))));
+    assert_eq!((locate(shape, 3, 13), locate(shape, 11, 24)), (Some((1, Some(9))), Some((30, Some(1)))));
+    assert_eq!((locate(shape, 29, 10), locate(shape, 1, 65)), (Some((73, Some(7))), Some((8, Some(3)))));
+    assert_eq!((locate(shape, 30, 10), locate(shape, 1, 66), locate(shape, 1, 67)), (None, None, None));
+
+    let rules = Rules::for_puzzle(shape, Variant::Standard, &[], &[]).unwrap();
+    let solution = SOLUTION;
+    let mut game = Game::new(SudokuBoard::from_str(&format!("..{}", &solution[2 ..]), &rules));
+    let mut view = View { cursor: 0, marking: false, checking: true, elapsed: Duration::ZERO, hinting: Hinting::Off };
+    // The first click selects, the next ones toggle.
+    handle(&mut game, &mut view, Action::Click { cell: 1, digit: Some(8) });
+    assert_eq!((view.cursor, game.marks(1)), (1, 0));
+    handle(&mut game, &mut view, Action::Click { cell: 1, digit: Some(8) });
+    handle(&mut game, &mut view, Action::Click { cell: 1, digit: None });
+    assert_eq!(game.marks(1), 0b1000_0000);
+    // The 8 is drawn where it is clicked.
+    let screen = draw(&game, &view, &config::from_text("").unwrap());
+    let (mut line, mut escape) = (String::new(), false);
+    for c in screen.lines().nth(3).unwrap().chars() {
+        match c {
+            '\x1b' => { escape = true; }
+            _ if escape => { escape = c != 'm'; }
+            _ => { line.push(c); }
+        }
+    }
+    assert_eq!(locate(shape, 3, line.find('8').unwrap()), Some((1, Some(8))));
+}
@@ -0,0 +1,73 @@
+// The digits that can still go in a cell, for assistants that show
+// them whenever a cell is picked: those left by the constraints, by
+// the techniques of a human solver as well, or only those of the
+// solutions of the puzzle, which no technique can do better than.
+
+use super::SudokuBoard;
+use logic::{self, Technique};
+
+/// How far the digits of a cell are narrowed down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reach {
+    /// By the constraints, until they remove nothing more.
+    Propagation,
+    /// By every technique of the logic solver.
+    Techniques,
+    /// To the digits of the cell in the solutions.
+    Solutions,
+}
+
+impl Reach {
+    /// Return the reach named `name`, e.g., "solutions", if any.
+    pub fn from_name(name: &str) -> Option<Reach> {
+        return match name {
+            "propagation" => Some(Reach::Propagation),
+            "techniques" => Some(Reach::Techniques),
+            "solutions" => Some(Reach::Solutions),
+            _ => None,
+        };
+    }
+}
+
+/// Return the digits, counting from 1, that can still go in the cell
+/// of `board` at `row` and `col`, counting from 0, narrowed down as
+/// far as `reach`; none if the board is found to have no solution.
+pub fn possible_digits(board: &SudokuBoard, row: usize, col: usize, reach: Reach) -> Vec<usize> {
+    let side = board.rules.shape.side();
+    let cell = side * row + col;
+    let board = match reach {
+        Reach::Techniques => logic::solve(board, &Technique::ALL).0,
+        _ => {
+            let mut board = board.clone();
+            board.propagate();
+            board
+        }
+    };
+    if !board.solvable() {
+        return Vec::new();
+    }
+    return (0 .. side)
+        .filter(|&d| board.cells[cell] & (1 << d) != 0)
+        .filter(|&d| reach != Reach::Solutions || {
+            let mut guess = board.clone();
+            guess.cells[cell] = 1 << d;
+            guess.count_solutions(1) > 0
+        })
+        .map(|d| d + 1)
+        .collect();
+}
+
+#[test]
+fn test_possible_digits() {
+    use super::{standard_rules, INKALA};
+
+    let rules = standard_rules();
+    // Arto Inkala's puzzle: a search finds the 1 of r1c2.
+    let board = SudokuBoard::from_str(INKALA, &rules);
+    assert_eq!(possible_digits(&board, 0, 1, Reach::Propagation), vec![1, 2, 4, 6]);
+    let techniques = possible_digits(&board, 0, 1, Reach::Techniques);
+    assert!(techniques.contains(&1) && techniques.iter().all(|d| [1, 2, 4, 6].contains(d)));
+    assert_eq!(possible_digits(&board, 0, 1, Reach::Solutions), vec![1]);
+    let board = SudokuBoard::from_str("11...............................................................................", &rules);
+    assert!(possible_digits(&board, 4, 4, Reach::Propagation).is_empty());
+}
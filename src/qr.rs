@@ -0,0 +1,396 @@
+// QR codes of puzzles, so that they can be sent to a phone from the
+// terminal.  The text is encoded in byte mode at the medium level of
+// error correction, in the smallest of versions 1 to 10 that holds
+// it, with the mask of lowest penalty (ISO/IEC 18004).
+
+use std::iter;
+use std::ops::Range;
+
+/// The codewords of each version at the medium level: the error
+/// correction codewords of a block, and the number of blocks.
+const BLOCKS: [(usize, usize); 10] = [
+    (10, 1), (16, 1), (26, 1), (18, 2), (24, 2), (16, 4), (18, 4), (22, 4), (22, 5), (26, 5),
+];
+
+/// The total number of codewords of each version.
+const CODEWORDS: [usize; 10] = [26, 44, 70, 100, 134, 172, 196, 242, 292, 346];
+
+/// The second coordinate of the alignment patterns of each version,
+/// after the first, 6.
+const ALIGNMENT: [&[usize]; 10] = [
+    &[], &[18], &[22], &[26], &[30], &[34], &[22, 38], &[24, 42], &[26, 46], &[28, 50],
+];
+
+/// Width of the light border around a code.
+const QUIET: usize = 4;
+
+/// Return the product of `x` and `y` in the field of the codewords.
+fn multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0 .. 8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    return z as u8;
+}
+
+/// Return the `n` error correction codewords of `data`.
+fn correction(data: &[u8], n: usize) -> Vec<u8> {
+    // The generator polynomial, without its leading coefficient.
+    let mut divisor = vec![0; n];
+    divisor[n - 1] = 1;
+    let mut root = 1;
+    for _ in 0 .. n {
+        for j in 0 .. n {
+            divisor[j] = multiply(divisor[j], root);
+            if j + 1 < n {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = multiply(root, 2);
+    }
+    let mut remainder = vec![0; n];
+    for &byte in data.iter() {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (r, &d) in remainder.iter_mut().zip(divisor.iter()) {
+            *r ^= multiply(d, factor);
+        }
+    }
+    return remainder;
+}
+
+/// Return the 15 bits of format information for `mask`.
+fn format_bits(mask: usize) -> u32 {
+    // The medium level of error correction is 0.
+    let data = mask as u32;
+    let mut rem = data;
+    for _ in 0 .. 10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    return (data << 10 | rem) ^ 0x5412;
+}
+
+/// Return the codewords of `text` in `version`, interleaved with
+/// those of error correction, or None if the text does not fit.
+fn codewords(text: &[u8], version: usize) -> Option<Vec<u8>> {
+    let (ecc, blocks) = BLOCKS[version - 1];
+    let total = CODEWORDS[version - 1];
+    let capacity = total - ecc * blocks;
+    let count_bits = if version < 10 { 8 } else { 16 };
+    if 4 + count_bits + 8 * text.len() > 8 * capacity {
+        return None;
+    }
+    let mut bits = Vec::new();
+    let mut push = |value: usize, n: usize| {
+        for i in (0 .. n).rev() {
+            bits.push((value >> i) & 1 == 1);
+        }
+    };
+    push(0b0100, 4);
+    push(text.len(), count_bits);
+    for &byte in text.iter() {
+        push(byte as usize, 8);
+    }
+    // The terminator, then the bits completing the last codeword.
+    let terminator = (8 * capacity - bits.len()).min(4);
+    bits.extend(iter::repeat_n(false, terminator));
+    while bits.len() % 8 != 0 {
+        bits.push(false);
+    }
+    let mut data: Vec<u8> = bits.chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].iter().cycle() {
+        if data.len() == capacity {
+            break;
+        }
+        data.push(*pad);
+    }
+
+    // The last blocks take a data codeword more than the first ones.
+    let short = total / blocks - ecc;
+    let long_blocks = total % blocks;
+    let mut split = Vec::new();
+    let mut start = 0;
+    for i in 0 .. blocks {
+        let len = short + (i >= blocks - long_blocks) as usize;
+        split.push(&data[start .. start + len]);
+        start += len;
+    }
+    let corrections: Vec<Vec<u8>> = split.iter().map(|block| correction(block, ecc)).collect();
+    let mut result = Vec::with_capacity(total);
+    for i in 0 .. short + 1 {
+        result.extend(split.iter().filter_map(|block| block.get(i)));
+    }
+    for i in 0 .. ecc {
+        result.extend(corrections.iter().map(|block| block[i]));
+    }
+    return Some(result);
+}
+
+/// A QR code: its dark modules, and the modules of its patterns,
+/// which hold no data.
+struct Code {
+    size: usize,
+    dark: Vec<Vec<bool>>,
+    function: Vec<Vec<bool>>,
+}
+
+impl Code {
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        self.dark[y][x] = dark;
+        self.function[y][x] = true;
+    }
+
+    /// Draw the timing, finder and alignment patterns, and the
+    /// version information.
+    fn draw_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for i in 0 .. size {
+            self.set(6, i, i % 2 == 0);
+            self.set(i, 6, i % 2 == 0);
+        }
+        for &(cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)].iter() {
+            for dy in -4i32 ..= 4 {
+                for dx in -4i32 ..= 4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    let distance = dx.abs().max(dy.abs());
+                    if (0 .. size as i32).contains(&x) && (0 .. size as i32).contains(&y) {
+                        self.set(x as usize, y as usize, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+        let positions: Vec<usize> = if version == 1 {
+            vec![]
+        } else {
+            Some(6).into_iter().chain(ALIGNMENT[version - 1].iter().cloned()).collect()
+        };
+        let last = positions.len().saturating_sub(1);
+        for (i, &cx) in positions.iter().enumerate() {
+            for (j, &cy) in positions.iter().enumerate() {
+                // The corners of the finder patterns.
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                for dy in 0 .. 5 {
+                    for dx in 0 .. 5 {
+                        let distance = (dx as i32 - 2).abs().max((dy as i32 - 2).abs());
+                        self.set(cx + dx - 2, cy + dy - 2, distance != 1);
+                    }
+                }
+            }
+        }
+        // Reserve the format information; it is drawn with the mask.
+        self.draw_format(0);
+        if version >= 7 {
+            let mut rem = version as u32;
+            for _ in 0 .. 12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+            }
+            let bits = (version as u32) << 12 | rem;
+            for i in 0 .. 18 {
+                let dark = (bits >> i) & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set(a, b, dark);
+                self.set(b, a, dark);
+            }
+        }
+    }
+
+    /// Draw both copies of the format information for `mask`.
+    fn draw_format(&mut self, mask: usize) {
+        let size = self.size;
+        let bits = format_bits(mask);
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+        for i in 0 .. 6 {
+            self.set(8, i, bit(i));
+        }
+        self.set(8, 7, bit(6));
+        self.set(8, 8, bit(7));
+        self.set(7, 8, bit(8));
+        for i in 9 .. 15 {
+            self.set(14 - i, 8, bit(i));
+        }
+        for i in 0 .. 8 {
+            self.set(size - 1 - i, 8, bit(i));
+        }
+        for i in 8 .. 15 {
+            self.set(8, size - 15 + i, bit(i));
+        }
+        self.set(8, size - 8, true);
+    }
+
+    /// Place `data` in the modules outside the patterns, in the
+    /// zigzag of columns pairs from the bottom right corner.
+    fn draw_data(&mut self, data: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        loop {
+            // The vertical timing pattern takes a column of its own.
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0 .. size {
+                let y = if upward { size - 1 - vertical } else { vertical };
+                for x in [right, right - 1].iter().cloned() {
+                    if !self.function[y][x] && i < 8 * data.len() {
+                        self.dark[y][x] = (data[i / 8] >> (7 - i % 8)) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 3 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Invert the data modules selected by `mask`; applying the same
+    /// mask again removes it.
+    fn apply_mask(&mut self, mask: usize) {
+        for y in 0 .. self.size {
+            for x in 0 .. self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !self.function[y][x] {
+                    self.dark[y][x] = !self.dark[y][x];
+                }
+            }
+        }
+    }
+
+    /// Return the penalty of the code, which readers find harder to
+    /// read the higher it is.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let module = |x: usize, y: usize, transposed: bool| {
+            if transposed { self.dark[x][y] } else { self.dark[y][x] }
+        };
+        let finder = [true, false, true, true, true, false, true];
+        let mut penalty = 0;
+        for &transposed in [false, true].iter() {
+            for y in 0 .. size {
+                let line: Vec<bool> = (0 .. size).map(|x| module(x, y, transposed)).collect();
+                // Runs of five modules or more of the same color.
+                let mut run = 1;
+                for x in 1 ..= size {
+                    if x < size && line[x] == line[x - 1] {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        penalty += run - 2;
+                    }
+                    run = 1;
+                }
+                // Patterns looking like a finder, with four light
+                // modules on a side; the border counts as light.
+                for x in 0 ..= size - finder.len() {
+                    if line[x .. x + finder.len()] != finder {
+                        continue;
+                    }
+                    let light = |range: Range<usize>| range.into_iter().all(|i| !line.get(i).cloned().unwrap_or(false));
+                    if light(x.saturating_sub(4) .. x) || light(x + 7 .. x + 11) {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        // Blocks of two by two modules of the same color.
+        for y in 1 .. size {
+            for x in 1 .. size {
+                let color = self.dark[y][x];
+                if self.dark[y - 1][x] == color && self.dark[y][x - 1] == color && self.dark[y - 1][x - 1] == color {
+                    penalty += 3;
+                }
+            }
+        }
+        // Every 5% of dark modules away from a half.
+        let total = size * size;
+        let dark = self.dark.iter().flatten().filter(|&&dark| dark).count();
+        penalty += 10 * ((20 * dark).abs_diff(10 * total) / total);
+        return penalty;
+    }
+}
+
+/// Return the QR code of `text`, dark modules as true, or None if it
+/// is too long.
+fn encode(text: &str) -> Option<Vec<Vec<bool>>> {
+    let (version, data) = (1 ..= BLOCKS.len())
+        .find_map(|version| codewords(text.as_bytes(), version).map(|data| (version, data)))?;
+    let size = 17 + 4 * version;
+    let mut code = Code { size, dark: vec![vec![false; size]; size], function: vec![vec![false; size]; size] };
+    code.draw_patterns(version);
+    code.draw_data(&data);
+    let mask = (0 .. 8).min_by_key(|&mask| {
+        code.apply_mask(mask);
+        code.draw_format(mask);
+        let penalty = code.penalty();
+        code.apply_mask(mask);
+        return penalty;
+    }).unwrap();
+    code.apply_mask(mask);
+    code.draw_format(mask);
+    return Some(code.dark);
+}
+
+/// Return the QR code of `text` drawn in text, two rows of modules
+/// to a line, for a terminal with a dark background: the light
+/// modules are drawn, with the border around the code.  Return None
+/// if the text is too long.
+pub fn render(text: &str) -> Option<String> {
+    let dark = encode(text)?;
+    let size = dark.len() + 2 * QUIET;
+    let light = |x: usize, y: usize| {
+        let inside = |i: usize| (QUIET .. QUIET + dark.len()).contains(&i);
+        return !(inside(x) && inside(y) && dark[y - QUIET][x - QUIET]);
+    };
+    let mut lines = Vec::new();
+    for y in (0 .. size).step_by(2) {
+        let line: String = (0 .. size).map(|x| {
+            match (light(x, y), y + 1 < size && light(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            }
+        }).collect();
+        lines.push(line);
+    }
+    return Some(lines.join("\n"));
+}
+
+#[test]
+fn test_correction() {
+    // The example of the standard, for version 1 at the medium level.
+    let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+    assert_eq!(correction(&data, 10), vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]);
+    assert_eq!(format_bits(0), 0b101010000010010);
+}
+
+#[test]
+fn test_render() {
+    use super::PUZZLE;
+
+    let puzzle = PUZZLE;
+    let code = encode(puzzle).unwrap();
+    // Version 5, with its finder patterns in the corners.
+    assert_eq!(code.len(), 37);
+    assert_eq!(code[0][.. 8], [true, true, true, true, true, true, true, false]);
+    assert_eq!(code[36][.. 8], [true, true, true, true, true, true, true, false]);
+    assert_eq!(render(puzzle).unwrap().lines().count(), (37 + 2 * QUIET).div_ceil(2));
+    assert!(render(&"x".repeat(300)).is_none());
+}
@@ -0,0 +1,119 @@
+// Binary constraints: relations between the digits of two cells.
+
+use super::CandidateSet;
+use constraint::Constraint;
+
+/// How the digits of two cells must relate to each other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Relation {
+    /// The digits are not consecutive.
+    NonConsecutive,
+    /// The first digit is smaller than the second.
+    #[cfg(feature = "variants")]
+    Less,
+    /// The digits add up to the given sum.
+    #[cfg(feature = "variants")]
+    Sum(u32),
+    /// The digits do not add up to the given sum.
+    #[cfg(feature = "variants")]
+    NotSum(u32),
+}
+
+impl Relation {
+    /// Whether the digits `x` and `y` of the first and second
+    /// cell satisfy the relation.
+    fn holds(self, x: u32, y: u32) -> bool {
+        match self {
+            Relation::NonConsecutive => x + 1 != y && y + 1 != x,
+            #[cfg(feature = "variants")]
+            Relation::Less => x < y,
+            #[cfg(feature = "variants")]
+            Relation::Sum(sum) => x + y == sum,
+            #[cfg(feature = "variants")]
+            Relation::NotSum(sum) => x + y != sum,
+        }
+    }
+}
+
+/// A link is a relation between the digits of two cells.
+pub struct Link {
+    pub first: usize,
+    pub second: usize,
+    pub relation: Relation,
+}
+
+impl Link {
+    pub fn new(first: usize, second: usize, relation: Relation) -> Self {
+        Link { first, second, relation }
+    }
+
+    /// Return the `targets` of one cell that satisfy the relation
+    /// with at least one of the `candidates` of the other cell;
+    /// `forward` tells whether `candidates` belong to the first cell.
+    fn supported(&self, candidates: CandidateSet, targets: CandidateSet, forward: bool) -> CandidateSet {
+        let digits = |set: CandidateSet| {
+            (1 ..= CandidateSet::BITS).filter(move |d| set & (1 << (d - 1)) != 0)
+        };
+        let mut set = 0;
+        for x in digits(candidates) {
+            for y in digits(targets) {
+                let holds = if forward {
+                    self.relation.holds(x, y)
+                } else {
+                    self.relation.holds(y, x)
+                };
+                if holds {
+                    set |= 1 << (y - 1);
+                }
+            }
+        }
+        return set;
+    }
+}
+
+impl Constraint for Link {
+    /// Remove from both cells the candidates that do not satisfy
+    /// the relation with any candidate of the other cell.
+    /// Return whether any candidate was removed.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        let second = self.supported(cells[self.first], cells[self.second], true);
+        let first = self.supported(second, cells[self.first], false);
+        let changed = first != cells[self.first] || second != cells[self.second];
+        cells[self.first] = first;
+        cells[self.second] = second;
+        return changed;
+    }
+}
+
+#[test]
+fn test_non_consecutive() {
+    let link = Link::new(0, 1, Relation::NonConsecutive);
+    let mut cells = vec![0b1_0000, 0b1_1111_1111];
+    assert!(link.prune(&mut cells));
+    assert_eq!(cells, vec![0b1_0000, 0b1_1101_0111]);
+    cells[1] = 0b1000;
+    assert!(link.prune(&mut cells));
+    assert_eq!(cells[0], 0);
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_less() {
+    let link = Link::new(0, 1, Relation::Less);
+    let mut cells = vec![0b1_1111_1111, 0b1_1111_1111];
+    assert!(link.prune(&mut cells));
+    assert_eq!(cells, vec![0b0_1111_1111, 0b1_1111_1110]);
+}
+
+#[cfg(feature = "variants")]
+#[test]
+fn test_sum() {
+    let link = Link::new(0, 1, Relation::Sum(5));
+    let mut cells = vec![0b1_1111_1111, 0b0_0000_0011];
+    assert!(link.prune(&mut cells));
+    assert_eq!(cells, vec![0b0_0000_1100, 0b0_0000_0011]);
+    let link = Link::new(0, 1, Relation::NotSum(5));
+    let mut cells = vec![0b1_1111_1111, 0b0_0000_0010];
+    assert!(link.prune(&mut cells));
+    assert_eq!(cells, vec![0b1_1111_1011, 0b0_0000_0010]);
+}
@@ -0,0 +1,72 @@
+// A small pseudo-random number generator, xorshift64*, so that
+// generated puzzles can be reproduced from their seed.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Scramble the seed (splitmix64) so that nearby seeds give
+        // unrelated streams and the state is never zero.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        return Rng { state: if z == 0 { 1 } else { z } };
+    }
+
+    /// Return the generator of the item `index` of a run seeded with
+    /// `seed`, so that every item can be reproduced on its own.
+    #[cfg(feature = "generate")]
+    pub fn derive(seed: u64, index: u64) -> Self {
+        return Rng::new(seed ^ Rng::new(index).next_u64());
+    }
+
+    /// Return a seed taken from the clock, for runs without one.
+    pub fn clock_seed() -> u64 {
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        return elapsed.as_secs() ^ ((elapsed.subsec_nanos() as u64) << 32);
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        return self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    /// Return a number in 0 .. n.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Put `items` in a random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1 .. items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[test]
+fn test_shuffle() {
+    let mut items: Vec<usize> = (0 .. 10).collect();
+    Rng::new(42).shuffle(&mut items);
+    let mut again: Vec<usize> = (0 .. 10).collect();
+    Rng::new(42).shuffle(&mut again);
+    assert_eq!(items, again);
+    items.sort();
+    assert_eq!(items, (0 .. 10).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "generate")]
+#[test]
+fn test_derive() {
+    assert_eq!(Rng::derive(42, 7).next_u64(), Rng::derive(42, 7).next_u64());
+    assert!(Rng::derive(42, 7).next_u64() != Rng::derive(42, 8).next_u64());
+    assert!(Rng::derive(42, 7).next_u64() != Rng::derive(43, 7).next_u64());
+}
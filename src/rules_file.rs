@@ -0,0 +1,102 @@
+// Rule sets read from a JSON file, so that new combinations of rules
+// can be solved without recompiling, e.g.,
+//
+//     {"extras": ["anti-knight"],
+//      "units": [["r1c1", "r2c2", "r3c3", "r4c4", "r5c5", "r6c6", "r7c7", "r8c8", "r9c9"]],
+//      "cages": [{"cells": ["r1c1", "r1c2"], "sum": 3}],
+//      "parity": "E.O......",
+//      "thermometers": [["r9c1", "r8c1", "r7c1"]]}
+//
+// The parity mask has one character per cell: E for an even digit,
+// O for an odd one and a dot for either.  A rule set also takes the
+// clues of JSON puzzles.
+
+use std::fs;
+
+use super::{error, CandidateSet, Extra, Rules, FULL_SET, NSQ};
+use constraint::Restriction;
+use json::{self, Json};
+use json_puzzle::{cells, clues, list};
+use killer::Cage;
+
+/// The even digits.
+const EVEN: CandidateSet = 0b0_1010_1010;
+
+/// Read the rule set in the file at `path`.
+pub fn read(path: &str) -> Json {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| error(format!("cannot read {}: {}", path, e)));
+    return json::parse(&text).unwrap_or_else(|e| error(e));
+}
+
+/// Return the extra rules named by a rule set.
+pub fn extras(value: &Json) -> Vec<Extra> {
+    list(value, "extras").unwrap_or_else(|e| error(e)).iter()
+        .map(|name| name.as_str().and_then(Extra::from_name)
+             .unwrap_or_else(|| error(format!("unknown extra rule {:?}", name))))
+        .collect()
+}
+
+/// Add to `rules` the units, cages, parity and clues of a rule
+/// set; its extras must be given when the rules are created.
+pub fn apply(value: &Json, rules: &mut Rules) {
+    let mut units: Vec<Vec<usize>> = list(value, "units").unwrap_or_else(|e| error(e)).iter()
+        .map(|unit| cells(unit, "unit").unwrap_or_else(|e| error(e)))
+        .collect();
+    let mut cages = Vec::new();
+    for cage in list(value, "cages").unwrap_or_else(|e| error(e)) {
+        let cage_cells = cage.get("cells")
+            .unwrap_or_else(|| error(format!("missing cells in cage {:?}", cage)));
+        let sum = cage.get("sum").and_then(Json::as_u32)
+            .unwrap_or_else(|| error(format!("missing sum in cage {:?}", cage)));
+        cages.push(Cage::new(cells(cage_cells, "cage").unwrap_or_else(|e| error(e)), sum).unwrap_or_else(|e| error(e)));
+    }
+    // The digits of a cage never repeat.
+    units.extend(cages.iter().map(|cage| cage.cells.clone()));
+    rules.add_units(units);
+    rules.extend(cages);
+
+    if let Some(mask) = value.get("parity") {
+        let mask = mask.as_str()
+            .unwrap_or_else(|| error("the parity mask must be a string".to_string()));
+        if mask.chars().count() != NSQ {
+            error(format!("invalid parity mask length; expected {}, got {}",
+                          NSQ, mask.chars().count()));
+        }
+        let (mut even, mut odd) = (Vec::new(), Vec::new());
+        for (cell, c) in mask.chars().enumerate() {
+            match c {
+                'E' | 'e' => { even.push(cell); }
+                'O' | 'o' => { odd.push(cell); }
+                '.' => {}
+                _ => { error(format!("invalid parity {:?} in mask", c)); }
+            }
+        }
+        rules.extend(vec![
+            Restriction { cells: even, digits: EVEN },
+            Restriction { cells: odd, digits: FULL_SET & !EVEN },
+        ]);
+    }
+
+    rules.add_clues(clues(value).unwrap_or_else(|e| error(e)));
+}
+
+#[test]
+fn test_apply() {
+    use super::{satisfies, Shape, SudokuBoard, Variant};
+
+    let value = json::parse(&format!(r#"{{
+        "extras": ["anti-king"],
+        "units": [["r1c1", "r2c2", "r3c3", "r4c4", "r5c5", "r6c6", "r7c7", "r8c8", "r9c9"]],
+        "cages": [{{"cells": ["r1c1", "r1c2"], "sum": 3}}],
+        "parity": "..OE{}"}}"#, ".".repeat(NSQ - 4))).unwrap();
+    let mut rules = Rules::for_puzzle(Shape::STANDARD, Variant::Standard, &extras(&value), &[]).unwrap();
+    apply(&value, &mut rules);
+    // The diagonal leaves the box of r1c1 at r4c4.
+    assert_eq!(rules.neighbors[0].len(), 20 + 6);
+    let empty: String = ".".repeat(NSQ);
+    let solution = SudokuBoard::from_str(&empty, &rules).solve().unwrap();
+    assert!(satisfies(&rules, &solution.cells));
+    assert_eq!(solution.cells[2] & EVEN, 0);
+    assert_eq!(solution.cells[3] & !EVEN, 0);
+}
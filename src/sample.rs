@@ -0,0 +1,67 @@
+// Sampling the solutions of puzzles with several of them, for
+// statistics over their completions.  The solver's random search
+// favors the solutions at the end of short branches; here every
+// guess is drawn in proportion to the number of solutions under
+// each candidate, which makes the draw uniform.  The solutions are
+// counted up to a cap, beyond which the draw is only approximately
+// uniform.
+
+use super::SudokuBoard;
+use rng::Rng;
+
+/// Most solutions counted under a candidate.
+const CAP: usize = 256;
+
+/// Return a solution of `board` drawn at random, every solution being
+/// about as likely, or None if it has none.
+pub fn sample<'a>(board: &SudokuBoard<'a>, rng: &mut Rng) -> Option<SudokuBoard<'a>> {
+    let mut board = board.clone();
+    loop {
+        board.propagate();
+        if board.solved() {
+            return Some(board);
+        }
+        if !board.solvable() {
+            return None;
+        }
+        let cell = board.most_promising()?;
+        let candidates = board.cells[cell];
+        let weights: Vec<(usize, usize)> = (0 .. board.rules.shape.side())
+            .filter(|d| candidates & (1 << d) != 0)
+            .map(|d| {
+                let mut guess = board.clone();
+                guess.cells[cell] = 1 << d;
+                return (d, guess.count_solutions(CAP));
+            })
+            .collect();
+        let total: usize = weights.iter().map(|&(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.below(total);
+        for &(d, weight) in weights.iter() {
+            if pick < weight {
+                board.cells[cell] = 1 << d;
+                break;
+            }
+            pick -= weight;
+        }
+    }
+}
+
+#[test]
+fn test_sample() {
+    use super::standard_rules;
+
+    // The 9 and 6 of r2c1 and r2c2 can be swapped with the 6 and 9 of
+    // r9c1 and r9c2: two solutions.
+    let rules = standard_rules();
+    let board = SudokuBoard::from_str("483921657..7345821251876493548132976729564138136798245372689514814253769..5417382", &rules);
+    assert_eq!(board.count_solutions(3), 2);
+    let mut rng = Rng::new(1);
+    let draws: Vec<String> = (0 .. 100).map(|_| sample(&board, &mut rng).unwrap().to_str()).collect();
+    let first = draws.iter().filter(|&draw| draw == &draws[0]).count();
+    assert!(first > 30 && first < 70);
+    let board = SudokuBoard::from_str("11...............................................................................", &rules);
+    assert!(sample(&board, &mut rng).is_none());
+}
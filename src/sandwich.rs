@@ -0,0 +1,116 @@
+// Sandwich clues: the sum of the digits between the 1 and the 9
+// of a row or column.
+
+use super::{CandidateSet, N};
+use constraint::Constraint;
+
+/// The crust of a sandwich: the smallest and largest digits.
+const ONE: CandidateSet = 1;
+const NINE: CandidateSet = 1 << (N - 1);
+
+/// A sandwich is a line of cells, a row or a column, where the
+/// digits between the 1 and the 9 add up to a given sum.
+pub struct Sandwich {
+    pub cells: Vec<usize>,
+    /// The sets of digits that could fill a sandwich, indexed by
+    /// the number of cells between the crusts.
+    fillings: Vec<Vec<CandidateSet>>,
+}
+
+impl Sandwich {
+    /// Return the sandwich of `cells` adding up to `sum`, or why
+    /// there is none.
+    pub fn new(cells: Vec<usize>, sum: u32) -> Result<Self, String> {
+        let mut fillings = vec![Vec::new(); N - 1];
+        let inner = !(ONE | NINE) & ((1 << N) - 1);
+        for set in (0 ..= inner).filter(|s| s & !inner == 0) {
+            let set_sum: u32 = (0 .. N as u32).filter(|d| set & (1 << d) != 0).map(|d| d + 1).sum();
+            if set_sum == sum {
+                fillings[set.count_ones() as usize].push(set);
+            }
+        }
+        if fillings.iter().all(|f| f.is_empty()) {
+            return Err(format!("no sandwich adds up to {}", sum));
+        }
+        return Ok(Sandwich { cells, fillings });
+    }
+}
+
+impl Constraint for Sandwich {
+    /// Remove the candidates of the line that do not appear in any
+    /// possible placement of the crusts and filling.  Placing the
+    /// crusts is possible if both ends have the right candidates,
+    /// every cell in between has a candidate in a possible filling,
+    /// and every other cell has a candidate other than 1 and 9.
+    /// Return whether any candidate was removed.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        let line: Vec<CandidateSet> = self.cells.iter().map(|&c| cells[c]).collect();
+        let mut allowed = vec![0; line.len()];
+        for lo in 0 .. line.len() {
+            for hi in lo + 1 .. line.len() {
+                let ends: Vec<(CandidateSet, CandidateSet)> = [(ONE, NINE), (NINE, ONE)].iter()
+                    .filter(|&&(a, b)| line[lo] & a != 0 && line[hi] & b != 0)
+                    .cloned()
+                    .collect();
+                let outside = (0 .. lo).chain(hi + 1 .. line.len());
+                if ends.is_empty() || outside.clone().any(|i| line[i] & !(ONE | NINE) == 0) {
+                    continue;
+                }
+
+                let between = &line[lo + 1 .. hi];
+                let mut filled = vec![0; between.len()];
+                let mut possible = false;
+                for &filling in self.fillings[between.len()].iter() {
+                    let covered = between.iter().fold(0, |acc, &c| acc | (c & filling));
+                    if covered == filling && between.iter().all(|&c| c & filling != 0) {
+                        possible = true;
+                        for (f, &c) in filled.iter_mut().zip(between.iter()) {
+                            *f |= c & filling;
+                        }
+                    }
+                }
+                if !possible {
+                    continue;
+                }
+
+                for &(a, b) in ends.iter() {
+                    allowed[lo] |= a;
+                    allowed[hi] |= b;
+                }
+                for (i, &f) in filled.iter().enumerate() {
+                    allowed[lo + 1 + i] |= f;
+                }
+                for i in outside {
+                    allowed[i] |= line[i] & !(ONE | NINE);
+                }
+            }
+        }
+
+        let mut changed = false;
+        for (i, &cell) in self.cells.iter().enumerate() {
+            changed = changed || cells[cell] & !allowed[i] != 0;
+            cells[cell] &= allowed[i];
+        }
+        return changed;
+    }
+}
+
+#[test]
+fn test_prune() {
+    // With nothing between the crusts, the 1 and the 9 are adjacent.
+    let sandwich = Sandwich::new((0 .. N).collect(), 0).unwrap();
+    let mut cells = vec![0b1_1111_1111; N];
+    cells[0] = ONE;
+    assert!(sandwich.prune(&mut cells));
+    assert_eq!(cells[1], NINE);
+    assert!(cells[2 ..].iter().all(|&c| c == 0b0_1111_1110));
+
+    // The largest sandwich spans the whole line.
+    let sandwich = Sandwich::new((0 .. N).collect(), 35).unwrap();
+    let mut cells = vec![0b1_1111_1111; N];
+    assert!(sandwich.prune(&mut cells));
+    assert_eq!(cells[0], ONE | NINE);
+    assert_eq!(cells[N - 1], ONE | NINE);
+
+    assert!(Sandwich::new((0 .. N).collect(), 36).is_err());
+}
@@ -0,0 +1,134 @@
+// Games saved to be resumed: a small text file of the puzzle, the
+// digits and marks entered, the time played and the mistakes made,
+// one field a line, under the data directory of the user, e.g.:
+//
+//     sudoku-rs game
+//     variant standard
+//     side 9
+//     puzzle ..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..
+//     grid 4.3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..
+//     marks - 28 - 579 ...
+//     elapsed 125
+//     mistakes 1
+//
+// The marks of a cell are its digits written together, `-` for none.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{num_to_char, CandidateSet};
+
+/// The first line of a saved game.
+const MAGIC: &str = "sudoku-rs game";
+
+/// A game saved.
+#[derive(Debug, PartialEq)]
+pub struct Saved {
+    /// The name of the variant of the puzzle.
+    pub variant: String,
+    /// The side of its grid.
+    pub side: usize,
+    /// The givens of the puzzle.
+    pub puzzle: String,
+    /// The givens and the digits entered.
+    pub grid: String,
+    /// The pencil marks of every cell.
+    pub marks: Vec<CandidateSet>,
+    /// The seconds played so far.
+    pub elapsed: u64,
+    /// The digits entered that disagree with the solution.
+    pub mistakes: usize,
+}
+
+/// Return the file `name` of the data of the game, e.g., `game.txt`
+/// for the saved game: `sudoku-rs/name` in `$XDG_DATA_HOME`, or else in
+/// `~/.local/share`; None without a home.
+pub fn path(name: &str) -> Option<PathBuf> {
+    let data = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").filter(|dir| !dir.is_empty())?).join(".local").join("share"),
+    };
+    return Some(data.join("sudoku-rs").join(name));
+}
+
+/// Return the text of `saved`.
+pub fn to_text(saved: &Saved) -> String {
+    let marks: Vec<String> = saved.marks.iter().map(|&set| {
+        if set == 0 {
+            return "-".to_string();
+        }
+        return (0 .. saved.side as u32).filter(|d| set & (1 << d) != 0).map(|d| num_to_char(d + 1)).collect();
+    }).collect();
+    return format!("{}\nvariant {}\nside {}\npuzzle {}\ngrid {}\nmarks {}\nelapsed {}\nmistakes {}\n",
+                   MAGIC, saved.variant, saved.side, saved.puzzle, saved.grid, marks.join(" "), saved.elapsed,
+                   saved.mistakes);
+}
+
+/// Return the game saved in `text`, or why it cannot be read.
+pub fn from_text(text: &str) -> Result<Saved, String> {
+    let mut lines = text.lines();
+    if lines.next() != Some(MAGIC) {
+        return Err("not a saved game".to_string());
+    }
+    let mut field = |name: &str| -> Result<String, String> {
+        return lines.next()
+            .and_then(|line| line.strip_prefix(name))
+            .and_then(|value| value.strip_prefix(' '))
+            .map(str::to_string)
+            .ok_or_else(|| format!("missing {} in saved game", name));
+    };
+    let variant = field("variant")?;
+    let side = field("side")?.parse().map_err(|_| "invalid side in saved game".to_string())?;
+    let (puzzle, grid) = (field("puzzle")?, field("grid")?);
+    let marks = field("marks")?.split(' ').map(|token| {
+        if token == "-" {
+            return Ok(0);
+        }
+        return token.chars().try_fold(0, |set, c| match c.to_digit(36) {
+            Some(d) if d >= 1 && d as usize <= side => Ok(set | 1 << (d - 1)),
+            _ => Err(format!("invalid marks {:?} in saved game", token)),
+        });
+    }).collect::<Result<Vec<CandidateSet>, String>>()?;
+    let elapsed = field("elapsed")?.parse().map_err(|_| "invalid time in saved game".to_string())?;
+    let mistakes = field("mistakes")?.parse().map_err(|_| "invalid number of mistakes in saved game".to_string())?;
+    if puzzle.chars().count() != side * side || grid.chars().count() != side * side || marks.len() != side * side {
+        return Err("the saved game does not fill its grid".to_string());
+    }
+    return Ok(Saved { variant, side, puzzle, grid, marks, elapsed, mistakes });
+}
+
+/// Save `saved` in the file at `path`, making its directory if need be.
+pub fn write(path: &PathBuf, saved: &Saved) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot make {}: {}", dir.display(), e))?;
+    }
+    return fs::write(path, to_text(saved)).map_err(|e| format!("cannot write {}: {}", path.display(), e));
+}
+
+/// Return the game saved in the file at `path`.
+pub fn read(path: &PathBuf) -> Result<Saved, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    return from_text(&text);
+}
+
+#[test]
+fn test_saved() {
+    let mut marks = vec![0; 16];
+    marks[1] = 0b1010;
+    let saved = Saved {
+        variant: "standard".to_string(),
+        side: 4,
+        puzzle: "1.3.............".to_string(),
+        grid: "123.............".to_string(),
+        marks,
+        elapsed: 75,
+        mistakes: 2,
+    };
+    let text = to_text(&saved);
+    assert!(text.contains("\nmarks - 24 - -"));
+    assert_eq!(from_text(&text), Ok(saved));
+    assert!(from_text(&text.replace("side 4", "side 9")).is_err());
+    assert!(from_text(&text.replace(" 24 ", " 25 ")).is_err());
+    assert!(from_text("sudoku-rs game\nvariant standard\n").is_err());
+}
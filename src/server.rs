@@ -0,0 +1,513 @@
+// A small HTTP server for the solver, so that it can back a puzzle
+// web service: `POST /solve` and `POST /rate` take a puzzle in the
+// body, `GET /generate` takes the generator's settings in its query,
+// and every answer is a JSON object.  Connections are handled one
+// at a time by each of a pool of threads, but for WebSocket ones,
+// which last, and so get threads of their own, as many as a few
+// dozen connections at once take.  A WebSocket
+// connection to `/ws` takes a stream of puzzles and answers each as
+// soon as it is solved.  Its messages, which are also those of the
+// JSON-Lines protocol of the solver, are JSON requests with an `op`,
+// a `grid` and an `id` that the answer repeats.  `GET /metrics` gives
+// the server's metrics to Prometheus.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{cell_position, error, Rules, SudokuBoard};
+use check::{self, Conflict};
+use generate::{self, Settings, Source};
+use json::{self, Json};
+use logic::{self, Difficulty};
+use metrics;
+use possible::Reach;
+use rng::Rng;
+use symmetry::Symmetry;
+use websocket;
+
+/// Largest request body accepted.
+const MAX_BODY: usize = 1 << 16;
+
+/// Largest request line and headers accepted, together.
+const MAX_HEADER: usize = 1 << 13;
+
+/// Most headers accepted.
+const MAX_HEADERS: usize = 100;
+
+/// Most WebSocket connections open at once, each of which holds the
+/// threads of its messages.
+const MAX_SOCKETS: usize = 32;
+
+/// Time given to a client to send its whole request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The error of a request not sent in time.
+const TIMED_OUT: &str = "request timed out";
+
+/// The error of a request whose line or headers are too large.
+const TOO_LARGE: &str = "request header fields too large";
+
+/// A connection read until a deadline: every read waits only for the
+/// time left, so that a client sending a byte at a time still has to
+/// send its whole request in time.
+struct Deadline {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        return self.stream.read(buf);
+    }
+}
+
+/// An HTTP request.
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    /// The headers, with their names in lower case.
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    /// Return the value of the header `name`, in lower case.
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|h| h.0 == name).map(|h| h.1.as_str())
+    }
+}
+
+/// Return the message of the error `e` met reading a request.
+fn read_error(e: io::Error) -> String {
+    return match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => TIMED_OUT.to_string(),
+        _ => e.to_string(),
+    };
+}
+
+/// Read a line of the request line and headers from `input` into
+/// `line`, out of the `left` bytes they may still take.
+fn read_header_line<R: BufRead>(input: &mut R, line: &mut String, left: &mut usize) -> Result<(), String> {
+    input.by_ref().take(*left as u64 + 1).read_line(line).map_err(read_error)?;
+    if line.len() > *left {
+        return Err(TOO_LARGE.to_string());
+    }
+    *left -= line.len();
+    return Ok(());
+}
+
+/// Read a request from `input`.
+fn read_request<R: BufRead>(input: &mut R) -> Result<Request, String> {
+    let mut left = MAX_HEADER;
+    let mut line = String::new();
+    read_header_line(input, &mut line, &mut left)?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => { return Err("invalid request line".to_string()); }
+    };
+    let (path, query) = match target.find('?') {
+        Some(i) => (&target[.. i], &target[i + 1 ..]),
+        None => (target, ""),
+    };
+    let query = query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            (parts.next().unwrap().to_string(), parts.next().unwrap_or("").to_string())
+        })
+        .collect();
+
+    let mut headers = Vec::new();
+    let mut header = String::new();
+    loop {
+        header.clear();
+        read_header_line(input, &mut header, &mut left)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(TOO_LARGE.to_string());
+        }
+        let mut parts = header.splitn(2, ':');
+        headers.push((parts.next().unwrap().to_lowercase(), parts.next().unwrap_or("").trim().to_string()));
+    }
+    let length = match headers.iter().find(|h| h.0 == "content-length") {
+        Some(h) => h.1.parse().map_err(|_| "invalid Content-Length".to_string())?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err("request body too large".to_string());
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).map_err(read_error)?;
+    let body = String::from_utf8(body).map_err(|_| "request body is not UTF-8".to_string())?;
+    return Ok(Request { method, path: path.to_string(), query, headers, body });
+}
+
+/// Return the puzzle `digits` under `rules`, or why it is invalid.
+fn board<'a>(digits: &str, rules: &'a Rules) -> Result<SudokuBoard<'a>, String> {
+    let (ncells, side) = (rules.neighbors.len(), rules.shape.side() as u32);
+    if digits.chars().count() != ncells {
+        return Err(format!("invalid puzzle length; expected {}", ncells));
+    }
+    let valid = |c: char| c == '.' || c.to_digit(36).is_some_and(|n| (1 ..= side).contains(&n));
+    if let Some(c) = digits.chars().find(|&c| !valid(c)) {
+        return Err(format!("invalid digit ({:?}) in puzzle", c));
+    }
+    return Ok(SudokuBoard::from_str(digits, rules));
+}
+
+/// Return the generator's settings given by the query of a request.
+fn settings(query: &[(String, String)]) -> Result<Settings, String> {
+    let mut settings = Settings::new();
+    for (name, value) in query.iter() {
+        let invalid = || format!("invalid value {:?} for {}", value, name);
+        match name.as_str() {
+            "difficulty" => { settings.difficulty = Some(Difficulty::from_name(value).ok_or_else(invalid)?); }
+            "symmetry" => { settings.symmetry = Symmetry::from_name(value).ok_or_else(invalid)?; }
+            "seed" => { settings.seed = Some(value.parse().map_err(|_| invalid())?); }
+            "clues" => { settings.clues = Some(generate::parse_range(value).ok_or_else(invalid)?); }
+            "minimal" => { settings.minimal = value != "false"; }
+            _ => { return Err(format!("unknown setting {:?}", name)); }
+        }
+    }
+    return Ok(settings);
+}
+
+/// Return the answer to a request to solve `digits`.
+fn solve(digits: &str, rules: &Rules) -> Result<Json, String> {
+    let board = board(digits, rules)?;
+    let start = Instant::now();
+    let (solution, guesses) = board.solve_counting();
+    metrics::solved(start.elapsed(), guesses);
+    let solution = solution.map_or(Json::Null, |solution| Json::String(solution.to_str()));
+    return Ok(Json::object(vec![("solution", solution)]));
+}
+
+/// Return the answer to a request to rate `digits`.
+fn rate(digits: &str, rules: &Rules) -> Result<Json, String> {
+    let board = board(digits, rules)?;
+    return Ok(Json::object(vec![
+        ("rating", Json::String(logic::rate(&board).name().to_string())),
+        ("unique", Json::Bool(board.count_solutions(2) == 1)),
+    ]));
+}
+
+/// Return the answer to a request for a hint on `digits`: the
+/// technique of the next step of a human solver, with the digits it
+/// places and the candidates it removes, or null if none is found.
+fn hint(digits: &str, rules: &Rules) -> Result<Json, String> {
+    let board = board(digits, rules)?;
+    let name = |cell: usize| format!("r{}c{}", rules.shape.row(cell) + 1, rules.shape.col(cell) + 1);
+    let candidates = |pairs: &[(usize, usize)]| Json::Array(pairs.iter().map(|&(cell, digit)| Json::object(vec![
+        ("cell", Json::String(name(cell))),
+        ("digit", Json::Number(digit as f64)),
+    ])).collect());
+    return Ok(Json::object(vec![("hint", board.hint().map_or(Json::Null, |hint| Json::object(vec![
+        ("technique", Json::String(hint.technique.name().to_string())),
+        ("placements", candidates(&hint.placements)),
+        ("eliminations", candidates(&hint.eliminations)),
+    ])))]));
+}
+
+/// Return the answer to a request for the digits that can still go
+/// in the cell named `cell` of `digits`, narrowed down as far as the
+/// reach named `reach`.
+fn possible(digits: &str, cell: &str, reach: &str, rules: &Rules) -> Result<Json, String> {
+    let board = board(digits, rules)?;
+    let (row, col) = cell_position(cell, rules.shape.side()).ok_or_else(|| format!("invalid cell {:?}", cell))?;
+    let reach = Reach::from_name(reach).ok_or_else(|| format!("unknown reach {:?}", reach))?;
+    let digits = board.possible_digits(row, col, reach);
+    return Ok(Json::object(vec![("digits", Json::Array(digits.iter().map(|&d| Json::Number(d as f64)).collect()))]));
+}
+
+/// Return the answer to a request to check `progress`, the digits
+/// filled in so far on `digits`: the conflicts found.
+fn conflicts(digits: &str, progress: &str, rules: &Rules) -> Result<Json, String> {
+    let found = check::conflicts(&board(digits, rules)?, &board(progress, rules)?);
+    let name = |cell: usize| Json::String(format!("r{}c{}", rules.shape.row(cell) + 1, rules.shape.col(cell) + 1));
+    return Ok(Json::object(vec![("conflicts", Json::Array(found.iter().map(|conflict| match *conflict {
+        Conflict::Duplicate { first, second, digit } => Json::object(vec![
+            ("kind", Json::String("duplicate".to_string())),
+            ("cells", Json::Array(vec![name(first), name(second)])),
+            ("digit", Json::Number(digit as f64)),
+        ]),
+        Conflict::Wrong { cell, digit } => Json::object(vec![
+            ("kind", Json::String("wrong".to_string())),
+            ("cells", Json::Array(vec![name(cell)])),
+            ("digit", Json::Number(digit as f64)),
+        ]),
+        Conflict::Excluded { cell, digit } => Json::object(vec![
+            ("kind", Json::String("excluded".to_string())),
+            ("cells", Json::Array(vec![name(cell)])),
+            ("digit", Json::Number(digit as f64)),
+        ]),
+    }).collect()))]));
+}
+
+/// Answer a JSON request under `rules`: solve its `grid`, or rate it
+/// or give a hint if its `op` is `rate` or `hint`, or check the
+/// digits of its `progress` on it if its `op` is `conflicts`, or give
+/// the digits that can go in its `cell`, as far as its `reach`, if its
+/// `op` is `possible`.  Return the JSON answer, with the `id` of the
+/// request.
+pub fn reply(text: &str, rules: &Rules) -> String {
+    let request = json::parse(text);
+    let id = request.as_ref().ok().and_then(|request| request.get("id").cloned()).unwrap_or(Json::Null);
+    let result = request.and_then(|request| {
+        let grid = request.get("grid").and_then(Json::as_str).ok_or_else(|| "missing grid".to_string())?;
+        match request.get("op").and_then(Json::as_str).unwrap_or("solve") {
+            "solve" => solve(grid, rules),
+            "rate" => rate(grid, rules),
+            "hint" => hint(grid, rules),
+            "conflicts" => {
+                let progress = request.get("progress").and_then(Json::as_str)
+                    .ok_or_else(|| "missing progress".to_string())?;
+                conflicts(grid, progress, rules)
+            }
+            "possible" => {
+                let cell = request.get("cell").and_then(Json::as_str).ok_or_else(|| "missing cell".to_string())?;
+                possible(grid, cell, request.get("reach").and_then(Json::as_str).unwrap_or("propagation"), rules)
+            }
+            op => Err(format!("unknown op {:?}", op)),
+        }
+    });
+    let mut pairs = vec![("id".to_string(), id)];
+    match result {
+        Ok(Json::Object(answer)) => pairs.extend(answer),
+        Ok(answer) => pairs.push(("answer".to_string(), answer)),
+        Err(msg) => pairs.push(("error".to_string(), Json::String(msg))),
+    }
+    return Json::Object(pairs).to_string();
+}
+
+/// Answer the requests on the lines of `input` under `rules` with
+/// `write`, each on a line of its own, until the end of the input.
+/// A request that cannot be read is answered with why, like any
+/// other, and the next one is read.
+pub fn answer_lines<R: BufRead, W: FnMut(&str)>(input: &mut R, rules: &Rules, mut write: W) -> io::Result<()> {
+    let mut buf = String::new();
+    while input.read_line(&mut buf)? > 0 {
+        if !buf.trim().is_empty() {
+            write(&reply(buf.trim(), rules));
+        }
+        buf.clear();
+    }
+    return Ok(());
+}
+
+/// Answer `request` under `rules`: return the HTTP status and the
+/// JSON object of the answer.
+fn answer(request: &Request, rules: &Rules) -> (u16, Json) {
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/solve") => solve(request.body.trim(), rules),
+        ("POST", "/rate") => rate(request.body.trim(), rules),
+        ("GET", "/generate") => settings(&request.query).and_then(|settings| {
+            let mut rng = Rng::new(settings.seed.unwrap_or_else(Rng::clock_seed));
+            let (digits, _, rating, _) = generate::one(&Source::Shared(rules), &settings, &mut rng)
+                .ok_or_else(generate::not_found)?;
+            Ok(Json::object(vec![
+                ("puzzle", Json::String(digits)),
+                ("rating", Json::String(rating.name().to_string())),
+            ]))
+        }),
+        _ => { return (404, Json::object(vec![("error", Json::String("not found".to_string()))])); }
+    };
+    return match result {
+        Ok(json) => (200, json),
+        Err(msg) => (400, Json::object(vec![("error", Json::String(msg))])),
+    };
+}
+
+/// Read a request from `stream` and write the answer; serve a
+/// WebSocket connection on a thread of `scope`, counted in `sockets`,
+/// unless MAX_SOCKETS are open.
+fn handle<'scope, 'env>(mut stream: TcpStream, rules: &'env Rules, scope: &'scope thread::Scope<'scope, 'env>,
+                        sockets: &'env AtomicUsize) {
+    let request = match stream.try_clone() {
+        Ok(input) => read_request(&mut BufReader::new(Deadline { stream: input, deadline: Instant::now() + TIMEOUT })),
+        Err(_) => { return; }
+    };
+    let path = request.as_ref().map_or("", |request| request.path.as_str());
+    let (status, json) = match request {
+        Ok(ref request) if request.path == "/ws" => {
+            match request.header("sec-websocket-key") {
+                Some(key) if request.header("upgrade").is_some_and(|u| u.eq_ignore_ascii_case("websocket")) => {
+                    let opened = sockets.fetch_update(Ordering::SeqCst, Ordering::SeqCst,
+                                                      |open| if open < MAX_SOCKETS { Some(open + 1) } else { None });
+                    if opened.is_err() {
+                        (503, Json::object(vec![("error", Json::String("too many WebSocket connections".to_string()))]))
+                    } else {
+                        metrics::request(path, 101);
+                        let key = key.to_string();
+                        scope.spawn(move || {
+                            websocket::serve(stream, &key, &|message| reply(message, rules));
+                            sockets.fetch_sub(1, Ordering::SeqCst);
+                        });
+                        return;
+                    }
+                }
+                _ => (400, Json::object(vec![("error", Json::String("expected a WebSocket handshake".to_string()))])),
+            }
+        }
+        Ok(ref request) if request.path == "/metrics" => {
+            metrics::request(path, 200);
+            let body = metrics::text();
+            let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n\
+                                    Content-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            return;
+        }
+        Ok(ref request) => answer(request, rules),
+        Err(ref msg) if msg == TIMED_OUT => {
+            metrics::timeout();
+            (408, Json::object(vec![("error", Json::String(msg.clone()))]))
+        }
+        Err(ref msg) if msg == TOO_LARGE => (431, Json::object(vec![("error", Json::String(msg.clone()))])),
+        Err(ref msg) => (400, Json::object(vec![("error", Json::String(msg.clone()))])),
+    };
+    metrics::request(path, status);
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Bad Request",
+    };
+    let body = format!("{}\n", json);
+    let _ = write!(stream, "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\n\
+                            Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                   status, reason, body.len(), body);
+}
+
+/// Serve requests on `address` under `rules` on `threads` threads,
+/// forever.
+pub fn run(address: &str, rules: &Rules, threads: usize) {
+    let listener = TcpListener::bind(address)
+        .unwrap_or_else(|e| error(format!("cannot listen on {}: {}", address, e)));
+    let (sender, receiver) = mpsc::channel();
+    let receiver = Mutex::new(receiver);
+    let sockets = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0 .. threads {
+            let (receiver, sockets) = (&receiver, &sockets);
+            scope.spawn(move || {
+                loop {
+                    // The lock is released as soon as a connection is taken.
+                    let taken = receiver.lock().unwrap().recv();
+                    match taken {
+                        Ok(stream) => handle(stream, rules, scope, sockets),
+                        Err(_) => { return; }
+                    }
+                }
+            });
+        }
+        for stream in listener.incoming().flatten() {
+            let _ = sender.send(stream);
+        }
+    });
+}
+
+#[test]
+fn test_answer() {
+    use std::io::Cursor;
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let puzzle = PUZZLE;
+    let text = format!("POST /solve HTTP/1.1\r\nHost: x\r\nContent-Length: {}\r\n\r\n{}", puzzle.len(), puzzle);
+    let request = read_request(&mut Cursor::new(text)).unwrap();
+    let (status, json) = answer(&request, &rules);
+    assert_eq!(status, 200);
+    assert_eq!(json.get("solution").and_then(Json::as_str).map(str::len), Some(81));
+
+    let text = "GET /generate?difficulty=easy&seed=1 HTTP/1.1\r\n\r\n";
+    let (status, json) = answer(&read_request(&mut Cursor::new(text)).unwrap(), &rules);
+    assert_eq!(status, 200);
+    assert_eq!(json.get("rating").and_then(Json::as_str), Some("easy"));
+
+    let text = "GET /generate?difficulty=impossible HTTP/1.1\r\n\r\n";
+    assert_eq!(answer(&read_request(&mut Cursor::new(text)).unwrap(), &rules).0, 400);
+    let text = "POST /solve HTTP/1.1\r\nContent-Length: 3\r\n\r\n123";
+    assert_eq!(answer(&read_request(&mut Cursor::new(text)).unwrap(), &rules).0, 400);
+    let text = "GET /nowhere HTTP/1.1\r\n\r\n";
+    assert_eq!(answer(&read_request(&mut Cursor::new(text)).unwrap(), &rules).0, 404);
+    let text = format!("GET /{} HTTP/1.1\r\n\r\n", "x".repeat(MAX_HEADER));
+    assert_eq!(read_request(&mut Cursor::new(text)).err().as_deref(), Some(TOO_LARGE));
+    let text = format!("GET / HTTP/1.1\r\n{}\r\n", "X: y\r\n".repeat(MAX_HEADERS + 1));
+    assert_eq!(read_request(&mut Cursor::new(text)).err().as_deref(), Some(TOO_LARGE));
+
+    let message = format!(r#"{{"id": 7, "op": "rate", "grid": "{}"}}"#, puzzle);
+    assert_eq!(reply(&message, &rules), r#"{"id": 7, "rating": "easy", "unique": true}"#);
+    let message = format!(r#"{{"op": "hint", "grid": "{}"}}"#, puzzle);
+    assert_eq!(reply(&message, &rules),
+               r#"{"id": null, "hint": {"technique": "singles", "placements": [{"cell": "r5c6", "digit": 4}], "eliminations": []}}"#);
+    let progress = format!("4{}", &puzzle[1 ..]).replacen("..1", ".11", 1);
+    let message = format!(r#"{{"op": "conflicts", "grid": "{}", "progress": "{}"}}"#, puzzle, progress);
+    assert_eq!(reply(&message, &rules),
+               r#"{"id": null, "conflicts": [{"kind": "duplicate", "cells": ["r2c8", "r2c9"], "digit": 1}, {"kind": "wrong", "cells": ["r2c8"], "digit": 1}]}"#);
+    let message = format!(r#"{{"op": "possible", "grid": "{}", "cell": "r5c6", "reach": "solutions"}}"#, puzzle);
+    assert_eq!(reply(&message, &rules), r#"{"id": null, "digits": [4]}"#);
+    let message = format!(r#"{{"op": "possible", "grid": "{}", "cell": "r10c1"}}"#, puzzle);
+    assert_eq!(reply(&message, &rules), r#"{"id": null, "error": "invalid cell \"r10c1\""}"#);
+    assert_eq!(reply("{", &rules), r#"{"id": null, "error": "unexpected end of JSON"}"#);
+}
+
+#[test]
+fn test_answer_lines() {
+    use std::io::Cursor;
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    // A line nested past the parser's depth does not end the loop.
+    let text = format!("{{\"id\": 1, \"grid\": \"{}\"}}\n{{\"op\":\"solve\",\"grid\":{}\n\n{{\"id\": 3, \"grid\": \"{}\"}}\n",
+                       PUZZLE, "[".repeat(60000), PUZZLE);
+    let mut answers = Vec::new();
+    answer_lines(&mut Cursor::new(text), &rules, |answer| answers.push(answer.to_string())).unwrap();
+    assert_eq!(answers.len(), 3);
+    assert!(answers[0].starts_with(r#"{"id": 1, "solution": ""#));
+    assert_eq!(answers[1], r#"{"id": null, "error": "too deeply nested"}"#);
+    assert!(answers[2].starts_with(r#"{"id": 3, "solution": ""#));
+}
+
+#[test]
+fn test_handle() {
+    use std::net::TcpListener;
+    use super::standard_rules;
+
+    let rules = standard_rules();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    // Upgrade a connection, with `open` connections open, and return
+    // the answer and the connections open once it is closed.
+    let upgrade = |open: usize| {
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(b"GET /ws HTTP/1.1\r\nUpgrade: websocket\r\nSec-WebSocket-Key: x3JJHMbDL1EzLkh9GBhXDw==\r\n\r\n")
+            .unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let sockets = AtomicUsize::new(open);
+        thread::scope(|scope| handle(stream, &rules, scope, &sockets));
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).unwrap();
+        return (answer, sockets.load(Ordering::SeqCst));
+    };
+    let (answer, open) = upgrade(0);
+    assert!(answer.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    assert_eq!(open, 0);
+    // Past the connections open, an upgrade is refused.
+    let (answer, open) = upgrade(MAX_SOCKETS);
+    assert!(answer.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    assert_eq!(open, MAX_SOCKETS);
+}
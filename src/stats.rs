@@ -0,0 +1,56 @@
+// Statistics of puzzles, for cataloguing collections: the number of
+// givens and of each digit, the rows, columns and boxes without a
+// given, the symmetries of the givens, and whether every given is
+// needed for the solution to be unique.
+
+use super::{num_to_char, SudokuBoard};
+use analyze;
+use symmetry::Symmetry;
+
+/// Return the line of statistics of `board`, a puzzle on a single grid.
+pub fn report(board: &SudokuBoard) -> String {
+    let shape = board.rules.shape;
+    let givens: Vec<usize> = (0 .. board.cells.len()).filter(|&c| board.cell_solved(c)).collect();
+    let mut counts = vec![0; shape.side()];
+    for &cell in givens.iter() {
+        counts[board.cells[cell].trailing_zeros() as usize] += 1;
+    }
+    let digits: Vec<String> = counts.iter().enumerate()
+        .map(|(d, n)| format!("{}:{}", num_to_char(d as u32 + 1), n))
+        .collect();
+    // The units without a given, by the first cell of the unit.
+    let empty = |unit: &dyn Fn(usize) -> usize| {
+        let mut units: Vec<usize> = (0 .. shape.cells()).map(unit).collect();
+        units.sort();
+        units.dedup();
+        return units.iter().filter(|&&u| !givens.iter().any(|&c| unit(c) == u)).count();
+    };
+    let symmetries: Vec<&str> = [Symmetry::Rotational, Symmetry::Mirror, Symmetry::Diagonal].iter()
+        .filter(|symmetry| symmetry.holds(board))
+        .map(|symmetry| symmetry.name())
+        .collect();
+    let minimal = if board.count_solutions(2) != 1 {
+        "no unique solution"
+    } else if analyze::redundant(board).is_empty() {
+        "minimal"
+    } else {
+        "not minimal"
+    };
+    return format!("{} givens, digits {}, {} empty rows, {} empty columns, {} empty boxes, symmetry {}, {}",
+                   givens.len(), digits.join(" "), empty(&|c| shape.row(c)), empty(&|c| shape.col(c)),
+                   empty(&|c| shape.group(c)),
+                   if symmetries.is_empty() { "none".to_string() } else { symmetries.join(" ") }, minimal);
+}
+
+#[test]
+fn test_report() {
+    use super::{standard_rules, PUZZLE};
+
+    let rules = standard_rules();
+    let board = SudokuBoard::from_str(PUZZLE, &rules);
+    assert_eq!(report(&board), "32 givens, digits 1:4 2:5 3:4 4:1 5:3 6:4 7:2 8:5 9:4, 0 empty rows, \
+                                2 empty columns, 0 empty boxes, symmetry rotational mirror, not minimal");
+    let board = SudokuBoard::from_str(&format!("1{}", ".".repeat(80)), &rules);
+    assert_eq!(report(&board), "1 givens, digits 1:1 2:0 3:0 4:0 5:0 6:0 7:0 8:0 9:0, 8 empty rows, \
+                                8 empty columns, 8 empty boxes, symmetry diagonal, no unique solution");
+}
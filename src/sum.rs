@@ -0,0 +1,75 @@
+// Sums of digits that may repeat, such as those of arrows and of
+// little killer diagonals, which are pruned by their bounds.
+
+use super::CandidateSet;
+use constraint::Constraint;
+
+/// Return the smallest digit of a non-empty set.
+pub fn min_digit(set: CandidateSet) -> u32 {
+    set.trailing_zeros() + 1
+}
+
+/// Return the largest digit of a non-empty set.
+pub fn max_digit(set: CandidateSet) -> u32 {
+    32 - set.leading_zeros()
+}
+
+/// Return the digits of `set` between `lo` and `hi` inclusively.
+pub fn between(set: CandidateSet, lo: i64, hi: i64) -> CandidateSet {
+    let mut output = 0;
+    for d in lo.max(1) ..= hi.min(32) {
+        output |= set & (1 << (d - 1));
+    }
+    return output;
+}
+
+/// Return the smallest and largest sums of the digits of `terms`.
+pub fn bounds(terms: &[usize], cells: &[CandidateSet]) -> (i64, i64) {
+    let lo = terms.iter().map(|&c| min_digit(cells[c]) as i64).sum();
+    let hi = terms.iter().map(|&c| max_digit(cells[c]) as i64).sum();
+    return (lo, hi);
+}
+
+/// Remove from the `terms` cells the candidates that cannot be part
+/// of a sum between `total_lo` and `total_hi`, given the bounds of
+/// the other terms.  The terms must have candidates.  Return whether
+/// any candidate was removed.
+pub fn prune_terms(terms: &[usize], cells: &mut [CandidateSet],
+                   total_lo: i64, total_hi: i64) -> bool {
+    let (lo, hi) = bounds(terms, cells);
+    let mut changed = false;
+    for &c in terms.iter() {
+        let rest_lo = lo - min_digit(cells[c]) as i64;
+        let rest_hi = hi - max_digit(cells[c]) as i64;
+        let set = between(cells[c], total_lo - rest_hi, total_hi - rest_lo);
+        changed = changed || set != cells[c];
+        cells[c] = set;
+    }
+    return changed;
+}
+
+/// A little killer clue: the digits along a diagonal add up to `sum`.
+pub struct LittleKiller {
+    pub cells: Vec<usize>,
+    pub sum: u32,
+}
+
+impl Constraint for LittleKiller {
+    /// Remove the candidates of the diagonal that fall outside the
+    /// bounds of the sum.  Return whether any candidate was removed.
+    fn prune(&self, cells: &mut [CandidateSet]) -> bool {
+        if self.cells.iter().any(|&c| cells[c] == 0) {
+            return false;
+        }
+        return prune_terms(&self.cells, cells, self.sum as i64, self.sum as i64);
+    }
+}
+
+#[test]
+fn test_little_killer() {
+    let clue = LittleKiller { cells: vec![0, 1, 2], sum: 25 };
+    let mut cells = vec![0b1_1111_1111; 3];
+    assert!(clue.prune(&mut cells));
+    assert_eq!(cells, vec![0b1_1100_0000; 3]);
+    assert!(!clue.prune(&mut cells));
+}
@@ -0,0 +1,76 @@
+// Symmetries of the grid: the half turn and the reflections that
+// generated puzzles can keep their clues under, and that statistics
+// look for in the givens of a puzzle.
+
+use super::{Shape, SudokuBoard};
+
+/// A symmetry of the grid, which the clues of a puzzle can have.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    #[cfg(feature = "generate")]
+    None,
+    /// Unchanged by a half turn of the grid.
+    Rotational,
+    /// Unchanged by a left-right reflection.
+    Mirror,
+    /// Unchanged by a reflection across the main diagonal.
+    Diagonal,
+}
+
+impl Symmetry {
+    #[cfg(feature = "generate")]
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        match name {
+            "none" => Some(Symmetry::None),
+            "rotational" => Some(Symmetry::Rotational),
+            "mirror" => Some(Symmetry::Mirror),
+            "diagonal" => Some(Symmetry::Diagonal),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "generate")]
+            Symmetry::None => "none",
+            Symmetry::Rotational => "rotational",
+            Symmetry::Mirror => "mirror",
+            Symmetry::Diagonal => "diagonal",
+        }
+    }
+
+    /// Whether the clues of `board` are unchanged by the symmetry.
+    pub fn holds(self, board: &SudokuBoard) -> bool {
+        let shape = board.rules.shape;
+        return (0 .. shape.cells()).all(|c| board.cell_solved(c) == board.cell_solved(self.image(shape, c)));
+    }
+
+    /// Return the image of `cell` by the symmetry.
+    pub fn image(self, shape: Shape, cell: usize) -> usize {
+        let (r, c, last) = (shape.row(cell), shape.col(cell), shape.side() - 1);
+        let (r, c) = match self {
+            #[cfg(feature = "generate")]
+            Symmetry::None => (r, c),
+            Symmetry::Rotational => (last - r, last - c),
+            Symmetry::Mirror => (r, last - c),
+            Symmetry::Diagonal => (c, r),
+        };
+        return r * shape.side() + c;
+    }
+
+    /// Return the sets of cells that the symmetry maps onto each
+    /// other, so that they are all clues or all blank.
+    #[cfg(feature = "generate")]
+    pub fn orbits(self, shape: Shape) -> Vec<Vec<usize>> {
+        let mut orbits = Vec::new();
+        for cell in 0 .. shape.cells() {
+            let image = self.image(shape, cell);
+            if image == cell {
+                orbits.push(vec![cell]);
+            } else if image > cell {
+                orbits.push(vec![cell, image]);
+            }
+        }
+        return orbits;
+    }
+}
@@ -0,0 +1,173 @@
+// The terminal of the interactive commands, driven without a library:
+// `stty` puts it in raw mode, so that keys are read as they are
+// pressed, and ANSI escape sequences move the cursor and color the
+// text.  A read waits a second at most, so that a clock on the screen
+// keeps ticking without a key pressed.  Terminals that report the
+// mouse send its clicks as escape sequences too, read as keys.
+
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+
+/// Clear the screen and move the cursor to its upper-left corner.
+pub const CLEAR: &str = "\x1b[H\x1b[2J";
+/// Draw in bold, e.g., the givens.
+pub const BOLD: &str = "\x1b[1m";
+/// Draw in red, e.g., the mistakes.
+pub const RED: &str = "\x1b[31m";
+/// Draw on a yellow background, e.g., the cells of a hint.
+pub const YELLOW: &str = "\x1b[43m";
+/// Swap the colors of the text and the background, e.g., for the
+/// selected cell.
+pub const REVERSE: &str = "\x1b[7m";
+/// Go back to plain text.
+pub const RESET: &str = "\x1b[0m";
+
+/// A key pressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Backspace,
+    Delete,
+    /// Ctrl-C, since raw mode keeps it from interrupting.
+    Interrupt,
+    /// A press of the left button of the mouse on the line `row` and
+    /// column `col` of the screen, counting from 0.
+    Click { row: usize, col: usize },
+    Char(char),
+}
+
+/// The terminal in raw mode, with its cursor hidden and the mouse
+/// reported; dropping it brings back the settings it had before.
+pub struct RawMode {
+    saved: String,
+}
+
+/// Run `stty` with `args` on the terminal of the standard input, and
+/// return what it writes.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+}
+
+impl RawMode {
+    /// Put the terminal in raw mode.
+    pub fn enter() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo", "min", "0", "time", "10"])?;
+        // The clicks are reported in the extended form of xterm.
+        print!("\x1b[?25l\x1b[?1000h\x1b[?1006h");
+        return Ok(RawMode { saved });
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?1006l\x1b[?1000l\x1b[?25h");
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Return the escape sequence of the style `spec`: words among
+/// `bold`, `dim`, `italic`, `underline`, `reverse`, the colors of the
+/// text, e.g., `red` or `bright-red`, and those of the background,
+/// e.g., `on-red`; `plain` for none.  None if a word is unknown.
+pub fn style(spec: &str) -> Option<String> {
+    const COLORS: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+    let color = |name: &str| COLORS.iter().position(|&color| color == name);
+    let mut codes = Vec::new();
+    for word in spec.split_whitespace() {
+        let code = match word {
+            "plain" => { continue; }
+            "bold" => 1,
+            "dim" => 2,
+            "italic" => 3,
+            "underline" => 4,
+            "reverse" => 7,
+            _ => {
+                if let Some(name) = word.strip_prefix("on-") {
+                    40 + color(name)?
+                } else if let Some(name) = word.strip_prefix("bright-") {
+                    90 + color(name)?
+                } else {
+                    30 + color(word)?
+                }
+            }
+        };
+        codes.push(code.to_string());
+    }
+    if codes.is_empty() {
+        return Some(String::new());
+    }
+    return Some(format!("\x1b[{}m", codes.join(";")));
+}
+
+/// Return the next key pressed among `bytes`, those read from the
+/// terminal, skipping the keys not understood; None at the end of
+/// the bytes, when a second goes by without a key in raw mode.
+pub fn read_key<I: Iterator<Item = u8>>(bytes: &mut I) -> Option<Key> {
+    loop {
+        let key = match bytes.next()? {
+            // The arrows and the delete key send escape sequences.
+            0x1b => match (bytes.next()?, bytes.next()?) {
+                (b'[', b'A') => Some(Key::Up),
+                (b'[', b'B') => Some(Key::Down),
+                (b'[', b'C') => Some(Key::Right),
+                (b'[', b'D') => Some(Key::Left),
+                (b'[', b'3') if bytes.next()? == b'~' => Some(Key::Delete),
+                // The button, the column and the line of a mouse
+                // event, then M when pressed, m when released.
+                (b'[', b'<') => {
+                    let (mut fields, mut field) = ([0usize; 3], 0);
+                    let end = loop {
+                        match bytes.next()? {
+                            digit @ b'0' ..= b'9' => {
+                                fields[field] = fields[field].saturating_mul(10).saturating_add((digit - b'0') as usize);
+                            }
+                            b';' if field < 2 => { field += 1; }
+                            byte => { break byte; }
+                        }
+                    };
+                    match (fields, end) {
+                        ([0, col, row], b'M') if col > 0 && row > 0 => {
+                            Some(Key::Click { row: row - 1, col: col - 1 })
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            0x7f | 0x08 => Some(Key::Backspace),
+            0x03 => Some(Key::Interrupt),
+            byte if byte.is_ascii_graphic() || byte == b' ' => Some(Key::Char(byte as char)),
+            _ => None,
+        };
+        if key.is_some() {
+            return key;
+        }
+    }
+}
+
+/// Return the bytes read from the standard input, one at a time.
+pub fn input() -> impl Iterator<Item = u8> {
+    return io::stdin().lock().bytes().map_while(Result::ok);
+}
+
+#[test]
+fn test_read_key() {
+    let mut bytes = b"\x1b[A5\x1b[3~\x01\x7fq\x03\x1b[<0;12;3M\x1b[<0;12;3m\x1b[<2;4;4M".iter().cloned();
+    let keys: Vec<Key> = std::iter::from_fn(|| read_key(&mut bytes)).collect();
+    assert_eq!(keys, vec![Key::Up, Key::Char('5'), Key::Delete, Key::Backspace, Key::Char('q'), Key::Interrupt,
+                          Key::Click { row: 2, col: 11 }]);
+}
+
+#[test]
+fn test_style() {
+    assert_eq!(style("bold  bright-blue on-white").as_deref(), Some("\x1b[1;94;47m"));
+    assert_eq!((style("plain").as_deref(), style("on-plaid")), (Some(""), None));
+}
@@ -0,0 +1,130 @@
+// Solve traces: the steps of the backtracking solver, in order, so
+// that a solve can be replayed.  Every round of propagation gives
+// the digits it places and the candidates it removes; every guess
+// and every guess undone is a step of its own, with its depth.
+
+use std::mem;
+
+use super::{CandidateSet, Limits, Shape, SudokuBoard};
+use json::Json;
+
+/// A step of the solver.  Digits count from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveStep {
+    /// The constraints leave a single candidate in `cell`.
+    Place { cell: usize, digit: usize },
+    /// The constraints remove `digits` from `cell`.
+    Eliminate { cell: usize, digits: Vec<usize> },
+    /// The solver tries `digit` in `cell`, at `depth` guesses.
+    Guess { cell: usize, digit: usize, depth: usize },
+    /// The guess of `digit` in `cell` leads nowhere.
+    Backtrack { cell: usize, digit: usize, depth: usize },
+}
+
+/// Return the digits of `set`.
+fn digits(set: CandidateSet) -> Vec<usize> {
+    (0 .. 32).filter(|d| set & (1 << d) != 0).map(|d| d + 1).collect()
+}
+
+/// Record the steps of propagating `board`.
+fn propagate(board: &mut SudokuBoard, steps: &mut Vec<SolveStep>) {
+    let before = board.cells.clone();
+    board.propagate();
+    for (cell, (&was, &set)) in before.iter().zip(board.cells.iter()).enumerate() {
+        if was == set {
+            continue;
+        }
+        if set.count_ones() == 1 {
+            steps.push(SolveStep::Place { cell, digit: digits(set)[0] });
+        } else {
+            steps.push(SolveStep::Eliminate { cell, digits: digits(was & !set) });
+        }
+    }
+}
+
+/// Solve `board` as `SudokuBoard::search` does, within `limits`,
+/// recording the steps taken at `depth` guesses.  The steps are held
+/// to the end of the search.
+fn search<'a>(board: &SudokuBoard<'a>, depth: usize, steps: &mut Vec<SolveStep>, limits: &Limits)
+              -> Option<SudokuBoard<'a>> {
+    if limits.exceeded() { return None; }
+
+    let mut newboard = board.clone();
+    let _held = limits.hold(newboard.footprint())?;
+    let recorded = steps.len();
+    propagate(&mut newboard, steps);
+    if !limits.charge((steps.len() - recorded) * mem::size_of::<SolveStep>()) { return None; }
+
+    if newboard.solved() { return Some(newboard); }
+
+    if !newboard.solvable() { return None; }
+
+    if let Some(cell) = newboard.most_promising() {
+        let candidates = newboard.cells[cell];
+        for digit in digits(candidates) {
+            // The guess and its backtrack, if any.
+            if !limits.charge(2 * mem::size_of::<SolveStep>()) { return None; }
+            steps.push(SolveStep::Guess { cell, digit, depth: depth + 1 });
+            newboard.cells[cell] = 1 << (digit - 1);
+            if let Some(solved) = search(&newboard, depth + 1, steps, limits) {
+                return Some(solved);
+            }
+            steps.push(SolveStep::Backtrack { cell, digit, depth: depth + 1 });
+        }
+    }
+    return None;
+}
+
+/// Solve `board` within `limits`; return the solution, if any, and
+/// the steps taken.
+pub fn solve<'a>(board: &SudokuBoard<'a>, limits: &Limits) -> (Option<SudokuBoard<'a>>, Vec<SolveStep>) {
+    let mut steps = Vec::new();
+    let solution = search(board, 0, &mut steps, limits);
+    return (solution, steps);
+}
+
+/// Return `steps` as a JSON array, naming the cells of a grid of
+/// `shape` by their row and column.
+pub fn to_json(steps: &[SolveStep], shape: Shape) -> Json {
+    let name = |cell: usize| Json::String(format!("r{}c{}", shape.row(cell) + 1, shape.col(cell) + 1));
+    let number = |n: usize| Json::Number(n as f64);
+    return Json::Array(steps.iter().map(|step| {
+        let (kind, cell, rest) = match *step {
+            SolveStep::Place { cell, digit } => ("place", cell, vec![("digit", number(digit))]),
+            SolveStep::Eliminate { cell, ref digits } => {
+                ("eliminate", cell, vec![("digits", Json::Array(digits.iter().map(|&d| number(d)).collect()))])
+            }
+            SolveStep::Guess { cell, digit, depth } => {
+                ("guess", cell, vec![("digit", number(digit)), ("depth", number(depth))])
+            }
+            SolveStep::Backtrack { cell, digit, depth } => {
+                ("backtrack", cell, vec![("digit", number(digit)), ("depth", number(depth))])
+            }
+        };
+        let mut pairs = vec![("step", Json::String(kind.to_string())), ("cell", name(cell))];
+        pairs.extend(rest);
+        return Json::object(pairs);
+    }).collect());
+}
+
+#[test]
+fn test_solve() {
+    use super::{standard_rules, INKALA, PUZZLE};
+
+    let rules = standard_rules();
+    let board = SudokuBoard::from_str(PUZZLE, &rules);
+    let (solution, steps) = solve(&board, &Limits::none());
+    assert_eq!(solution.unwrap().cells, board.solve().unwrap().cells);
+    // Singles solve it: every blank is placed, without a guess.
+    assert_eq!(steps.iter().filter(|s| matches!(s, SolveStep::Place { .. })).count(),
+               board.cells.iter().filter(|c| c.count_ones() > 1).count());
+    assert!(!steps.iter().any(|s| matches!(s, SolveStep::Guess { .. })));
+
+    // Inkala's puzzle needs guesses, some of them wrong.
+    let board = SudokuBoard::from_str(INKALA, &rules);
+    let (solution, steps) = solve(&board, &Limits::none());
+    assert_eq!(solution.unwrap().cells, board.solve().unwrap().cells);
+    assert!(steps.iter().any(|s| matches!(s, SolveStep::Backtrack { .. })));
+    let json = to_json(&steps[.. 1], Shape::STANDARD).to_string();
+    assert!(json.starts_with(r#"[{"step": "#));
+}
@@ -0,0 +1,275 @@
+// Transformations of grids that keep the rules of plain sudoku:
+// transposing, reordering the bands and stacks, the rows of a band
+// and the columns of a stack, and relabeling the digits.  Each is a
+// value that can be applied to puzzles and boards, composed with
+// others and inverted, so that puzzles need not be rewritten by hand.
+
+use super::{CandidateSet, Shape, SudokuBoard};
+#[cfg(test)]
+use super::num_to_char;
+
+/// A transformation of a grid: transposing it, if `transpose`, then
+/// taking its rows in the order of `rows` and its columns in the
+/// order of `cols`, and writing each digit `d` as `digits[d - 1]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub transpose: bool,
+    pub rows: Vec<usize>,
+    pub cols: Vec<usize>,
+    pub digits: Vec<u32>,
+}
+
+impl Transform {
+    /// Return the transformation of a grid of `shape` that changes
+    /// nothing.
+    pub fn identity(shape: Shape) -> Self {
+        let side = shape.side();
+        return Transform {
+            transpose: false,
+            rows: (0 .. side).collect(),
+            cols: (0 .. side).collect(),
+            digits: (1 ..= side as u32).collect(),
+        };
+    }
+
+    /// Return the transposition of a grid of `shape`, whose boxes
+    /// must be square.
+    pub fn transposition(shape: Shape) -> Self {
+        return Transform { transpose: true, ..Transform::identity(shape) };
+    }
+
+    /// Return the transformation swapping the bands `a` and `b`,
+    /// counted from 0, of a grid of `shape`.
+    pub fn swap_bands(shape: Shape, a: usize, b: usize) -> Self {
+        let mut transform = Transform::identity(shape);
+        swap_groups(&mut transform.rows, shape.box_rows, a, b);
+        return transform;
+    }
+
+    /// Return the transformation swapping the stacks `a` and `b`,
+    /// counted from 0, of a grid of `shape`.
+    pub fn swap_stacks(shape: Shape, a: usize, b: usize) -> Self {
+        let mut transform = Transform::identity(shape);
+        swap_groups(&mut transform.cols, shape.box_cols, a, b);
+        return transform;
+    }
+
+    /// Return the transformation taking the rows of band `band` of a
+    /// grid of `shape` in the order of `order`, counted from 0 within
+    /// the band.
+    pub fn permute_rows(shape: Shape, band: usize, order: &[usize]) -> Self {
+        let mut transform = Transform::identity(shape);
+        for (i, &row) in order.iter().enumerate() {
+            transform.rows[shape.box_rows * band + i] = shape.box_rows * band + row;
+        }
+        return transform;
+    }
+
+    /// Return the transformation taking the columns of stack `stack`
+    /// of a grid of `shape` in the order of `order`, counted from 0
+    /// within the stack.
+    pub fn permute_cols(shape: Shape, stack: usize, order: &[usize]) -> Self {
+        let mut transform = Transform::identity(shape);
+        for (i, &col) in order.iter().enumerate() {
+            transform.cols[shape.box_cols * stack + i] = shape.box_cols * stack + col;
+        }
+        return transform;
+    }
+
+    /// Return the transformation writing each digit `d` of a grid of
+    /// `shape` as `digits[d - 1]`.
+    pub fn relabel(shape: Shape, digits: Vec<u32>) -> Self {
+        return Transform { digits, ..Transform::identity(shape) };
+    }
+
+    /// Return the transformation applying this one, then `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        // Transposing after reordering reorders the other lines.
+        let (rows, cols) = if next.transpose { (&self.cols, &self.rows) } else { (&self.rows, &self.cols) };
+        return Transform {
+            transpose: self.transpose != next.transpose,
+            rows: next.rows.iter().map(|&r| rows[r]).collect(),
+            cols: next.cols.iter().map(|&c| cols[c]).collect(),
+            digits: self.digits.iter().map(|&d| next.digits[d as usize - 1]).collect(),
+        };
+    }
+
+    /// Return the transformation undoing this one.
+    pub fn inverse(&self) -> Transform {
+        let invert = |order: &[usize]| {
+            let mut inverse = vec![0; order.len()];
+            for (i, &j) in order.iter().enumerate() {
+                inverse[j] = i;
+            }
+            return inverse;
+        };
+        let (rows, cols) = if self.transpose { (&self.cols, &self.rows) } else { (&self.rows, &self.cols) };
+        let mut digits = vec![0; self.digits.len()];
+        for (d, &e) in self.digits.iter().enumerate() {
+            digits[e as usize - 1] = d as u32 + 1;
+        }
+        return Transform { transpose: self.transpose, rows: invert(rows), cols: invert(cols), digits };
+    }
+
+    /// Describe the transformation, counting rows, columns and digits
+    /// from 1, e.g., "rows 4 5 6 1 2 3 7 8 9, columns 1 2 3 4 5 6 7 8
+    /// 9, digits 2 1 3 4 5 6 7 8 9", after "transposed, " if it is.
+    pub fn text(&self) -> String {
+        let list = |values: &mut dyn Iterator<Item = usize>| values.map(|v| v.to_string()).collect::<Vec<_>>().join(" ");
+        return format!("{}rows {}, columns {}, digits {}", if self.transpose { "transposed, " } else { "" },
+                       list(&mut self.rows.iter().map(|r| r + 1)), list(&mut self.cols.iter().map(|c| c + 1)),
+                       list(&mut self.digits.iter().map(|&d| d as usize)));
+    }
+
+    /// Return the cell of the grid, before the transformation, that
+    /// goes to `cell`.
+    fn source(&self, cell: usize) -> usize {
+        let side = self.rows.len();
+        let (row, col) = (self.rows[cell / side], self.cols[cell % side]);
+        return if self.transpose { side * col + row } else { side * row + col };
+    }
+
+    /// Return the puzzle `digits`, transformed.
+    #[cfg(test)]
+    pub fn apply(&self, digits: &str) -> String {
+        let chars: Vec<char> = digits.chars().collect();
+        return (0 .. chars.len()).map(|cell| match chars[self.source(cell)].to_digit(36) {
+            Some(d) if d > 0 => num_to_char(self.digits[d as usize - 1]),
+            _ => '.',
+        }).collect();
+    }
+
+    /// Return `board`, transformed, its candidates with it.
+    pub fn apply_board<'a>(&self, board: &SudokuBoard<'a>) -> SudokuBoard<'a> {
+        let relabel = |set: CandidateSet| (0 .. self.digits.len())
+            .filter(|d| set & (1 << d) != 0)
+            .fold(0, |relabeled, d| relabeled | 1 << (self.digits[d] - 1));
+        let mut transformed = board.clone();
+        for cell in 0 .. board.cells.len() {
+            transformed.cells[cell] = relabel(board.cells[self.source(cell)]);
+        }
+        return transformed;
+    }
+}
+
+/// Return the transformation of a grid of `shape` given by the
+/// options of the `transform` command in `args`, each applied after
+/// those before it, or why they are invalid.  Bands, stacks, rows,
+/// columns and digits count from 1.
+pub fn parse<I: Iterator<Item = String>>(shape: Shape, args: &mut I) -> Result<Transform, String> {
+    let (bands, stacks) = (shape.box_cols, shape.box_rows);
+    let number = |text: &str, count: usize| match text.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= count => Ok(n - 1),
+        _ => Err(format!("invalid number {:?}", text)),
+    };
+    // Two groups to swap, e.g., "1,3".
+    let pair = |text: &str, count: usize| -> Result<(usize, usize), String> {
+        let (a, b) = text.split_once(',').ok_or_else(|| format!("expected two numbers, got {:?}", text))?;
+        return Ok((number(a, count)?, number(b, count)?));
+    };
+    // A group and the order of its lines, e.g., "2:3,1,2".
+    let order = |text: &str, count: usize, size: usize| -> Result<(usize, Vec<usize>), String> {
+        let (group, lines) = text.split_once(':').ok_or_else(|| format!("expected a group and an order, got {:?}", text))?;
+        let lines = lines.split(',').map(|line| number(line, size)).collect::<Result<Vec<_>, _>>()?;
+        let mut sorted = lines.clone();
+        sorted.sort();
+        if sorted != (0 .. size).collect::<Vec<_>>() {
+            return Err(format!("invalid order {:?}", text));
+        }
+        return Ok((number(group, count)?, lines));
+    };
+
+    let mut transform = Transform::identity(shape);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        let step = match arg.as_str() {
+            "--transpose" if shape.box_rows != shape.box_cols => {
+                return Err("only grids with square boxes can be transposed".to_string());
+            }
+            "--transpose" => Transform::transposition(shape),
+            "--swap-bands" => {
+                let (a, b) = pair(&value()?, bands)?;
+                Transform::swap_bands(shape, a, b)
+            }
+            "--swap-stacks" => {
+                let (a, b) = pair(&value()?, stacks)?;
+                Transform::swap_stacks(shape, a, b)
+            }
+            "--rows" => {
+                let (band, lines) = order(&value()?, bands, shape.box_rows)?;
+                Transform::permute_rows(shape, band, &lines)
+            }
+            "--cols" => {
+                let (stack, lines) = order(&value()?, stacks, shape.box_cols)?;
+                Transform::permute_cols(shape, stack, &lines)
+            }
+            "--relabel" => {
+                let text = value()?;
+                let digits: Vec<u32> = text.chars().map(|c| c.to_digit(36).unwrap_or(0)).collect();
+                let mut sorted = digits.clone();
+                sorted.sort();
+                if sorted != (1 ..= shape.side() as u32).collect::<Vec<_>>() {
+                    return Err(format!("invalid relabeling {:?}", text));
+                }
+                Transform::relabel(shape, digits)
+            }
+            "--invert" => {
+                transform = transform.inverse();
+                continue;
+            }
+            _ => { return Err(format!("unknown transformation {:?}", arg)); }
+        };
+        transform = transform.then(&step);
+    }
+    return Ok(transform);
+}
+
+/// Swap the groups `a` and `b` of `size` lines of `order`.
+fn swap_groups(order: &mut [usize], size: usize, a: usize, b: usize) {
+    for i in 0 .. size {
+        order.swap(size * a + i, size * b + i);
+    }
+}
+
+#[test]
+fn test_transform() {
+    use super::{Rules, Variant, PUZZLE};
+
+    let shape = Shape::STANDARD;
+    let puzzle = PUZZLE;
+    let transform = Transform::swap_bands(shape, 0, 2)
+        .then(&Transform::transposition(shape))
+        .then(&Transform::permute_rows(shape, 1, &[2, 0, 1]))
+        .then(&Transform::swap_stacks(shape, 0, 1))
+        .then(&Transform::permute_cols(shape, 2, &[1, 2, 0]))
+        .then(&Transform::relabel(shape, vec![2, 3, 1, 4, 5, 6, 7, 8, 9]));
+    let transformed = transform.apply(puzzle);
+    assert!(transformed != puzzle);
+    assert_eq!(transform.inverse().apply(&transformed), puzzle);
+    assert_eq!(transform.then(&transform.inverse()), Transform::identity(shape));
+    assert_eq!(transform.inverse().then(&transform), Transform::identity(shape));
+    // The first row of the transposed grid is the first column, from
+    // the last band.
+    assert_eq!(&Transform::swap_bands(shape, 0, 2).then(&Transform::transposition(shape)).apply(puzzle)[.. 3], ".8.");
+
+    // Transformed puzzles keep their solutions, transformed.
+    let rules = Rules::for_puzzle(shape, Variant::Standard, &[], &[]).unwrap();
+    let board = SudokuBoard::from_str(puzzle, &rules);
+    let solution = transform.apply_board(&board).solve().unwrap();
+    assert_eq!(solution.cells, transform.apply_board(&board.solve().unwrap()).cells);
+}
+
+#[test]
+fn test_parse() {
+    let shape = Shape::STANDARD;
+    let args = ["--swap-bands", "1,3", "--transpose", "--rows", "2:3,1,2", "--relabel", "231456789"];
+    let transform = parse(shape, &mut args.iter().map(|arg| arg.to_string())).unwrap();
+    assert_eq!(transform, Transform::swap_bands(shape, 0, 2)
+               .then(&Transform::transposition(shape))
+               .then(&Transform::permute_rows(shape, 1, &[2, 0, 1]))
+               .then(&Transform::relabel(shape, vec![2, 3, 1, 4, 5, 6, 7, 8, 9])));
+    let inverse = parse(shape, &mut args.iter().map(|arg| arg.to_string()).chain(Some("--invert".to_string())));
+    assert_eq!(inverse.unwrap(), transform.inverse());
+    assert!(parse(shape, &mut ["--rows", "2:1,1,2"].iter().map(|arg| arg.to_string())).is_err());
+    assert!(parse(shape, &mut ["--swap-stacks", "1,4"].iter().map(|arg| arg.to_string())).is_err());
+}
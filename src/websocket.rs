@@ -0,0 +1,217 @@
+// WebSocket connections (RFC 6455) for the server: after the
+// handshake, the text messages are answered by a few threads of the
+// connection, so that the answers go back as soon as they are ready,
+// in any order.  The messages past those being answered wait to be
+// read, so that a client cannot start more threads than that.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// The GUID that the handshake appends to the client's key.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest message accepted.
+const MAX_MESSAGE: u64 = 1 << 16;
+
+/// Time a connection may stay without a message before it is closed.
+const IDLE: Duration = Duration::from_secs(300);
+
+/// Number of messages of a connection answered at once.
+const WORKERS: usize = 4;
+
+const TEXT: u8 = 0x1;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Return the SHA-1 digest of `data`.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(8 * data.len() as u64).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in 0 .. 16 {
+            w[i] = u32::from_be_bytes([block[4 * i], block[4 * i + 1], block[4 * i + 2], block[4 * i + 3]]);
+        }
+        for i in 16 .. 80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5A82_7999),
+                1 => (b ^ c ^ d, 0x6ED9_EBA1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let t = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e].iter()) {
+            *h = h.wrapping_add(*x);
+        }
+    }
+    let mut digest = [0; 20];
+    for (i, word) in h.iter().enumerate() {
+        digest[4 * i .. 4 * i + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    return digest;
+}
+
+/// Return `data` in base 64.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0 .. 4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    return text;
+}
+
+/// Return the accept key of the handshake for the client's `key`.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), GUID).as_bytes()))
+}
+
+/// Read a frame from `input`: return its opcode and its payload.
+fn read_frame<R: Read>(input: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    input.read_exact(&mut head)?;
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    if head[0] & 0x80 == 0 {
+        return Err(invalid("fragmented messages are not supported"));
+    }
+    let length = match head[1] & 0x7F {
+        126 => {
+            let mut bytes = [0; 2];
+            input.read_exact(&mut bytes)?;
+            u16::from_be_bytes(bytes) as u64
+        }
+        127 => {
+            let mut bytes = [0; 8];
+            input.read_exact(&mut bytes)?;
+            u64::from_be_bytes(bytes)
+        }
+        length => length as u64,
+    };
+    if length > MAX_MESSAGE {
+        return Err(invalid("message too large"));
+    }
+    let mut mask = [0; 4];
+    if head[1] & 0x80 != 0 {
+        input.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; length as usize];
+    input.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    return Ok((head[0] & 0x0F, payload));
+}
+
+/// Write a frame with `opcode` and `payload` to `output`.
+fn write_frame<W: Write>(output: &mut W, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => { frame.push(length as u8); }
+        length if length <= 0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    return output.write_all(&frame);
+}
+
+/// Complete the handshake of the client's `key` on `stream`, then
+/// answer every text message with `answer` until the client closes
+/// the connection.
+pub fn serve(mut stream: TcpStream, key: &str, answer: &(dyn Fn(&str) -> String + Sync)) {
+    let handshake = format!("HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                             Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n", accept_key(key));
+    if stream.write_all(handshake.as_bytes()).is_err() {
+        return;
+    }
+    // Messages can be far apart, but not forever.
+    let _ = stream.set_read_timeout(Some(IDLE));
+    let output = match stream.try_clone() {
+        Ok(output) => Mutex::new(output),
+        Err(_) => { return; }
+    };
+    let reply = |opcode: u8, payload: &[u8]| {
+        let _ = write_frame(&mut *output.lock().unwrap(), opcode, payload);
+    };
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(WORKERS);
+    let receiver = Mutex::new(receiver);
+    thread::scope(|scope| {
+        for _ in 0 .. WORKERS {
+            let (receiver, reply) = (&receiver, &reply);
+            scope.spawn(move || {
+                loop {
+                    // The lock is released as soon as a message is taken.
+                    let taken = receiver.lock().unwrap().recv();
+                    match taken {
+                        Ok(payload) => reply(TEXT, answer(&String::from_utf8_lossy(&payload)).as_bytes()),
+                        Err(_) => { return; }
+                    }
+                }
+            });
+        }
+        while let Ok((opcode, payload)) = read_frame(&mut stream) {
+            match opcode {
+                // The workers take messages until the sender is dropped.
+                TEXT => { let _ = sender.send(payload); }
+                PING => { reply(PONG, &payload); }
+                CLOSE => {
+                    reply(CLOSE, &[]);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        drop(sender);
+    });
+}
+
+#[test]
+fn test_handshake() {
+    // The example of RFC 6455.
+    assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    assert_eq!(base64(b"ab"), "YWI=");
+}
+
+#[test]
+fn test_frames() {
+    use std::io::Cursor;
+
+    let mut output = Vec::new();
+    write_frame(&mut output, TEXT, b"hello").unwrap();
+    assert_eq!(read_frame(&mut Cursor::new(output)).unwrap(), (TEXT, b"hello".to_vec()));
+    // A masked frame from a client.
+    let masked = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+    assert_eq!(read_frame(&mut Cursor::new(masked)).unwrap(), (TEXT, b"Hello".to_vec()));
+}